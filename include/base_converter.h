@@ -0,0 +1,36 @@
+/* C interface of base_converter, built with `cargo build --release --features ffi`.
+ * Link with target/release/libbase_converter.so (or .a/.dll).
+ *
+ * Bases are named "hex", "dec", "bin" or "oct". Text results are written like
+ * snprintf: at most len - 1 bytes and a terminating nul, and the full length
+ * is returned. Errors are returned as negative BC_ERR_* codes.
+ */
+
+#ifndef BASE_CONVERTER_H
+#define BASE_CONVERTER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define BC_ERR_INVALID -1
+#define BC_ERR_USAGE -2
+#define BC_ERR_OVERFLOW -3
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Read input in base into *out. Return 0 on success. */
+int bc_parse(const char *input, const char *base, uint64_t *out);
+
+/* Format value in base, padded to width bits (0 for no padding). */
+int bc_format(uint64_t value, const char *base, uint32_t width, char *buf, size_t len);
+
+/* Convert input, a value or a function call like "htonl(1)", from one base to another. */
+int bc_convert(const char *input, const char *from, const char *to, uint32_t width, char *buf, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BASE_CONVERTER_H */
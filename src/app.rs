@@ -1,230 +1,1125 @@
 //! Main module of the program.
 
-use std::io::{stdin, stdout, Write};
-use std::fmt;
-use std::error::Error;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::cell::RefCell;
+use std::io::{self, stderr, stdout, BufRead, Write};
+use std::path::Path;
+use std::hint::black_box;
+use std::rc::Rc;
+use std::time::Instant;
+use rustyline::{Config, Editor};
+use rustyline::history::DefaultHistory;
+use rustyline::config::Configurer;
+use rustyline::error::ReadlineError;
+use crate::base::Base;
+use crate::clipboard;
+use crate::command::{self, split_quoted, Command};
+use crate::completion::CommandHelper;
+use crate::plugin::{Plugin, Registry};
+use crate::func;
+use crate::list;
+use crate::random::Rng;
+use crate::seq;
+use crate::history;
+use crate::render;
+use crate::pager;
+use crate::batch;
+use crate::cancel;
+use crate::config;
+use crate::converter;
+use crate::dump;
+use crate::enums;
+use crate::exit;
+use crate::export;
+use crate::field::{self, Field};
+use crate::error::{CommandError, ConvertError};
+use crate::conversion::{Conversion, OutputFormat};
+use crate::settings::{self, Settings};
+use crate::theme::Theme;
+use crate::token;
+use crate::transcript::{self, Transcript};
+use crate::units;
+#[cfg(feature = "tui")]
+use crate::tui;
 
 pub const START_CMD: &str = ":";
+/// Number of previous settings kept for `:undo`.
+const UNDO_DEPTH: usize = 32;
+/// Name of the last result in inputs.
+const ANS: &str = "ans";
+/// Help of the built-in commands. Feature commands and functions follow.
 const HELP_MSG: &str = "
     -- Base Converter -- <Author: Nguyen Duc Toan>
 Usage:
     :from <base> to <base>      change input base and output base
     :from <base>                change input base
     :to <base>                  change output base
-<base> can be \"hex\", \"dec\", \"bin\"
+<base> can be \"hex\", \"dec\", \"bin\", \"oct\", \"size\" (byte sizes, e.g. 256 KiB)
+    :width <bits>               pad output to <bits> bits, 0 for no padding
+    :group <n>                  group binary digits by <n>, 0 for no grouping
+    :case lower|upper           case of hex digits
+    :format <format>            print results as text, json, csv or tsv
+    :style <style>              number syntax: plain, c (0b/0x), verilog (16'h) or vhdl (x\"\")
+    :color on|off|auto          color output (auto: only on a terminal without NO_COLOR)
+    :set <key> <value>          change any of the settings above, e.g. :set width 16
+    :show                       print all settings
+    :pager on|off               page output taller than the terminal ($PAGER or built-in)
+    :quiet on|off               print only converted values, without <base> tag
+    :fullscale q<n>|<value>     value of 100% for pct, frac and ratio, q15 for Q1.15 (2^15)
+    :tickrate <frequency>       tick rate for ticks and duration, e.g. 1kHz or 32768
+    :kilo 1024|1000             value of k in the shorthand 4k, 2M or 1G, 1024 by default
+    :float on|off               read float literals as their bits, 3.14f as f32 and 2.5e3 as f64
+    :others on|off              pad vhdl literals with an aggregate: (15 downto 8 => '0') & x\"be\"
+    :round <mode>               rounding of function results: nearest, floor, ceil or truncate
+    :prompt \"<template>\"        prompt, {in}, {out}, {width}... are replaced by settings
+    :undo                       revert the last settings change
+    :reset                      restore default settings
+    :config save                save settings, aliases, bookmarks and fields as defaults
+    :config load                load the saved settings, aliases, bookmarks and fields
+    :save <path>                save settings, aliases, bookmarks and fields to workspace <path>
+    :load <path>                restore settings, aliases, bookmarks and fields of workspace <path>
+    :alias                      list aliases
+    :alias <name> \"<command>\"   define <name> as a shortcut for <command>
+    :bookmark <name>            save the last result as <name>, usable as a value
+    :bookmarks                  list bookmarks in hex, dec and bin
+    :enum [<C enum>]            bookmark the members of a C enum, of the clipboard without one
+    :whois <value>              names of the bookmarks of a value, e.g. of enum members
+    :deffield <name>[<hi>:<lo>] define register fields, e.g. :deffield mode[7:4] en[3]
+    :deffield                   list fields with their values in the last result
+    :deffield clear             remove all fields
+    :copy [<base>] [nosep]      copy the last result to the clipboard, nosep drops _ separators
+    :paste                      convert the numbers found in the clipboard
+    :rand [<bits>] [<n>]        n random values of <bits> bits, by default the width setting
+    :seq <a> <b> [<step>]       values from a to b, add --index for an index column
+    :combos width=<w> bits=<n>  values of w bits with exactly n bits set, e.g. flag combinations
+    :alignreport <n> [<list>]   flag the values of the list, or the clipboard, not aligned to n
+    :dups [--mask <m>] [<list>] list the duplicated values, or the ones equal once masked by m
+    :sort [<options>] [<list>]  sort the values, options --unique and --reverse
+    :agg [<list>]               count, min, max, sum, mean and range of the values
+    :bitfreq [<list>]           how often each bit is set across the values, and stuck bits
+    :bench <input> [<n>]        time n conversions of a value, expression or \":<decoder> ...\"
+    :log <path>                 append inputs and outputs with timestamps to file <path>
+    :log off                    stop logging
+    :tui                        open the full-screen interface
+    :batch <in> [<out>]         convert every line of file <in>, into file <out> if given
+    :history                    print input history
+    :history size <n>           keep at most <n> history entries
+    :export <path>              write the session's conversions to .md, .csv, .tsv or .json
+    !! or !<n>                  run the last input, or input <n> of :history, with current settings
+    ans                         the last result, usable as a value, e.g. htons(ans)
     :h or :help                 print help message
     :q or :quit                 stop program
+    :<cmd>; <cmd>; ...          run several commands in order
+";
+/// Default number of iterations of `:bench`.
+const BENCH_ITERATIONS: u64 = 100_000;
+
+/// Column of the descriptions in the help.
+const HELP_COLUMN: usize = 32;
+const FUNCTIONS_HELP: &str = "Functions:
+    htons(x), ntohs(x)          swap bytes of a 16-bit value
+    htonl(x), ntohl(x)          swap bytes of a 32-bit value
+    size(s)                     byte size s in bytes, e.g. size(1.5MiB)
+    bytes(x)                    x as a byte size, e.g. bytes(0x180000) is 1.5 MiB
+    freq(t)                     frequency in Hz of period t (ns, us, ms, s; ns if no unit)
+    period(f)                   period in ns of frequency f (Hz, kHz, MHz, GHz; Hz if no unit)
+    pct(p), frac(f)             percentage p or fraction f (0.75, 3/4) of :fullscale
+    ratio(x)                    x as a fraction and percentage of :fullscale
+    ratio(x, maxden=n)          closest fraction to x, e.g. 0.3072, with a denominator up to n
+    ticks(d), duration(x)       ticks at :tickrate in duration d (1h 2m 3.45s), and back
+    onehot(n)                   value with only bit n set, e.g. onehot(5) is 0b10_0000
+    decode_onehot(x)            number of the single bit set in x
+    roundto(x, n)               nearest multiple of n, floorto and ceilto round down and up
+    gcd(a, b), lcm(a, b)        greatest common divisor and least common multiple
+    isprime(x), factor(x)       1 if x is prime, else 0, and the prime factors of x
+    bits(x), frombits(n, ...)   positions of the bits set in x, e.g. bits(0xb6) is 1,2,4,5,7, and back
+    ones(x, n)                  n-bit ones' complement x, e.g. ones(0xfff0, 16) is -15
+    bias(x, n), unbias(x, n)    excess-n encoding of x, e.g. bias(-3, 127) is 124, and back
 
 ";
 
-/// Enum for base types.
-#[derive(PartialEq)]
-enum Base {
-    Bin,
-    Dec,
-    Hex,
+/// Output kept in memory and shared, e.g. between a test and its App.
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
-impl fmt::Display for Base {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Base::Bin => write!(f, "bin"),
-            Base::Hex => write!(f, "hex"),
-            Base::Dec => write!(f, "dec"),
-        }
+impl Output {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
     }
 }
 
-impl Base {
-    /// Read a string and convert it to u64 based on base type.
-    /// # Example:
-    /// ```
-    /// assert_eq!(Base::Bin.to_num("0b10").ok(), Some(3));
-    /// assert_eq!(Base::Bin.to_num("0001_0000").ok(), Some(16));
-    /// assert_eq!(Base::Hex.to_num("0xff").ok(), Some(255));
-    /// ```
-    pub fn to_num(&self, input: &str) -> Result<u64, Box<dyn Error>> {
-    	let mut input = input.to_owned();
-        if input.ends_with('u') {
-            input.pop();
-        } else if input.ends_with("uL") {
-            input.pop();
-            input.pop();
-        }
-        match self {
-            Base::Bin => {
-                let input = input.trim().to_lowercase().replace("_", "");
-                if let Some(input) = input.strip_prefix("0b") {
-                    let ret = u64::from_str_radix(input, 2)?;
-                    return Ok(ret);
-                } else {
-                    let ret = u64::from_str_radix(&input, 2)?;
-                    return Ok(ret);
-                }
-            },
-            Base::Dec => {
-                let ret = u64::from_str_radix(&input, 10)?;
-                return Ok(ret);
-            }
-            Base::Hex => {
-                let input = input.trim().to_lowercase();
-                if let Some(input) = input.strip_prefix("0x") {
-                    let ret = u64::from_str_radix(input, 16)?;
-                    return Ok(ret);
-                } else {
-                    let ret = u64::from_str_radix(&input, 16)?;
-                    return Ok(ret);
-                }
+/// The settings and names (bookmarks and `ans`) of an `App`, to convert
+/// from other threads, e.g. in batch conversions.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    settings: Settings,
+    names: BTreeMap<String, u64>,
+}
+
+impl Snapshot {
+    /// Convert an input like `App::convert`.
+    pub fn convert(&self, input: &str) -> Result<Conversion, ConvertError> {
+        converter::convert_with(input, &self.settings, &|name| self.names.get(name).copied())
+    }
+
+    /// Render a conversion result like `App::render`.
+    pub fn render(&self, conversion: &Conversion, decorated: bool) -> String {
+        render_with(conversion, decorated, &self.settings)
+    }
+
+    /// Convert an input and append it rendered like `render` undecorated to
+    /// `out`. In text format, values other than function calls are written
+    /// without allocating.
+    pub fn render_into(&self, input: &str, out: &mut String) -> Result<(), ConvertError> {
+        match self.settings.format {
+            OutputFormat::Text if !(self.settings.quiet && func::is_call(input)) => {
+                converter::convert_into(input, &self.settings, &|name| self.names.get(name).copied(), out).map(|_| ())
+            }
+            _ => {
+                out.push_str(&self.render(&self.convert(input)?, false));
+                Ok(())
             }
         }
     }
+}
 
-    /// Format an u64 number based on base type. Return the formated `String`.
-    /// # Example:
-    /// ```
-    /// assert_eq!(Base::Bin.from(4), "100");
-    /// assert_eq!(Base::Bin.from(16), "0001_0000");
-    /// assert_eq!(Base::Hex.from(255), "0xff");
-    /// ```
-    pub fn from(&self, mut num: u64) -> String {
-        match self {
-            Base::Hex => format!("0x{:x}", num),
-            Base::Dec => format!("{}", num),
-            Base::Bin => {
-                if num < 16 {
-                    format!("{:b}", num)
-                } else {
-                    let mut ret = Vec::new();
-                    while num > 0 {
-                        let four_bits = num & 0b1111;
-                        num = num >> 4;
-                        ret.push(format!("{:04b}", four_bits));
-                    }
-                    ret.into_iter().rev().collect::<Vec<String>>().join("_")
-                }
-            }
+/// Whether a bookmark can be named `name`: names that read as hex numbers
+/// would hide them.
+fn valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && Base::Hex.to_num(name).is_err()
+}
+
+/// Render a conversion result in the output format of `settings`, see
+/// `App::render`.
+fn render_with(conversion: &Conversion, decorated: bool, settings: &Settings) -> String {
+    match settings.format {
+        OutputFormat::Text if settings.quiet => conversion.raw(settings),
+        OutputFormat::Text if decorated => {
+            render::output(&settings.out_base.to_string(), &conversion.text, settings.color.enabled())
         }
+        OutputFormat::Text => conversion.text.clone(),
+        OutputFormat::Json => conversion.to_json(settings),
+        OutputFormat::Csv | OutputFormat::Tsv => conversion.to_row(&settings.format, settings),
     }
 }
 
 /// Main struct that manage the workflow of the aplication.
 pub struct App {
-    in_base: Base,
-    out_base: Base,
+    settings: Settings,
+    /// Previous settings, the most recent last.
+    undo: Vec<Settings>,
+    /// Command aliases, e.g. `fd` for `from dec`.
+    aliases: BTreeMap<String, String>,
+    /// Values saved with `:bookmark`, usable in place of numbers.
+    bookmarks: BTreeMap<String, u64>,
+    /// Register fields defined with `:deffield`, the most significant first.
+    fields: Vec<Field>,
+    /// Colors of the TUI.
+    theme: Theme,
+    /// Last conversion.
+    last: Option<Conversion>,
+    /// Inputs of the session, after those of previous sessions.
+    history: Vec<history::Entry>,
+    /// Set by `:q`.
+    done: bool,
+    /// Feature commands, e.g. `:can`.
+    plugins: Registry,
+    editor: Editor<CommandHelper, DefaultHistory>,
+    /// Where results and messages are written.
+    out: RefCell<Box<dyn Write>>,
+    /// Where errors are written.
+    err: RefCell<Box<dyn Write>>,
+    /// Output goes to the terminal, so long texts can be paged.
+    terminal: bool,
+    /// Session log started by `:log`.
+    transcript: RefCell<Option<Transcript>>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
-    /// Creat a new instance of App.
+    /// Creat a new instance of App, writing to stdout and stderr.
     pub fn new() -> Self {
+        let mut app = App::with_output(Box::new(stdout()), Box::new(stderr()));
+        app.terminal = true;
+        app
+    }
+
+    /// Create an App writing results to `out` and errors to `err`, e.g. to
+    /// drive a session from tests.
+    pub fn with_output(out: Box<dyn Write>, err: Box<dyn Write>) -> Self {
+        let plugins = Registry::new();
+        let mut editor = Editor::with_config(Config::builder().max_history_size(history::DEFAULT_SIZE).unwrap().bracketed_paste(true).build())
+            .expect("Fail creating line editor");
+        editor.set_helper(Some(CommandHelper::new(&plugins)));
         Self {
-            in_base: Base::Hex,
-            out_base: Base::Bin,
+            settings: Settings::default(),
+            undo: Vec::new(),
+            aliases: BTreeMap::new(),
+            bookmarks: BTreeMap::new(),
+            fields: Vec::new(),
+            theme: Theme::default(),
+            last: None,
+            history: Vec::new(),
+            done: false,
+            plugins,
+            editor,
+            out: RefCell::new(out),
+            err: RefCell::new(err),
+            terminal: false,
+            transcript: RefCell::new(None),
         }
     }
 
-    /// Read stdin for user input.
-    pub fn get_input(&self) -> String {
-        print!("<{}>$ ", self.in_base);
-        stdout().flush().expect("Fail flushing stdout");
-        let mut input = String::new();
-        stdin().read_line(&mut input).expect("Fail reading input");
-        return input.trim().to_string()
+    /// Add a feature command, e.g. from a library user.
+    pub fn register(&mut self, plugin: Plugin) {
+        self.plugins.register(plugin);
+        self.editor.set_helper(Some(CommandHelper::new(&self.plugins)));
     }
 
-    /// Print output to stdout.
-    pub fn print(&self, out: &str) {
-        println!("<{}> {}", self.out_base, out);
+    /// Read a line of user input with line editing and history. Ctrl+C
+    /// discards the line, Ctrl+D (end of input) is read as the quit command.
+    /// A paste of several lines is read at once, as lines separated by `\n`
+    /// for `handle_paste`.
+    pub fn get_input(&mut self) -> String {
+        let prompt = render::prompt(&self.settings.prompt, &self.settings);
+        match self.editor.readline(&prompt) {
+            Ok(input) => input.trim().to_string(),
+            Err(ReadlineError::Interrupted) => String::new(),
+            Err(ReadlineError::Eof) => format!("{}q", START_CMD),
+            Err(e) => panic!("Fail reading input: {}", e),
+        }
+    }
+
+    /// Run a line of input: a command, or a value to convert, after history
+    /// expansion of `!!` or `!n`. A trailing `//` or `#` comment is ignored.
+    /// The input is added to the history, and the result or error is
+    /// printed. Return the exit code of a failure, 0 on success.
+    pub fn handle(&mut self, input: &str) -> i32 {
+        let input = token::strip_comment(input.trim());
+        if input.is_empty() {
+            return 0;
+        }
+        let input = match history::expand(input, &self.history) {
+            Ok(Some(expanded)) => {
+                if self.terminal {
+                    self.print_line(&expanded);
+                }
+                expanded
+            }
+            Ok(None) => input.to_string(),
+            Err(e) => {
+                self.log(transcript::INPUT, input);
+                self.print_error(&format!("Error: {}", e));
+                return exit::USAGE;
+            }
+        };
+        let input = input.as_str();
+        self.log(transcript::INPUT, input);
+        self.remember(input);
+        if self.is_command(input) {
+            if let Err(e) = self.execute(input) {
+                self.print_error(&format!("Error: {}", e));
+                return exit::USAGE;
+            }
+        } else {
+            match self.convert_line(input) {
+                Ok(conversion) => {
+                    self.print(&conversion);
+                    if let Some(entry) = self.history.last_mut() {
+                        entry.conversion = Some(conversion.clone());
+                    }
+                    self.last = Some(conversion);
+                }
+                Err(e) => {
+                    self.print_error(&format!("Error: {}", e));
+                    return exit::code(&e);
+                }
+            }
+        }
+        0
+    }
+
+    /// Lines of a paste, where a command with a `{` left open, e.g. a
+    /// multi-line `:enum`, is joined with the next lines up to its `}`.
+    fn paste_lines(&self, input: &str) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut open = 0;
+        for line in input.lines().map(|line| token::strip_comment(line.trim())).filter(|line| !line.is_empty()) {
+            match lines.last_mut() {
+                Some(last) if open > 0 => {
+                    last.push(' ');
+                    last.push_str(line);
+                }
+                _ if self.is_command(line) => lines.push(line.to_string()),
+                _ => {
+                    lines.push(line.to_string());
+                    continue;
+                }
+            }
+            open += line.matches('{').count() as isize - line.matches('}').count() as isize;
+        }
+        lines
+    }
+
+    /// Run an input of the prompt, where a paste can bring several lines:
+    /// each line, value or command, is run like `handle` until `:q` or
+    /// Ctrl+C, then a summary of the lines converted and failed is printed.
+    /// Return the exit code of the first failure, 0 if none.
+    pub fn handle_paste(&mut self, input: &str) -> i32 {
+        if input.lines().filter(|line| !token::strip_comment(line.trim()).is_empty()).count() <= 1 {
+            return self.handle(input);
+        }
+        let lines = self.paste_lines(input);
+        if let [line] = &lines[..] {
+            return self.handle(line);
+        }
+        let (mut converted, mut commands, mut failed, mut code) = (0, 0, 0, 0);
+        for line in &lines {
+            if self.done || cancel::cancelled() {
+                break;
+            }
+            let command = self.is_command(line);
+            let line_code = self.handle(line);
+            match (line_code, command) {
+                (0, true) => commands += 1,
+                (0, false) => converted += 1,
+                _ => failed += 1,
+            }
+            if code == 0 {
+                code = line_code;
+            }
+        }
+        let mut summary = format!("{} lines: {} converted, {} failed", lines.len(), converted, failed);
+        if commands > 0 {
+            summary += &format!(", {} command{}", commands, if commands == 1 { "" } else { "s" });
+        }
+        let skipped = lines.len() - converted - commands - failed;
+        if skipped > 0 {
+            summary += &format!(", {} not run", skipped);
+        }
+        self.print_line(&summary);
+        code
+    }
+
+    /// Run every line of `input` like the prompt does, without line editing,
+    /// until its end or `:q`. Return the exit code of the first failure, 0 if
+    /// none.
+    pub fn session(&mut self, input: impl BufRead) -> i32 {
+        let mut code = 0;
+        self.print_header();
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    self.print_error(&format!("Error: {}", e));
+                    return exit::IO;
+                }
+            };
+            let line_code = self.handle(&line);
+            if code == 0 {
+                code = line_code;
+            }
+            if self.done {
+                break;
+            }
+        }
+        code
+    }
+
+    /// Print a conversion result.
+    pub fn print(&self, conversion: &Conversion) {
+        self.print_line(&self.render(conversion, true));
+    }
+
+    /// Print the header line of the output format, if it has one.
+    pub fn print_header(&self) {
+        if let Some(header) = self.header() {
+            self.print_line(&header);
+        }
+    }
+
+    /// Write a line to the output.
+    fn print_line(&self, line: &str) {
+        let _ = writeln!(self.out.borrow_mut(), "{}", line);
+        self.log(transcript::OUTPUT, line);
+    }
+
+    /// Append a text to the session log, if logging.
+    fn log(&self, marker: char, text: &str) {
+        if let Some(transcript) = self.transcript.borrow_mut().as_mut() {
+            transcript.write(marker, text);
+        }
+    }
+
+    /// Render a conversion result in the output format. In text format, the
+    /// result is `decorated` with its base for display, e.g. `<bin> 0001_0000`,
+    /// unless quiet.
+    pub fn render(&self, conversion: &Conversion, decorated: bool) -> String {
+        render_with(conversion, decorated, &self.settings)
+    }
+
+    /// Copy of the settings and names, to convert from other threads.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut names = self.bookmarks.clone();
+        if let Some(last) = &self.last {
+            names.entry(ANS.to_string()).or_insert(last.value);
+        }
+        Snapshot { settings: self.settings.clone(), names }
+    }
+
+    /// Header line to print before a list of results, if the output format
+    /// has one.
+    pub fn header(&self) -> Option<String> {
+        self.settings.format.header()
+    }
+
+    /// Print a text of possibly many lines, through the pager if it is taller
+    /// than the terminal.
+    fn print_text(&self, text: &str) {
+        if self.terminal {
+            // Like `print_line`, output to a closed pipe is dropped.
+            let _ = pager::print(text, self.settings.pager, &mut **self.out.borrow_mut());
+            self.log(transcript::OUTPUT, text);
+        } else {
+            self.print_line(text.strip_suffix('\n').unwrap_or(text));
+        }
+    }
+
+    /// Print an error message.
+    pub fn print_error(&self, msg: &str) {
+        let _ = writeln!(self.err.borrow_mut(), "{}", render::error(msg, self.settings.color.enabled()));
+        self.log(transcript::ERROR, msg);
     }
 
     /// Convert an input from input base to output base. Default input base is hex
     /// and output base is bin. These bases can be changed with command `:from <base>`
-    /// and `:to <base>`
-    pub fn convert(&self, input: &str) -> Result<String, Box<dyn Error>> {
-        let num = self.in_base.to_num(input)?;
-        return Ok(self.out_base.from(num));
+    /// and `:to <base>`.
+    pub fn convert(&self, input: &str) -> Result<Conversion, ConvertError> {
+        converter::convert_with(input, &self.settings, &|name| self.lookup(name))
+    }
+
+    /// Convert an input like `convert`, or a line of a register dump such as
+    /// `CR1 = 0x00000081` that does not convert as a whole: its value is
+    /// converted, noted with the name, and saved as a bookmark of that name.
+    fn convert_line(&mut self, input: &str) -> Result<Conversion, ConvertError> {
+        let e = match self.convert(input) {
+            Ok(conversion) => return Ok(conversion),
+            Err(e) => e,
+        };
+        let (name, value) = match dump::parse(input) {
+            Some((name, value)) if valid_name(name) => (name, value),
+            _ => return Err(e),
+        };
+        let mut conversion = self.convert(value).map_err(|_| e)?;
+        conversion.text = format!("{} ({})", conversion.text, name);
+        self.bookmarks.insert(name.to_string(), conversion.value);
+        Ok(conversion)
+    }
+
+    /// Value of a bookmark, or of `ans`.
+    fn lookup(&self, name: &str) -> Option<u64> {
+        self.bookmarks.get(name).copied().or_else(|| self.last.as_ref().filter(|_| name == ANS).map(|last| last.value))
     }
 
     /// Check if user input is a command.
     pub fn is_command(&self, cmd: &str) -> bool {
-        return cmd.starts_with(START_CMD);
-    }
-
-    /// Execute most of commands, except `:q` or `:quit`, these commands are
-    /// executed from `main` function because it relates to the flow of the
-    /// program, not the configuration.
-    pub fn execute(&mut self, cmd: &str) -> Result<(), String> {
-        if let Some(mut cmd) = cmd.strip_prefix(START_CMD) {
-            cmd = cmd.trim();
-            if cmd == "h" || cmd == "help" {
-                self.help();
-                return Ok(());
-            }
-            let words: Vec<&str> = cmd.split_ascii_whitespace().collect();
-            if !(words.len() == 2 || words.len() == 4) {
-                return Err(format!("Error: wrong command format"));
-            } else {
-                self.change_base(words[0], words[1])?;
-                if words.len() == 4 {
-                    self.change_base(words[2], words[3])?;
-                }
-            }
-        } else {
-            return Err(format!("Error: wrong command format"));
+        cmd.starts_with(START_CMD)
+    }
+
+    /// Execute a command line. Several commands can be given on one line
+    /// separated by `;`, e.g. `:from dec; to hex; width 32`. They run in order
+    /// until one fails. `:q` or `:quit` marks the application as done, see
+    /// `is_done`.
+    pub fn execute(&mut self, cmd: &str) -> Result<(), CommandError> {
+        let cmd = cmd.strip_prefix(START_CMD).ok_or(CommandError::Syntax)?;
+        let segments: Vec<&str> = split_quoted(cmd, ';')
+            .into_iter()
+            .map(|seg| seg.trim())
+            .filter(|seg| !seg.is_empty())
+            .collect();
+        if segments.len() <= 1 {
+            return self.execute_one(cmd.trim().trim_end_matches(';'));
+        }
+        for (i, seg) in segments.iter().enumerate() {
+            let seg = seg.strip_prefix(START_CMD).unwrap_or(seg);
+            self.execute_one(seg).map_err(|e| CommandError::Segment {
+                index: i + 1,
+                segment: seg.to_string(),
+                error: Box::new(e),
+            })?;
         }
         Ok(())
     }
 
-    /// Change input and output base.
-    fn change_base(&mut self, cmd: &str, arg: &str) -> Result<(), String> {
-        match cmd {
-            "from" => {
-                self.in_base = match arg {
-                    "hex" => Base::Hex,
-                    "dec" => Base::Dec,
-                    "bin" => Base::Bin,
-                    _ => {
-                        return Err(format!("No type {}", arg));
-                    }
+    /// Check if `:q` was executed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Last conversion, if any.
+    pub fn last(&self) -> Option<&Conversion> {
+        self.last.as_ref()
+    }
+
+    /// Replace the last conversion, e.g. with a value edited in the TUI.
+    pub fn set_last(&mut self, conversion: Conversion) {
+        self.last = Some(conversion);
+    }
+
+    /// Register fields, the most significant first.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Inputs of the history, the oldest first.
+    pub fn history(&self) -> &[history::Entry] {
+        &self.history
+    }
+
+    /// Run `f` with the output and errors kept in memory instead of printed,
+    /// e.g. to show them in the TUI. Return its result, the output and the
+    /// errors.
+    pub fn capture<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> (R, String, String) {
+        let (out, err) = (Output::default(), Output::default());
+        let saved_out = self.out.replace(Box::new(out.clone()));
+        let saved_err = self.err.replace(Box::new(err.clone()));
+        let terminal = std::mem::replace(&mut self.terminal, false);
+        let ret = f(self);
+        self.out.replace(saved_out);
+        self.err.replace(saved_err);
+        self.terminal = terminal;
+        (ret, out.text(), err.text())
+    }
+
+    /// Current settings, after the config file, environment and flags.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Execute a single command, given without the leading `:`. An alias in
+    /// first position is replaced by its command.
+    fn execute_one(&mut self, cmd: &str) -> Result<(), CommandError> {
+        let cmd = cmd.trim();
+        let (first, rest) = cmd.split_once(char::is_whitespace).unwrap_or((cmd, ""));
+        let command = match self.aliases.get(first) {
+            Some(alias) => command::parse(&format!("{} {}", alias, rest), &self.plugins)?,
+            None => command::parse(cmd, &self.plugins)?,
+        };
+        self.run(command)
+    }
+
+    /// Run a parsed command.
+    pub fn run(&mut self, command: Command) -> Result<(), CommandError> {
+        match command {
+            Command::Help => self.help(),
+            Command::Quit => self.done = true,
+            Command::From(base) => return self.change(|app| {
+                app.settings.in_base = base;
+                Ok(())
+            }),
+            Command::To(base) => return self.change(|app| {
+                app.settings.out_base = base;
+                Ok(())
+            }),
+            Command::Set(pairs) => return self.change(|app| {
+                for (key, value) in &pairs {
+                    app.set(key, value)?;
                 }
-            },
-            "to" => {
-                self.out_base = match arg {
-                    "hex" => Base::Hex,
-                    "dec" => Base::Dec,
-                    "bin" => Base::Bin,
-                    _ => {
-                        return Err(format!("No type {}", arg));
+                Ok(())
+            }),
+            Command::Show => self.show(),
+            Command::Undo => {
+                self.settings = self.undo.pop().ok_or(CommandError::NothingToUndo)?;
+                return self.apply();
+            }
+            Command::Reset => return self.change(|app| {
+                app.settings = Settings::default();
+                app.apply()
+            }),
+            Command::History => self.print_history(),
+            Command::Export(path) => {
+                let conversions: Vec<&Conversion> = self.history.iter().filter_map(|entry| entry.conversion.as_ref()).collect();
+                export::write(Path::new(&path), &conversions, &self.settings)?;
+                self.print_line(&format!("Exported {} conversions to {}", conversions.len(), path));
+            }
+            Command::Batch { input, output } => return self.batch(&input, output.as_deref()),
+            Command::ConfigSave => {
+                let path = config::save(&self.config())?;
+                self.print_line(&format!("Saved to {}", path.display()));
+            }
+            Command::ConfigLoad => {
+                let config = config::load()?.ok_or(CommandError::NoConfig)?;
+                return self.change(|app| app.use_config(config));
+            }
+            Command::Save(path) => {
+                config::save_file(Path::new(&path), &self.config())?;
+                self.print_line(&format!("Saved to {}", path));
+            }
+            Command::Load(path) => {
+                let config = config::load_file(Path::new(&path))?;
+                return self.change(|app| app.use_config(config));
+            }
+            Command::Aliases => {
+                let text: String = self.aliases.iter()
+                    .map(|(name, command)| format!("{:10} {}\n", name, command))
+                    .collect();
+                self.print_text(&text);
+            }
+            Command::Alias { name, command } => {
+                self.aliases.insert(name, command);
+            }
+            Command::Bookmark(name) => {
+                if !valid_name(&name) {
+                    return Err(CommandError::invalid("bookmark", &name, "a name that is not a hex number"));
+                }
+                let value = self.last.as_ref().ok_or(CommandError::NoResult)?.value;
+                self.bookmarks.insert(name, value);
+            }
+            Command::Bookmarks => {
+                let text: String = self.bookmarks.iter()
+                    .map(|(name, value)| {
+                        let repr = |base: Base| base.format(*value, &self.settings);
+                        format!("{:10} {} {} {}\n", name, repr(Base::Hex), repr(Base::Dec), repr(Base::Bin))
+                    })
+                    .collect();
+                self.print_text(&text);
+            }
+            Command::Enum(code) => {
+                let code = if code.is_empty() { clipboard::paste()? } else { code };
+                let members = enums::parse(&code).map_err(CommandError::Decode)?;
+                if let Some((name, _)) = members.iter().find(|(name, _)| !valid_name(name)) {
+                    return Err(CommandError::invalid("bookmark", name, "a name that is not a hex number"));
+                }
+                let text: String = members.iter()
+                    .map(|(name, value)| format!("{:10} {}\n", name, self.settings.out_base.format(*value, &self.settings)))
+                    .collect();
+                self.bookmarks.extend(members);
+                self.print_text(&text);
+            }
+            Command::Whois(input) => {
+                let value = self.convert(&input).map_err(|e| CommandError::Decode(e.to_string()))?.value;
+                let names: Vec<&str> = self.bookmarks.iter().filter(|(_, v)| **v == value).map(|(name, _)| name.as_str()).collect();
+                if names.is_empty() {
+                    return Err(CommandError::Decode(format!("no bookmark has the value {}", input)));
+                }
+                self.print_line(&names.join("\n"));
+            }
+            Command::DefField(fields) => field::define(&mut self.fields, fields),
+            Command::Fields => {
+                let text: String = self.fields.iter()
+                    .map(|field| match &self.last {
+                        Some(last) => format!("{:16} {}\n", field.to_string(), field.format(last.value)),
+                        None => format!("{}\n", field),
+                    })
+                    .collect();
+                self.print_text(&text);
+            }
+            Command::ClearFields => self.fields.clear(),
+            Command::Copy { base, separators } => {
+                let last = self.last.as_ref().ok_or(CommandError::NoResult)?;
+                let mut text = last.repr(base.as_ref().unwrap_or(&self.settings.out_base), &self.settings);
+                if !separators {
+                    text.retain(|c| c != '_');
+                }
+                match clipboard::copy(&text) {
+                    Ok(()) => self.print_line(&format!("Copied {}", text)),
+                    // The terminal may still reach a clipboard, e.g. over SSH.
+                    Err(_) if self.terminal => {
+                        let _ = write!(self.out.borrow_mut(), "{}", clipboard::osc52(&text));
+                        self.print_line(&format!("Sent {} to the terminal clipboard", text));
                     }
+                    Err(e) => return Err(e),
                 }
             }
-            _ => {
-                return Err(format!("Error: wrong command format"));
+            Command::Paste => {
+                let text = clipboard::paste()?;
+                // Words that do not read in the input base are prose, e.g. `12bit`.
+                let conversions: Vec<Conversion> = clipboard::numbers(&text).into_iter()
+                    .filter_map(|word| self.convert(word).ok())
+                    .collect();
+                for conversion in &conversions {
+                    self.print(conversion);
+                }
+                self.last = Some(conversions.into_iter().last().ok_or(CommandError::NothingToPaste)?);
+            }
+            Command::Log(Some(path)) => {
+                let transcript = Transcript::open(&path)?;
+                self.print_line(&format!("Logging to {}", transcript.path().display()));
+                *self.transcript.borrow_mut() = Some(transcript);
+            }
+            Command::Log(None) => {
+                *self.transcript.borrow_mut() = None;
+            }
+            Command::Tui => {
+                #[cfg(feature = "tui")]
+                tui::run(self)?;
+                #[cfg(not(feature = "tui"))]
+                return Err(CommandError::Unsupported(":tui"));
+            }
+            Command::Bench { input, iterations } => {
+                let line = self.bench(&input, iterations.unwrap_or(BENCH_ITERATIONS))?;
+                self.print_line(&line);
+            }
+            Command::Rand { width, count } => {
+                // Without a width, values fill the width setting, or 64 bits.
+                let width = width.unwrap_or(if self.settings.width == 0 { 64 } else { self.settings.width });
+                let settings = Settings { width, ..self.settings.clone() };
+                let mut rng = Rng::from_clock();
+                let mut text = String::new();
+                for _ in 0..count {
+                    settings.out_base.format_into(rng.bits(width), &settings, &mut text);
+                    text.push('\n');
+                }
+                self.print_text(&text);
+            }
+            Command::Seq { start, end, step, index } => {
+                let read = |arg: &str| self.convert(arg).map(|conversion| conversion.value).map_err(|e| CommandError::Decode(e.to_string()));
+                let step = step.as_deref().map_or(Ok(1), read)?;
+                let values = seq::values(read(&start)?, read(&end)?, step).map_err(CommandError::Decode)?;
+                let format = |value| self.settings.out_base.format(value, &self.settings);
+                self.print_text(&seq::table(&values, index, &format));
+            }
+            Command::Combos { width, bits } => {
+                let values = seq::combos(width, bits).map_err(CommandError::Decode)?;
+                let settings = Settings { width, ..self.settings.clone() };
+                let format = |value| settings.out_base.format(value, &settings);
+                self.print_text(&seq::table(&values, false, &format));
+            }
+            Command::List(op, values) => {
+                let text;
+                let words: Vec<&str> = if values.is_empty() {
+                    text = clipboard::paste()?;
+                    clipboard::numbers(&text)
+                } else {
+                    values.iter().map(String::as_str).collect()
+                };
+                if words.is_empty() {
+                    return Err(CommandError::NothingToPaste);
+                }
+                let values = words.iter()
+                    .map(|word| self.convert(word).map(|conversion| conversion.value))
+                    .collect::<Result<Vec<u64>, ConvertError>>()
+                    .map_err(|e| CommandError::Decode(e.to_string()))?;
+                let format = |value| self.settings.out_base.format(value, &self.settings);
+                let out = list::report(&op, &values, &|arg| self.read_arg(arg), &format)
+                    .map_err(|e| CommandError::Decode(e.to_string()))?;
+                self.print_text(&out);
             }
+            Command::Plugin { name, args } => {
+                let plugin = self.plugins.find(&name).ok_or(CommandError::Syntax)?;
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                let out = (plugin.run)(&args, &|arg| self.read_arg(arg)).map_err(CommandError::Decode)?;
+                self.print_text(&out);
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the settings, aliases, bookmarks, fields and theme of the config
+    /// file at startup, as defaults that `:undo` does not revert. Return
+    /// false if there is no config file.
+    pub fn load_config(&mut self) -> Result<bool, CommandError> {
+        let config = match config::load()? {
+            Some(config) => config,
+            None => return Ok(false),
+        };
+        self.use_config(config)?;
+        Ok(true)
+    }
+
+    /// Run the commands of the startup file `~/.baseconvrc`. A failing command
+    /// is reported with its line and the next ones still run.
+    pub fn load_rc(&mut self) -> Result<(), CommandError> {
+        for (line, cmd) in config::load_rc()? {
+            if let Err(e) = self.execute(&format!("{}{}", START_CMD, cmd)) {
+                self.print_error(&format!("Error: {} (in ~/.baseconvrc line {})", e, line));
+            }
+        }
+        Ok(())
+    }
+
+    /// Current settings, aliases, bookmarks, fields and theme, as saved by
+    /// `:config save` and `:save`.
+    fn config(&self) -> config::Config {
+        config::Config {
+            settings: self.settings.clone(),
+            aliases: self.aliases.clone(),
+            bookmarks: self.bookmarks.clone(),
+            fields: self.fields.clone(),
+            theme: self.theme.clone(),
+        }
+    }
+
+    /// Replace the settings, the aliases, the bookmarks, the fields and the
+    /// theme. Commands wrap it in `change` so that `:undo` reverts the
+    /// settings.
+    fn use_config(&mut self, config: config::Config) -> Result<(), CommandError> {
+        let config::Config { settings, aliases, bookmarks, fields, theme } = config;
+        self.aliases = aliases;
+        self.bookmarks = bookmarks;
+        self.fields = fields;
+        self.theme = theme;
+        self.settings = settings;
+        self.apply()
+    }
+
+    /// Load the history saved by a previous session, if any.
+    pub fn load_history(&mut self) {
+        if let Some(path) = history::path() {
+            // A missing history file is expected on the first run.
+            let _ = self.editor.load_history(&path);
+            self.history = self.editor.history().iter()
+                .map(|input| history::Entry { input: input.clone(), conversion: None })
+                .collect();
         }
+    }
 
+    /// Add an input to the history, dropping the oldest entries past the
+    /// history size.
+    fn remember(&mut self, input: &str) {
+        self.editor.add_history_entry(input).expect("Fail adding history entry");
+        self.history.push(history::Entry { input: input.to_string(), conversion: None });
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.history.len().saturating_sub(self.settings.history_size);
+        self.history.drain(..excess);
+    }
+
+    /// Save the history for the next session.
+    pub fn save_history(&mut self) -> Result<(), CommandError> {
+        let path = history::path().ok_or_else(|| CommandError::Io("cannot locate home directory".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| CommandError::Io(format!("cannot save history: {}", e)))?;
+        }
+        self.editor.save_history(&path).map_err(|e| CommandError::Io(format!("cannot save history: {}", e)))
+    }
+
+    /// Convert every line of a file, into another file or to the output.
+    fn batch(&self, input: &str, output: Option<&str>) -> Result<(), CommandError> {
+        let input = batch::open(input)?;
+        let summary = match output {
+            Some(path) => {
+                let mut file = File::create(path).map_err(|e| CommandError::Io(format!("cannot create {}: {}", path, e)))?;
+                input.run(self, &mut file, false, true)
+            }
+            None => input.run(self, &mut **self.out.borrow_mut(), true, false),
+        }.map_err(|e| CommandError::Io(e.to_string()))?;
+        self.print_line(&format!("{} converted, {} failed", summary.converted, summary.failed));
         Ok(())
     }
 
+    /// Time `iterations` conversions of an input, or runs of a decoder
+    /// command starting with `:`, and describe the time per iteration.
+    fn bench(&self, input: &str, iterations: u64) -> Result<String, CommandError> {
+        let step: Box<dyn Fn() -> Result<String, CommandError> + '_> = match input.strip_prefix(START_CMD) {
+            Some(cmd) => match command::parse(cmd, &self.plugins)? {
+                Command::Plugin { name, args } => {
+                    let plugin = self.plugins.find(&name).ok_or(CommandError::Syntax)?;
+                    Box::new(move || {
+                        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                        (plugin.run)(&args, &|arg| self.read_arg(arg)).map_err(CommandError::Decode)
+                    })
+                }
+                _ => return Err(CommandError::invalid("benchmark", input, "a value, expression or decoder command")),
+            },
+            None => Box::new(move || self.convert(input).map(|conversion| conversion.text).map_err(|e| CommandError::Decode(e.to_string()))),
+        };
+        // A failing input is reported rather than timed.
+        step()?;
+        let start = Instant::now();
+        for i in 0..iterations {
+            if i % 1024 == 0 && cancel::cancelled() {
+                return Err(CommandError::Cancelled);
+            }
+            black_box(step()?);
+        }
+        let nanos = start.elapsed().as_nanos();
+        Ok(format!("{}  {:.1} ns/op, {} iterations in {}",
+            input, nanos as f64 / iterations as f64, iterations, units::format_duration(nanos * 1000, 1)))
+    }
+
+    /// Print the history.
+    fn print_history(&self) {
+        let mut text = String::new();
+        for (i, entry) in self.history.iter().enumerate() {
+            text += &format!("{:5}  {}\n", i + 1, entry.input);
+        }
+        self.print_text(&text);
+    }
+
+    /// Run a command that changes settings. If anything changed, the previous
+    /// settings are kept for `:undo`.
+    fn change(&mut self, f: impl FnOnce(&mut Self) -> Result<(), CommandError>) -> Result<(), CommandError> {
+        let before = self.settings.clone();
+        let ret = f(self);
+        if self.settings != before {
+            if self.undo.len() == UNDO_DEPTH {
+                self.undo.remove(0);
+            }
+            self.undo.push(before);
+        }
+        ret
+    }
+
+    /// Change a setting and apply it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), CommandError> {
+        self.settings.set(key, value)?;
+        self.apply()
+    }
+
+    /// Apply the settings that are not only read on use.
+    fn apply(&mut self) -> Result<(), CommandError> {
+        self.trim_history();
+        self.editor.set_max_history_size(self.settings.history_size).map_err(|e| CommandError::Io(e.to_string()))
+    }
+
+    /// Print all settings.
+    fn show(&self) {
+        let mut text = String::new();
+        for key in settings::KEYS {
+            let value = self.settings.get(key).unwrap_or_default();
+            if value.is_empty() || value.contains(char::is_whitespace) {
+                text += &format!("{:10} {:?}\n", key, value);
+            } else {
+                text += &format!("{:10} {}\n", key, value);
+            }
+        }
+        self.print_text(&text);
+    }
+
+    /// Read a numeric command argument in the input base, see `func::read_arg`.
+    fn read_arg(&self, arg: &str) -> Result<u64, ConvertError> {
+        func::read_arg(arg, &self.settings)
+    }
+
     /// Print help message.
     fn help(&self) {
-        print!("{}", HELP_MSG);
+        self.print_text(&format!("{}Decoders:\n{}{}", HELP_MSG, self.plugins.help(), FUNCTIONS_HELP));
+    }
+
+    /// Entries of `:help`, as (section, usage, description), e.g. for the
+    /// help of the TUI.
+    pub fn help_entries(&self) -> Vec<(&'static str, String, String)> {
+        let sections = [("Commands", HELP_MSG.to_string()), ("Decoders", self.plugins.help()), ("Functions", FUNCTIONS_HELP.to_string())];
+        let mut entries = Vec::new();
+        for (section, text) in sections.iter() {
+            // Entries are indented, with the description at a fixed column.
+            for line in text.lines().filter(|line| line.starts_with("    ")) {
+                let column = line.char_indices().nth(HELP_COLUMN).map_or(line.len(), |(i, _)| i);
+                let (usage, description) = line.split_at(column);
+                if usage.ends_with(' ') && !description.trim().is_empty() {
+                    entries.push((*section, usage.trim().to_string(), description.trim().to_string()));
+                }
+            }
+        }
+        entries
     }
 }
 
 #[cfg(test)]
 mod test_app {
     use super::*;
+    use crate::render::ColorMode;
+
+    /// Run a session on `input`, return its exit code, output and errors.
+    fn session(input: &str) -> (i32, String, String) {
+        let (out, err) = (Output::default(), Output::default());
+        let mut app = App::with_output(Box::new(out.clone()), Box::new(err.clone()));
+        app.settings.color = ColorMode::Off;
+        let code = app.session(input.as_bytes());
+        (code, out.text(), err.text())
+    }
+
     #[test]
-    #[ignore = "manual"]
     fn test_print() {
-        let app = App::new();
-        app.print("10");
+        let (code, out, err) = session("10\n:to dec\nff\n");
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "<bin> 0001_0000\n<dec> 255\n", ""));
+    }
+
+    #[test]
+    fn test_print_text_output() {
+        let out = Output::default();
+        let mut app = App::with_output(Box::new(out.clone()), Box::new(io::sink()));
+        app.terminal = true;
+        assert_eq!(app.session(":alias th \"to hex\"\n:alias\n".as_bytes()), 0);
+        assert_eq!(out.text(), "th         to hex\n");
+    }
+
+    #[test]
+    fn test_handle_paste() {
+        let (out, err) = (Output::default(), Output::default());
+        let mut app = App::with_output(Box::new(out.clone()), Box::new(err.clone()));
+        app.settings.color = ColorMode::Off;
+        assert_eq!(app.handle_paste("10\r\n:to dec\n\nzz\nff\n"), exit::INVALID);
+        assert_eq!(out.text(), "<bin> 0001_0000\n<dec> 255\n4 lines: 2 converted, 1 failed, 1 command\n");
+        assert!(err.text().starts_with("Error: "));
+        assert_eq!(app.handle_paste("1\n:q\n2\n"), 0);
+        assert!(out.text().ends_with("<dec> 1\n3 lines: 1 converted, 0 failed, 1 command, 1 not run\n"));
+        assert_eq!(app.handle_paste(" 3 "), 0);
+        assert!(out.text().ends_with("1 not run\n<dec> 3\n"));
+    }
+
+    #[test]
+    fn test_paste_enum() {
+        let out = Output::default();
+        let mut app = App::with_output(Box::new(out.clone()), Box::new(io::sink()));
+        app.settings.out_base = Base::Dec;
+        let paste = ":enum enum state {\n    STATE_IDLE, // first\n    STATE_RUN = 3,\n};\nSTATE_RUN\n:enum {\n  A,\n";
+        assert_eq!(app.handle_paste(paste), exit::USAGE);
+        assert!(out.text().ends_with("STATE_IDLE 0\nSTATE_RUN  3\n<dec> 3\n3 lines: 1 converted, 1 failed, 1 command\n"), "{}", out.text());
+        assert_eq!(app.handle_paste(":enum {\n  ONE = 1,\n  TWO\n}\n"), 0);
+        assert!(out.text().ends_with("ONE        1\nTWO        2\n"), "{}", out.text());
+    }
+
+    #[test]
+    fn test_comments() {
+        let (code, out, err) = session("# UART registers\n:to hex // as in the datasheet\n40 // base\n44\t# status\n");
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "<hex> 0x40\n<hex> 0x44\n", ""));
+    }
+
+    #[test]
+    fn test_session() {
+        let (code, out, err) = session(":from dec; to hex\n\nzz\n99999999999999999999\n16\n:q\n17\n");
+        assert_eq!(code, exit::INVALID);
+        assert_eq!(out, "<hex> 0x10\n");
+        assert_eq!(err, "Error: invalid dec digit in zz\nError: 99999999999999999999 does not fit in 64 bits\n");
+
+        let (code, out, _) = session(":format csv\n:foo\n1\n");
+        assert_eq!(code, exit::USAGE);
+        assert!(out.starts_with("1,0x1,1,1,0o1,1 B"), "{}", out);
+
+        let (_, out, _) = session(":show\n");
+        assert!(out.starts_with("from       hex\nto         bin\n"));
     }
 
     #[test]
     #[ignore = "manual"]
     fn test_get_input() {
-        let app = App::new();
+        let mut app = App::new();
         println!("{:?}", app.get_input());
     }
 
@@ -242,24 +1137,31 @@ mod test_app {
         assert!(!app.is_command("72"));
     }
 
+    #[test]
+    fn test_quit() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}to dec", START_CMD)).is_ok() && !app.is_done());
+        assert!(app.execute(&format!("{}to hex; quit", START_CMD)).is_ok() && app.is_done());
+    }
+
     #[test]
     fn test_change_base() {
         let mut app = App::new();
 
         // Ok cases
-        assert!(app.execute(&format!("{}from hex to dec", START_CMD)).is_ok() && app.in_base == Base::Hex && app.out_base == Base::Dec);
-        assert!(app.execute(&format!("{}from hex to hex", START_CMD)).is_ok() && app.in_base == Base::Hex && app.out_base == Base::Hex);
-        assert!(app.execute(&format!("{}from hex to bin", START_CMD)).is_ok() && app.in_base == Base::Hex && app.out_base == Base::Bin);
-        assert!(app.execute(&format!("{}from bin to bin", START_CMD)).is_ok() && app.in_base == Base::Bin && app.out_base == Base::Bin);
-        assert!(app.execute(&format!("{}from bin to dec", START_CMD)).is_ok() && app.in_base == Base::Bin && app.out_base == Base::Dec);
-        assert!(app.execute(&format!("{}from bin to hex", START_CMD)).is_ok() && app.in_base == Base::Bin && app.out_base == Base::Hex);
+        assert!(app.execute(&format!("{}from hex to dec", START_CMD)).is_ok() && app.settings.in_base == Base::Hex && app.settings.out_base == Base::Dec);
+        assert!(app.execute(&format!("{}from hex to hex", START_CMD)).is_ok() && app.settings.in_base == Base::Hex && app.settings.out_base == Base::Hex);
+        assert!(app.execute(&format!("{}from hex to bin", START_CMD)).is_ok() && app.settings.in_base == Base::Hex && app.settings.out_base == Base::Bin);
+        assert!(app.execute(&format!("{}from bin to bin", START_CMD)).is_ok() && app.settings.in_base == Base::Bin && app.settings.out_base == Base::Bin);
+        assert!(app.execute(&format!("{}from bin to dec", START_CMD)).is_ok() && app.settings.in_base == Base::Bin && app.settings.out_base == Base::Dec);
+        assert!(app.execute(&format!("{}from bin to hex", START_CMD)).is_ok() && app.settings.in_base == Base::Bin && app.settings.out_base == Base::Hex);
         assert!(app.execute(&format!("{}  from bin   to     hex  ", START_CMD)).is_ok());
-        assert!(app.execute(&format!("{}from hex", START_CMD)).is_ok() && app.in_base == Base::Hex);
-        assert!(app.execute(&format!("{}from dec", START_CMD)).is_ok() && app.in_base == Base::Dec);
-        assert!(app.execute(&format!("{}from bin", START_CMD)).is_ok() && app.in_base == Base::Bin);
-        assert!(app.execute(&format!("{}to hex", START_CMD)).is_ok() && app.out_base == Base::Hex);
-        assert!(app.execute(&format!("{}to dec", START_CMD)).is_ok() && app.out_base == Base::Dec);
-        assert!(app.execute(&format!("{}to bin", START_CMD)).is_ok() && app.out_base == Base::Bin);
+        assert!(app.execute(&format!("{}from hex", START_CMD)).is_ok() && app.settings.in_base == Base::Hex);
+        assert!(app.execute(&format!("{}from dec", START_CMD)).is_ok() && app.settings.in_base == Base::Dec);
+        assert!(app.execute(&format!("{}from bin", START_CMD)).is_ok() && app.settings.in_base == Base::Bin);
+        assert!(app.execute(&format!("{}to hex", START_CMD)).is_ok() && app.settings.out_base == Base::Hex);
+        assert!(app.execute(&format!("{}to dec", START_CMD)).is_ok() && app.settings.out_base == Base::Dec);
+        assert!(app.execute(&format!("{}to bin", START_CMD)).is_ok() && app.settings.out_base == Base::Bin);
 
         // Err cases
         assert!(app.execute("from hex to dec").is_err());
@@ -271,41 +1173,311 @@ mod test_app {
         assert!(app.execute(&format!("{}t hex", START_CMD)).is_err());
         assert!(app.execute(&format!("{}to hx", START_CMD)).is_err());
     }
-}
 
-#[cfg(test)]
-mod test_base {
-    use super::*;
     #[test]
-    fn test_to_num() {
-        // Ok cases
-        assert_eq!(Base::Hex.to_num("0xff").ok(), Some(255));
-        assert_eq!(Base::Hex.to_num("ff").ok(), Some(255));
-        assert_eq!(Base::Hex.to_num("0XFF").ok(), Some(255));
-        assert_eq!(Base::Hex.to_num("0").ok(), Some(0));
-        assert_eq!(Base::Hex.to_num("0x00").ok(), Some(0));
-        assert_eq!(Base::Hex.to_num("0xffffffffffffffff").ok(), Some(std::u64::MAX));
-        assert_eq!(Base::Bin.to_num("0b101010001101").ok(), Some(2701));
-        assert_eq!(Base::Bin.to_num("0B101010001101").ok(), Some(2701));
-        assert_eq!(Base::Bin.to_num("0b1010_1000_1101").ok(), Some(2701));
-        assert_eq!(Base::Bin.to_num("1010_1000_1101").ok(), Some(2701));
-        assert_eq!(Base::Dec.to_num("101").ok(), Some(101));
-        
-        // Error cases
-        assert!(Base::Hex.to_num("0xgk").is_err());
-        assert!(Base::Hex.to_num("-0xgk").is_err());
-        assert!(Base::Bin.to_num("0b12").is_err());
-        assert!(Base::Bin.to_num("012").is_err());
-        assert!(Base::Dec.to_num("-012").is_err());
-        assert!(Base::Dec.to_num("0d012").is_err());
-    }
-
-    #[test]
-    fn test_bin_format() {
-        assert_eq!(Base::Bin.from(15), String::from("1111"));
-        assert_eq!(Base::Bin.from(16), String::from("0001_0000"));
-        assert_eq!(Base::Bin.from(0), String::from("0"));
-        assert_eq!(Base::Bin.from(1), String::from("1"));
-        assert_eq!(Base::Bin.from(2), String::from("10"));
-    }
-}
\ No newline at end of file
+    fn test_decoder() {
+        let mut app = App::new();
+        app.settings.in_base = Base::Dec;
+        assert_eq!(app.read_arg("0x10").ok(), Some(16));
+        assert_eq!(app.read_arg("0b10").ok(), Some(2));
+        assert_eq!(app.read_arg("10").ok(), Some(10));
+        assert!(app.execute(&format!("{}can 0x18DAF110 j1939", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}can 0x18DAF110 foo", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}can", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_color() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}color on", START_CMD)).is_ok() && app.settings.color == ColorMode::On);
+        assert!(app.execute(&format!("{}color off", START_CMD)).is_ok() && app.settings.color == ColorMode::Off);
+        assert!(app.execute(&format!("{}color auto", START_CMD)).is_ok() && app.settings.color == ColorMode::Auto);
+        assert!(app.execute(&format!("{}color red", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}color", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}set width 16", START_CMD)).is_ok() && app.settings.width == 16);
+        assert!(app.execute(&format!("{}width 8", START_CMD)).is_ok() && app.settings.width == 8);
+        assert!(app.execute(&format!("{}from dec to hex width 32 case upper", START_CMD)).is_ok()
+            && app.settings.in_base == Base::Dec && app.settings.out_base == Base::Hex
+            && app.settings.width == 32 && app.settings.uppercase);
+        assert!(app.execute(&format!("{}show", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}set width", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}set foo 1", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}width 8 to", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_multiple_commands() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}from dec; to hex; width 32", START_CMD)).is_ok()
+            && app.settings.in_base == Base::Dec && app.settings.out_base == Base::Hex && app.settings.width == 32);
+        assert!(app.execute(&format!("{}to bin; :width 8;", START_CMD)).is_ok()
+            && app.settings.out_base == Base::Bin && app.settings.width == 8);
+        let err = app.execute(&format!("{}to hex; to hx; width 16", START_CMD)).unwrap_err();
+        assert_eq!(err.to_string(), "in segment 2 `to hx`: no base hx");
+        assert!(app.settings.out_base == Base::Hex && app.settings.width == 8);
+    }
+
+    #[test]
+    fn test_undo_reset() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}undo", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}from dec to hex", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}width 16", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}width 99", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}undo", START_CMD)).is_ok() && app.settings.width == 0);
+        assert!(app.settings.in_base == Base::Dec && app.settings.out_base == Base::Hex);
+        assert!(app.execute(&format!("{}reset", START_CMD)).is_ok() && app.settings == Settings::default());
+        assert!(app.execute(&format!("{}undo", START_CMD)).is_ok() && app.settings.out_base == Base::Hex);
+        assert!(app.execute(&format!("{}undo", START_CMD)).is_ok() && app.settings == Settings::default());
+        assert!(app.execute(&format!("{}undo", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_prompt() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}prompt \"{{in}}→{{out}} w{{width}}> \"; width 8", START_CMD)).is_ok());
+        assert_eq!(app.settings.prompt, "{in}→{out} w{width}> ");
+        assert_eq!(app.settings.width, 8);
+        assert!(app.execute(&format!("{}prompt \"a", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}prompt a\"b\"", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_log() {
+        let path = std::env::temp_dir().join(format!("base_converter_log_{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let (code, out, _) = session(&format!("1\n:log {}\n10\nzz\n:log off\n11\n", path.display()));
+        assert_eq!(code, exit::INVALID);
+        assert!(out.starts_with("<bin> 1\nLogging to "));
+        let log = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().map(|line| &line[20..]).collect();
+        assert_eq!(lines, vec!["> 10", "< <bin> 0001_0000", "> zz", "! Error: invalid hex digit in zz", "> :log off"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_workspace() {
+        let path = std::env::temp_dir().join(format!("base_converter_work_{}.toml", std::process::id()));
+        let input = format!(":to dec; width 16; alias th \"to hex\"\n:save {0}\n:reset\n:load {0}\n1\n:th\n1\n", path.display());
+        let (code, out, err) = session(&input);
+        assert_eq!((code, err.as_str()), (0, ""));
+        assert_eq!(out, format!("Saved to {}\n<dec> 1\n<hex> 0x0001\n", path.display()));
+        fs::remove_file(&path).unwrap();
+
+        let input = format!(":deffield mode[7:4] en[3]\n:save {0}\n:deffield clear\n:load {0}\n:deffield\n", path.display());
+        let (code, out, err) = session(&input);
+        assert_eq!((code, err.as_str()), (0, ""));
+        assert_eq!(out, format!("Saved to {}\nmode[7:4]\nen[3]\n", path.display()));
+        fs::remove_file(&path).unwrap();
+
+        let (code, _, err) = session(":load /nonexistent/work.toml\n");
+        assert_eq!(code, exit::USAGE);
+        assert!(err.starts_with("Error: cannot read /nonexistent/work.toml"));
+    }
+
+    #[test]
+    fn test_startup_config_undo() {
+        let err = Output::default();
+        let mut app = App::with_output(Box::new(io::sink()), Box::new(err.clone()));
+        let mut config = config::Config::default();
+        config.settings.set("width", "16").unwrap();
+        app.use_config(config).unwrap();
+        assert_eq!(app.session(":undo\n".as_bytes()), exit::USAGE);
+        assert_eq!(err.text(), "Error: nothing to undo\n");
+        assert_eq!(app.settings.width, 16);
+    }
+
+    #[test]
+    fn test_bookmark() {
+        let (code, out, err) = session(":bookmark reg\n1234\n:bookmark baud_reg\n:to hex; from dec\nbaud_reg\nhtons(baud_reg)\n:bookmarks\n");
+        assert_eq!(code, exit::USAGE);
+        assert_eq!(err, "Error: no result yet\n");
+        assert_eq!(out, "<bin> 0001_0010_0011_0100\n<hex> 0x1234\n<hex> 0x3412 (u16)\nbaud_reg   0x1234 4660 0001_0010_0011_0100\n");
+
+        let (code, _, err) = session("1\n:bookmark cafe\n");
+        assert_eq!(code, exit::USAGE);
+        assert_eq!(err, "Error: invalid bookmark cafe, expect a name that is not a hex number\n");
+    }
+
+    #[test]
+    fn test_enum() {
+        let (code, out, err) = session(":to hex\n:enum typedef enum { STATE_IDLE, STATE_RUN = 3, STATE_STOP } state_t;\nSTATE_STOP\n:whois 3\n:whois 0x4\n");
+        assert_eq!((code, err.as_str()), (0, ""));
+        assert_eq!(out, "STATE_IDLE 0x0\nSTATE_RUN  0x3\nSTATE_STOP 0x4\n<hex> 0x4\nSTATE_RUN\nSTATE_STOP\n");
+
+        let (code, _, err) = session(":enum enum { A, B }\n:whois 5\n");
+        assert_eq!(code, exit::USAGE);
+        assert_eq!(err, "Error: invalid bookmark A, expect a name that is not a hex number\nError: no bookmark has the value 5\n");
+    }
+
+    #[test]
+    fn test_register_dump() {
+        let (code, out, err) = session(":to hex\nCR1 = 0x00000081\nR0 0xDEADBEEF\nrax            0x1c                28\nhtons(CR1)\n:bookmarks\n");
+        assert_eq!((code, err.as_str()), (0, ""));
+        assert!(out.starts_with("<hex> 0x81 (CR1)\n<hex> 0xdeadbeef (R0)\n<hex> 0x1c (rax)\n<hex> 0x8100 (u16)\n"), "{}", out);
+        assert!(out.contains("CR1        0x81 129 1000_0001\n"), "{}", out);
+
+        let (code, _, err) = session("cafe = 1\n");
+        assert_eq!(code, exit::INVALID);
+        assert!(err.starts_with("Error: "), "{}", err);
+    }
+
+    #[test]
+    fn test_ans() {
+        let (code, out, err) = session("ans\n1234\nhtons(ans)\nans\n");
+        assert_eq!(code, exit::INVALID);
+        assert_eq!(err, "Error: invalid hex digit in ans\n");
+        assert_eq!(out, "<bin> 0001_0010_0011_0100\n<bin> 0011_0100_0001_0010 (u16)\n<bin> 0011_0100_0001_0010\n");
+    }
+
+    #[test]
+    fn test_deffield() {
+        let (code, out, _) = session(":deffield div[2:0] mode[7:4]\n:deffield\n3a\n:deffield en[3]\n:deffield\n:deffield clear\n:deffield\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, "mode[7:4]\ndiv[2:0]\n<bin> 0011_1010\nmode[7:4]        0x3\nen[3]            1\ndiv[2:0]         0x2\n\n");
+
+        let (code, _, err) = session(":deffield mode[4:7]\n");
+        assert_eq!(code, exit::USAGE);
+        assert_eq!(err, "Error: invalid field mode[4:7], expect <name>[<msb>:<lsb>] or <name>[<bit>]\n");
+    }
+
+    #[test]
+    fn test_help_entries() {
+        let app = App::with_output(Box::new(io::sink()), Box::new(io::sink()));
+        let entries = app.help_entries();
+        assert_eq!(entries[0], ("Commands", ":from <base> to <base>".to_string(), "change input base and output base".to_string()));
+        assert!(entries.contains(&("Commands", ":deffield <name>[<hi>:<lo>]".to_string(), "define register fields, e.g. :deffield mode[7:4] en[3]".to_string())));
+        assert!(entries.contains(&("Decoders", ":uuid <uuid>".to_string(), "convert a BLE UUID between short and 128-bit form".to_string())));
+        assert!(entries.contains(&("Functions", "htons(x), ntohs(x)".to_string(), "swap bytes of a 16-bit value".to_string())));
+        assert!(entries.iter().all(|(_, usage, description)| !usage.is_empty() && !description.is_empty()));
+    }
+
+    #[test]
+    fn test_copy() {
+        let (code, _, err) = session(":copy\n");
+        assert_eq!((code, err.as_str()), (exit::USAGE, "Error: no result yet\n"));
+    }
+
+    #[test]
+    fn test_bench() {
+        let (code, out, err) = session(":bench htons(1) 10\n:bench \":crc crc32 31 32\" 5\n:bench zz\n:bench \":show\"\n:bench \":crc crc32 bitwise 31 32\" 5\n");
+        assert_eq!(code, exit::USAGE);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("htons(1)  ") && lines[0].contains(" ns/op, 10 iterations in "), "{}", lines[0]);
+        assert!(lines[1].starts_with(":crc crc32 31 32  ") && lines[1].contains(" ns/op, 5 iterations in "), "{}", lines[1]);
+        assert!(lines[2].starts_with(":crc crc32 bitwise 31 32  ") && lines[2].contains(" ns/op, 5 iterations in "), "{}", lines[2]);
+        assert_eq!(err, "Error: invalid hex digit in zz\nError: invalid benchmark :show, expect a value, expression or decoder command\n");
+    }
+
+    #[test]
+    fn test_rand() {
+        let (code, out, _) = session(":to hex\n:rand 12 50\n:width 16\n:rand\n");
+        assert_eq!(code, 0);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 51);
+        assert!(lines[..50].iter().all(|line| line.len() == 5 && u64::from_str_radix(&line[2..], 16).is_ok()), "{:?}", lines);
+        assert_eq!(lines[50].len(), 6);
+    }
+
+    #[test]
+    fn test_seq() {
+        let (code, out, err) = session(":to hex\n:seq 40000000 40000010 4\n:seq 3 0 --index\n:seq 0 1 0\n");
+        assert_eq!((code, err.as_str()), (exit::USAGE, "Error: step must not be 0\n"));
+        assert_eq!(out, "0x40000000\n0x40000004\n0x40000008\n0x4000000c\n0x40000010\n0  0x3\n1  0x2\n2  0x1\n3  0x0\n");
+    }
+
+    #[test]
+    fn test_combos() {
+        let (code, out, err) = session(":combos width=4 bits=3\n:combos width=64 bits=32\n");
+        assert_eq!(out, "0111\n1011\n1101\n1110\n");
+        assert_eq!((code, err.as_str()), (exit::USAGE, "Error: 1832624140942590534 values, at most 1048576\n"));
+    }
+
+    #[test]
+    fn test_list() {
+        let (code, out, err) = session(":to hex\n:alignreport 4 1000, 1002 zz\n:alignreport 4 1000 1002 ffffffffffffffff\n");
+        assert_eq!((code, err.as_str()), (exit::USAGE, "Error: invalid hex digit in zz\n"));
+        assert_eq!(out, "0x1000  ok\n0x1002  misaligned by 2, down 0x1000, up 0x1004\n\
+            0xffffffffffffffff  misaligned by 3, down 0xfffffffffffffffc\n2 of 3 misaligned for alignment 4\n");
+
+        let (_, out, _) = session(":to hex\n:dups --mask 0x700 100 200 4ff 123\n");
+        assert_eq!(out, "0x100  2 times, at 1, 4, from 0x100, 0x123\n1 duplicated value in 4 values after mask 0x700\n");
+    }
+
+    #[test]
+    fn test_export() {
+        let path = std::env::temp_dir().join(format!("base_converter_export_{}.csv", std::process::id()));
+        let (code, out, _) = session(&format!("ff\n:to dec\nzz\n10\n:export {}\n", path.display()));
+        assert_eq!(code, exit::INVALID);
+        assert!(out.ends_with(&format!("Exported 2 conversions to {}\n", path.display())));
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("input,hex,dec,bin,oct,size"));
+        assert!(lines[1].starts_with("ff,0xff,255,1111_1111,0o377,255 B"));
+        assert!(lines[2].starts_with("10,0x10,16,0001_0000,0o20,16 B"));
+        fs::remove_file(&path).unwrap();
+
+        let (_, _, err) = session("1\n:export results.txt\n");
+        assert_eq!(err, "Error: invalid export format txt, expect md, csv, tsv or json\n");
+    }
+
+    #[test]
+    fn test_alias() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}alias fd \"from dec\"", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}fd to hex", START_CMD)).is_ok()
+            && app.settings.in_base == Base::Dec && app.settings.out_base == Base::Hex);
+        assert!(app.execute(&format!("{}alias", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}alias fd", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_convert() {
+        let mut app = App::new();
+        assert_eq!(app.convert("htons(0x12)").unwrap().text, "0001_0010_0000_0000 (u16)");
+        assert!(app.execute(&format!("{}to hex", START_CMD)).is_ok());
+        assert_eq!(app.convert("htons(0x12)").unwrap().text, "0x1200 (u16)");
+        assert!(app.execute(&format!("{}width 32", START_CMD)).is_ok());
+        assert_eq!(app.convert("htons(0x12)").unwrap().text, "0x00001200 (u16)");
+        assert_eq!(app.convert("ff").unwrap().text, "0x000000ff");
+        assert!(app.execute(&format!("{}width 4", START_CMD)).is_ok());
+        assert!(app.convert("ff").is_err());
+    }
+
+    #[test]
+    fn test_quiet() {
+        let mut app = App::new();
+        assert!(app.execute(&format!("{}quiet on to hex", START_CMD)).is_ok());
+        assert_eq!(app.render(&app.convert("htons(0x12)").unwrap(), true), "0x1200");
+        assert!(app.execute(&format!("{}quiet off color off", START_CMD)).is_ok());
+        assert_eq!(app.render(&app.convert("htons(0x12)").unwrap(), true), "<hex> 0x1200 (u16)");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut app = App::with_output(Box::new(io::sink()), Box::new(io::sink()));
+        app.handle("1");
+        app.handle("2");
+        app.handle("3");
+        assert!(app.execute(&format!("{}history size 2", START_CMD)).is_ok());
+        assert_eq!(app.editor.history().iter().count(), 2);
+        assert_eq!(app.history.iter().map(|entry| entry.input.as_str()).collect::<Vec<&str>>(), vec!["2", "3"]);
+        assert!(app.execute(&format!("{}history", START_CMD)).is_ok());
+        assert!(app.execute(&format!("{}history size x", START_CMD)).is_err());
+        assert!(app.execute(&format!("{}history foo", START_CMD)).is_err());
+    }
+
+    #[test]
+    fn test_history_expansion() {
+        let (code, out, err) = session("10\n!!\n:to dec\n!1\nhtons(!1)\n!9\n:history\n");
+        assert_eq!(code, exit::INVALID);
+        assert_eq!(err, "Error: invalid hex digit in !1\nError: no history entry !9\n");
+        assert_eq!(out, "<bin> 0001_0000\n<bin> 0001_0000\n<dec> 16\n    1  10\n    2  10\n    3  :to dec\n    4  10\n    5  htons(!1)\n    6  :history\n");
+    }
+}
@@ -0,0 +1,703 @@
+//! Number bases, and how values are read from and formatted to them.
+
+use std::fmt::{self, Write};
+use std::num::IntErrorKind;
+use std::sync::RwLock;
+use crate::error::ConvertError;
+use crate::settings::Settings;
+use crate::token;
+use crate::units;
+
+/// A number base: how digits are read and written. Implement it and
+/// `register` the implementation to add a base, e.g. a custom alphabet.
+pub trait NumberBase: fmt::Debug + Send + Sync {
+    /// Name used in commands, e.g. "hex".
+    fn name(&self) -> &str;
+    /// Lower case prefixes that may precede the digits, e.g. `0x`.
+    fn prefixes(&self) -> &[&str] {
+        &[]
+    }
+    /// Read digits, without prefix or C suffix.
+    fn parse(&self, digits: &str) -> Result<u64, IntErrorKind>;
+    /// Whether a character can appear in the digits, to locate invalid
+    /// ones. By default, whether it reads as a number alone.
+    fn is_digit(&self, c: char) -> bool {
+        self.parse(c.encode_utf8(&mut [0; 4])).is_ok()
+    }
+    /// Format a number with the output settings.
+    fn format(&self, num: u64, settings: &Settings) -> String;
+    /// Append a number formatted like `format` to `out`. Implement it to
+    /// format without allocating, e.g. in batch conversions.
+    fn format_into(&self, num: u64, settings: &Settings, out: &mut String) {
+        out.push_str(&self.format(num, settings));
+    }
+}
+
+/// Enum for base types. Bases other than the default ones are registered
+/// implementations of `NumberBase`, e.g. octal.
+#[derive(Clone, Debug)]
+pub enum Base {
+    Bin,
+    Dec,
+    Hex,
+    Other(&'static dyn NumberBase),
+}
+
+/// Bases registered with `register`.
+static REGISTRY: RwLock<Vec<&'static dyn NumberBase>> = RwLock::new(Vec::new());
+
+/// Bases available besides bin, dec and hex, before any `register`.
+const BUILTIN: &[&dyn NumberBase] = &[&Octal, &Size];
+
+/// Make a base available by its name, e.g. for `:from <base>`. A base with
+/// the name of an existing one replaces it, except bin, dec and hex.
+pub fn register(base: &'static dyn NumberBase) {
+    let mut registry = REGISTRY.write().unwrap();
+    registry.retain(|other| other.name() != base.name());
+    registry.push(base);
+}
+
+/// Names of all available bases.
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = vec!["hex".to_string(), "dec".to_string(), "bin".to_string()];
+    for base in BUILTIN.iter().chain(REGISTRY.read().unwrap().iter()) {
+        if !names.iter().any(|name| name == base.name()) {
+            names.push(base.name().to_string());
+        }
+    }
+    names
+}
+
+/// All available bases, in the order of `names`.
+pub fn all() -> Vec<Base> {
+    names().iter().filter_map(|name| Base::from_name(name)).collect()
+}
+
+impl PartialEq for Base {
+    fn eq(&self, other: &Base) -> bool {
+        self.imp().name() == other.imp().name()
+    }
+}
+
+impl fmt::Display for Base {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.imp().name())
+    }
+}
+
+/// Output style, i.e. the syntax used to write numbers.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Style {
+    /// Binary grouped with `_` and no prefix, hex with `0x`.
+    Plain,
+    /// C literals: `0b` and `0x` prefixes, no digit separator.
+    C,
+    /// Verilog sized literals, e.g. `16'hbeef` or `8'b1010_0001`, of the
+    /// width setting, or else of the bits of the number.
+    Verilog,
+    /// VHDL bit string literals for `std_logic_vector`, e.g. `x"beef"` or
+    /// `b"1010_0001"`, see `write_vhdl`.
+    Vhdl,
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Style::Plain => write!(f, "plain"),
+            Style::C => write!(f, "c"),
+            Style::Verilog => write!(f, "verilog"),
+            Style::Vhdl => write!(f, "vhdl"),
+        }
+    }
+}
+
+impl Style {
+    /// Get a style from its name, e.g. "c".
+    pub fn from_name(name: &str) -> Option<Style> {
+        match name {
+            "plain" => Some(Style::Plain),
+            "c" => Some(Style::C),
+            "verilog" => Some(Style::Verilog),
+            "vhdl" => Some(Style::Vhdl),
+            _ => None,
+        }
+    }
+}
+
+impl Base {
+    /// Get a base from its name, e.g. "hex".
+    pub fn from_name(name: &str) -> Option<Base> {
+        match name {
+            "hex" => Some(Base::Hex),
+            "dec" => Some(Base::Dec),
+            "bin" => Some(Base::Bin),
+            _ => {
+                let registry = REGISTRY.read().unwrap();
+                registry.iter().rev().chain(BUILTIN.iter())
+                    .find(|base| base.name() == name)
+                    .map(|base| Base::Other(*base))
+            }
+        }
+    }
+
+    /// Implementation of the base.
+    fn imp(&self) -> &dyn NumberBase {
+        match self {
+            Base::Bin => &Binary,
+            Base::Dec => &Decimal,
+            Base::Hex => &Hexadecimal,
+            Base::Other(base) => *base,
+        }
+    }
+
+    /// Letter of the base in Verilog literals, e.g. `h` in `16'hbeef`.
+    fn verilog_letter(&self) -> Option<char> {
+        match self {
+            Base::Bin => Some('b'),
+            Base::Dec => Some('d'),
+            Base::Hex => Some('h'),
+            Base::Other(base) if base.name() == "oct" => Some('o'),
+            Base::Other(_) => None,
+        }
+    }
+
+    /// Length of the Verilog size and base of `input`, e.g. 4 for
+    /// `16'hbeef` read in hex, or 0 if it has none.
+    fn verilog_prefix(&self, input: &str) -> usize {
+        let Some(letter) = self.verilog_letter() else { return 0 };
+        let size = input.len() - input.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let rest = &input[size..];
+        let signed = rest.get(1..2).is_some_and(|s| s.eq_ignore_ascii_case("s")) as usize;
+        match rest.get(..2 + signed) {
+            Some(start) if start.starts_with('\'') && start[1 + signed..].eq_ignore_ascii_case(letter.encode_utf8(&mut [0; 4])) => size + 2 + signed,
+            _ => 0,
+        }
+    }
+
+    /// Read a string and convert it to u64 based on base type. A prefix of
+    /// the base in any case, or the size and base of a Verilog literal such
+    /// as `16'h`, and a C `u`/`uL` suffix are ignored, and so are the digit
+    /// separators of `token`. The built-in bases read the string in place,
+    /// without allocating unless it has separators or is invalid.
+    /// # Example:
+    /// ```
+    /// # use base_converter::Base;
+    /// assert_eq!(Base::Bin.to_num("0b10").ok(), Some(2));
+    /// assert_eq!(Base::Bin.to_num("0001_0000").ok(), Some(16));
+    /// assert_eq!(Base::Hex.to_num("0xff").ok(), Some(255));
+    /// assert_eq!(Base::Hex.to_num("DE:AD:BE:EF").ok(), Some(0xdeadbeef));
+    /// assert_eq!(Base::Hex.to_num("16'hBEEF").ok(), Some(0xbeef));
+    /// ```
+    pub fn to_num(&self, input: &str) -> Result<u64, ConvertError> {
+        let input = input.trim();
+        let digits = input.strip_suffix("uL").or_else(|| input.strip_suffix('u')).unwrap_or(input);
+        let start = self.imp().prefixes().iter()
+            .find(|prefix| digits.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix)))
+            .map_or_else(|| self.verilog_prefix(digits), |prefix| prefix.len());
+        let digits = &digits[start..];
+        self.imp().parse(&token::digits(digits)).map_err(|kind| {
+            let position = digits.char_indices()
+                .find(|(_, c)| !token::is_separator(*c) && !self.imp().is_digit(*c))
+                .map(|(i, _)| start + i);
+            ConvertError::parse(input, self, kind).at(position)
+        })
+    }
+
+    /// Format an u64 number based on base type and the output settings (width,
+    /// binary digit grouping, hex case, style). Return the formated `String`.
+    /// # Example:
+    /// ```
+    /// # use base_converter::{Base, Settings};
+    /// let settings = Settings::default();
+    /// assert_eq!(Base::Bin.format(4, &settings), "100");
+    /// assert_eq!(Base::Bin.format(16, &settings), "0001_0000");
+    /// assert_eq!(Base::Hex.format(255, &settings), "0xff");
+    /// ```
+    pub fn format(&self, num: u64, settings: &Settings) -> String {
+        self.imp().format(num, settings)
+    }
+
+    /// Append a number formatted like `format` to `out`, reusing its buffer.
+    /// The built-in bases do not allocate otherwise.
+    /// # Example:
+    /// ```
+    /// # use base_converter::{Base, Settings};
+    /// let mut out = String::from("reg = ");
+    /// Base::Hex.format_into(255, &Settings::default(), &mut out);
+    /// assert_eq!(out, "reg = 0xff");
+    /// ```
+    pub fn format_into(&self, num: u64, settings: &Settings, out: &mut String) {
+        self.imp().format_into(num, settings, out)
+    }
+}
+
+/// Format with `NumberBase::format_into`, for bases that implement it.
+fn format_with(base: &dyn NumberBase, num: u64, settings: &Settings) -> String {
+    let mut out = String::new();
+    base.format_into(num, settings, &mut out);
+    out
+}
+
+/// Bits of a Verilog literal of `num`: the width setting, or else the bits
+/// of the number.
+fn verilog_size(num: u64, settings: &Settings) -> usize {
+    match settings.width {
+        0 => (64 - num.leading_zeros() as usize).max(1),
+        width => width as usize,
+    }
+}
+
+/// Append the `len` lowest bits of `num`, with `_` between groups of
+/// `group` bits from the right.
+fn push_bits(out: &mut String, num: u64, len: usize, group: usize) {
+    for i in (0..len).rev() {
+        let bit = if i < 64 { num >> i & 1 } else { 0 };
+        out.push(if bit == 1 { '1' } else { '0' });
+        if group > 0 && i > 0 && i.is_multiple_of(group) {
+            out.push('_');
+        }
+    }
+}
+
+/// Append a VHDL bit string literal of `num` with digits of `bits` bits,
+/// written by `digits` given their number, e.g. `x"00be"` in width 16, or
+/// the VHDL-2008 `10x"1ff"` for widths that are not a whole number of
+/// digits. With the `others` setting, the padding up to the width is an
+/// aggregate instead, e.g. `(15 downto 8 => '0') & x"be"`, and 0 is
+/// `(others => '0')`.
+fn write_vhdl(out: &mut String, num: u64, settings: &Settings, letter: &str, bits: usize, digits: impl Fn(&mut String, usize)) {
+    let width = settings.width as usize;
+    let count = (64 - num.leading_zeros() as usize).max(1).div_ceil(bits);
+    if settings.others && count * bits < width {
+        if num == 0 {
+            out.push_str("(others => '0')");
+            return;
+        }
+        let _ = write!(out, "({} downto {} => '0') & {}\"", width - 1, count * bits, letter);
+        digits(out, count);
+    } else if width > 0 {
+        if !width.is_multiple_of(bits) {
+            let _ = write!(out, "{}", width);
+        }
+        let _ = write!(out, "{}\"", letter);
+        digits(out, width.div_ceil(bits));
+    } else {
+        let _ = write!(out, "{}\"", letter);
+        digits(out, count);
+    }
+    out.push('"');
+}
+
+/// Read digits with `u64::from_str_radix`. An invalid digit is reported
+/// rather than an overflow, wherever it is, like `parse_digits`.
+fn parse_radix(digits: &str, radix: u32) -> Result<u64, IntErrorKind> {
+    u64::from_str_radix(digits, radix).map_err(|e| match e.kind() {
+        IntErrorKind::PosOverflow if !digits.strip_prefix('+').unwrap_or(digits).chars().all(|c| c.is_digit(radix)) => {
+            IntErrorKind::InvalidDigit
+        }
+        kind => *kind,
+    })
+}
+
+/// Read digits one at a time, without copying them. An invalid digit is
+/// reported rather than an overflow, wherever it is.
+fn parse_digits(digits: &str, radix: u32) -> Result<u64, IntErrorKind> {
+    let mut num: Option<u64> = None;
+    let mut overflow = false;
+    for c in digits.chars() {
+        let digit = c.to_digit(radix).ok_or(IntErrorKind::InvalidDigit)?;
+        let next = num.unwrap_or(0).checked_mul(radix as u64).and_then(|num| num.checked_add(digit as u64));
+        overflow |= next.is_none();
+        num = Some(next.unwrap_or(0));
+    }
+    match num {
+        _ if overflow => Err(IntErrorKind::PosOverflow),
+        Some(num) => Ok(num),
+        None => Err(IntErrorKind::Empty),
+    }
+}
+
+#[derive(Debug)]
+struct Binary;
+
+impl NumberBase for Binary {
+    fn name(&self) -> &str {
+        "bin"
+    }
+
+    fn prefixes(&self) -> &[&str] {
+        &["0b"]
+    }
+
+    /// Digits are read as they come, so that long strings, e.g. exported
+    /// from a logic analyzer, are not copied.
+    fn parse(&self, digits: &str) -> Result<u64, IntErrorKind> {
+        parse_digits(digits, 2)
+    }
+
+    fn is_digit(&self, c: char) -> bool {
+        matches!(c, '0' | '1')
+    }
+
+    fn format(&self, num: u64, settings: &Settings) -> String {
+        format_with(self, num, settings)
+    }
+
+    fn format_into(&self, num: u64, settings: &Settings, out: &mut String) {
+        let width = settings.width as usize;
+        let group = settings.group as usize;
+        match settings.style {
+            Style::C => {
+                let _ = write!(out, "0b{:0w$b}", num, w = width);
+                return;
+            }
+            // Only `b"..."` literals may have `_`, `"..."` is a plain string.
+            Style::Vhdl => {
+                let letter = if group > 0 { "b" } else { "" };
+                return write_vhdl(out, num, settings, letter, 1, |out, len| push_bits(out, num, len, group));
+            }
+            _ => (),
+        }
+        let len = (64 - num.leading_zeros() as usize).max(1);
+        let pad = if settings.style == Style::Verilog {
+            let size = verilog_size(num, settings);
+            let _ = write!(out, "{}'b", size);
+            size
+        } else if width > 0 {
+            width
+        } else if group > 0 && len > group {
+            len.div_ceil(group) * group
+        } else {
+            0
+        };
+        push_bits(out, num, len.max(pad), group);
+    }
+}
+
+#[derive(Debug)]
+struct Decimal;
+
+impl NumberBase for Decimal {
+    fn name(&self) -> &str {
+        "dec"
+    }
+
+    fn parse(&self, digits: &str) -> Result<u64, IntErrorKind> {
+        parse_radix(digits, 10)
+    }
+
+    fn format(&self, num: u64, settings: &Settings) -> String {
+        format_with(self, num, settings)
+    }
+
+    fn format_into(&self, num: u64, settings: &Settings, out: &mut String) {
+        // Unsized decimal literals are valid Verilog too.
+        let _ = if settings.style == Style::Verilog && settings.width > 0 {
+            write!(out, "{}'d{}", settings.width, num)
+        } else {
+            write!(out, "{}", num)
+        };
+    }
+}
+
+#[derive(Debug)]
+struct Hexadecimal;
+
+impl NumberBase for Hexadecimal {
+    fn name(&self) -> &str {
+        "hex"
+    }
+
+    fn prefixes(&self) -> &[&str] {
+        &["0x"]
+    }
+
+    fn parse(&self, digits: &str) -> Result<u64, IntErrorKind> {
+        parse_radix(digits, 16)
+    }
+
+    fn format(&self, num: u64, settings: &Settings) -> String {
+        format_with(self, num, settings)
+    }
+
+    fn format_into(&self, num: u64, settings: &Settings, out: &mut String) {
+        if settings.style == Style::Vhdl {
+            let uppercase = settings.uppercase;
+            return write_vhdl(out, num, settings, "x", 4, |out, len| {
+                let _ = if uppercase { write!(out, "{:0w$X}", num, w = len) } else { write!(out, "{:0w$x}", num, w = len) };
+            });
+        }
+        let mut width = (settings.width as usize).div_ceil(4);
+        if settings.style == Style::Verilog {
+            let size = verilog_size(num, settings);
+            let _ = write!(out, "{}'h", size);
+            width = size.div_ceil(4);
+        } else {
+            out.push_str("0x");
+        }
+        let _ = if settings.uppercase {
+            write!(out, "{:0w$X}", num, w = width)
+        } else {
+            write!(out, "{:0w$x}", num, w = width)
+        };
+    }
+}
+
+/// Octal, written `0o17`, or `017` in C style.
+#[derive(Debug)]
+struct Octal;
+
+impl NumberBase for Octal {
+    fn name(&self) -> &str {
+        "oct"
+    }
+
+    fn prefixes(&self) -> &[&str] {
+        &["0o"]
+    }
+
+    fn parse(&self, digits: &str) -> Result<u64, IntErrorKind> {
+        parse_radix(digits, 8)
+    }
+
+    fn format(&self, num: u64, settings: &Settings) -> String {
+        let width = (settings.width as usize).div_ceil(3);
+        match settings.style {
+            Style::C if num == 0 && width == 0 => "0".to_string(),
+            Style::C => format!("0{:0w$o}", num, w = width),
+            Style::Plain => format!("0o{:0w$o}", num, w = width),
+            Style::Verilog => {
+                let size = verilog_size(num, settings);
+                format!("{}'o{:0w$o}", size, num, w = size.div_ceil(3))
+            }
+            Style::Vhdl => {
+                let mut out = String::new();
+                write_vhdl(&mut out, num, settings, "o", 3, |out, len| {
+                    let _ = write!(out, "{:0w$o}", num, w = len);
+                });
+                out
+            }
+        }
+    }
+}
+
+/// Byte sizes, written `256 KiB`, see `units`.
+#[derive(Debug)]
+struct Size;
+
+impl NumberBase for Size {
+    fn name(&self) -> &str {
+        "size"
+    }
+
+    fn parse(&self, digits: &str) -> Result<u64, IntErrorKind> {
+        units::parse_size(digits)
+    }
+
+    fn format(&self, num: u64, _settings: &Settings) -> String {
+        units::format_size(num)
+    }
+}
+
+#[cfg(test)]
+mod test_base {
+    use super::*;
+    #[test]
+    fn test_to_num() {
+        // Ok cases
+        assert_eq!(Base::Hex.to_num("0xff").ok(), Some(255));
+        assert_eq!(Base::Hex.to_num("ff").ok(), Some(255));
+        assert_eq!(Base::Hex.to_num("0XFF").ok(), Some(255));
+        assert_eq!(Base::Hex.to_num("0").ok(), Some(0));
+        assert_eq!(Base::Hex.to_num("0x00").ok(), Some(0));
+        assert_eq!(Base::Hex.to_num("0xffffffffffffffff").ok(), Some(u64::MAX));
+        assert_eq!(Base::Bin.to_num("0b101010001101").ok(), Some(2701));
+        assert_eq!(Base::Bin.to_num("0B101010001101").ok(), Some(2701));
+        assert_eq!(Base::Bin.to_num("0b1010_1000_1101").ok(), Some(2701));
+        assert_eq!(Base::Bin.to_num("1010_1000_1101").ok(), Some(2701));
+        assert_eq!(Base::Dec.to_num("101").ok(), Some(101));
+        assert_eq!(Base::Bin.to_num("1111 0000").ok(), Some(0xf0));
+        assert_eq!(Base::Hex.to_num("0xDE:AD:BE:EF").ok(), Some(0xdeadbeef));
+        assert_eq!(Base::Dec.to_num("1'000'000").ok(), Some(1_000_000));
+        assert_eq!(Base::Dec.to_num("1,000,000").ok(), Some(1_000_000));
+        assert_eq!(Base::Hex.to_num("4000_0100").ok(), Some(0x4000_0100));
+        assert_eq!(Base::from_name("oct").unwrap().to_num("0o7_7").ok(), Some(0o77));
+        
+        // Error cases
+        assert!(Base::Hex.to_num("0xgk").is_err());
+        assert!(Base::Hex.to_num("-0xgk").is_err());
+        assert!(Base::Bin.to_num("0b12").is_err());
+        assert!(Base::Bin.to_num("012").is_err());
+        assert!(Base::Dec.to_num("-012").is_err());
+        assert!(Base::Dec.to_num("0d012").is_err());
+        assert_eq!(Base::Hex.to_num("1ffffffffffffffff"), Err(ConvertError::Overflow { input: "1ffffffffffffffff".to_string() }));
+        assert_eq!(Base::Bin.to_num("0b"), Err(ConvertError::Empty));
+    }
+
+    #[test]
+    fn test_octal() {
+        let oct = Base::from_name("oct").unwrap();
+        assert_eq!(oct.to_num("0o17").ok(), Some(15));
+        assert_eq!(oct.to_num("17").ok(), Some(15));
+        assert!(oct.to_num("8").is_err());
+        let mut settings = Settings::default();
+        assert_eq!(oct.format(15, &settings), "0o17");
+        settings.style = Style::C;
+        settings.width = 8;
+        assert_eq!(oct.format(15, &settings), "0017");
+        settings.style = Style::Verilog;
+        assert_eq!(oct.format(15, &settings), "8'o017");
+        assert_eq!(oct.to_num("8'o017").ok(), Some(15));
+    }
+
+    #[test]
+    fn test_size() {
+        let size = Base::from_name("size").unwrap();
+        assert_eq!(size.to_num("256K").ok(), Some(0x40000));
+        assert_eq!(size.format(0x40000, &Settings::default()), "256 KiB");
+        assert!(names().contains(&"size".to_string()));
+    }
+
+    #[derive(Debug)]
+    struct Letters;
+
+    impl NumberBase for Letters {
+        fn name(&self) -> &str {
+            "letters"
+        }
+
+        fn parse(&self, digits: &str) -> Result<u64, IntErrorKind> {
+            digits.chars().try_fold(0u64, |num, c| match c {
+                'a'..='j' => num.checked_mul(10).and_then(|n| n.checked_add(c as u64 - 'a' as u64))
+                    .ok_or(IntErrorKind::PosOverflow),
+                _ => Err(IntErrorKind::InvalidDigit),
+            })
+        }
+
+        fn format(&self, num: u64, _settings: &Settings) -> String {
+            num.to_string().chars().map(|d| (b'a' + d as u8 - b'0') as char).collect()
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        assert_eq!(Base::from_name("letters"), None);
+        register(&Letters);
+        let letters = Base::from_name("letters").unwrap();
+        assert_eq!(letters.to_num("bc").ok(), Some(12));
+        assert!(letters.to_num("bz").is_err());
+        assert_eq!(letters.format(305, &Settings::default()), "daf");
+        assert!(names().contains(&"letters".to_string()));
+    }
+
+    #[test]
+    fn test_bin_format() {
+        let settings = Settings::default();
+        assert_eq!(Base::Bin.format(15, &settings), String::from("1111"));
+        assert_eq!(Base::Bin.format(16, &settings), String::from("0001_0000"));
+        assert_eq!(Base::Bin.format(0, &settings), String::from("0"));
+        assert_eq!(Base::Bin.format(1, &settings), String::from("1"));
+        assert_eq!(Base::Bin.format(2, &settings), String::from("10"));
+    }
+
+    #[test]
+    fn test_format_settings() {
+        let mut settings = Settings { width: 16, ..Default::default() };
+        assert_eq!(Base::Hex.format(0x3412, &settings), String::from("0x3412"));
+        assert_eq!(Base::Hex.format(0x12, &settings), String::from("0x0012"));
+        assert_eq!(Base::Bin.format(0x12, &settings), String::from("0000_0000_0001_0010"));
+        assert_eq!(Base::Dec.format(0x12, &settings), String::from("18"));
+        settings.width = 0;
+        settings.group = 8;
+        assert_eq!(Base::Bin.format(0x1ff, &settings), String::from("00000001_11111111"));
+        settings.group = 0;
+        assert_eq!(Base::Bin.format(0x1ff, &settings), String::from("111111111"));
+        settings.uppercase = true;
+        assert_eq!(Base::Hex.format(0xbeef, &settings), String::from("0xBEEF"));
+        settings.style = Style::C;
+        settings.group = 4;
+        assert_eq!(Base::Bin.format(0x1ff, &settings), String::from("0b111111111"));
+        settings.width = 16;
+        assert_eq!(Base::Bin.format(0x1ff, &settings), String::from("0b0000000111111111"));
+        assert_eq!(Base::Dec.format(0x1ff, &settings), String::from("511"));
+    }
+
+    #[test]
+    fn test_verilog() {
+        let mut settings = Settings { style: Style::Verilog, width: 16, uppercase: true, ..Default::default() };
+        assert_eq!(Base::Hex.format(0xbeef, &settings), "16'hBEEF");
+        assert_eq!(Base::Hex.format(0x12, &settings), "16'h0012");
+        assert_eq!(Base::Dec.format(0x12, &settings), "16'd18");
+        settings.width = 8;
+        assert_eq!(Base::Bin.format(0xa1, &settings), "8'b1010_0001");
+        settings.width = 0;
+        assert_eq!(Base::Bin.format(0x11, &settings), "5'b1_0001");
+        assert_eq!(Base::Hex.format(0x1ff, &settings), "9'h1FF");
+        assert_eq!(Base::Hex.format(0, &settings), "1'h0");
+        assert_eq!(Base::Dec.format(0x12, &settings), "18");
+        assert_eq!(Base::Hex.to_num("16'hBEEF").ok(), Some(0xbeef));
+        assert_eq!(Base::Hex.to_num("'shbeef").ok(), Some(0xbeef));
+        assert_eq!(Base::Bin.to_num("8'b1010_0001").ok(), Some(0xa1));
+        assert_eq!(Base::Dec.to_num("16'D18").ok(), Some(18));
+        assert_eq!(Base::Dec.to_num("1'000").ok(), Some(1000));
+        assert!(Base::Bin.to_num("16'hBEEF").is_err());
+    }
+
+    #[test]
+    fn test_vhdl() {
+        let mut settings = Settings { style: Style::Vhdl, uppercase: true, ..Default::default() };
+        assert_eq!(Base::Hex.format(0xbeef, &settings), "x\"BEEF\"");
+        assert_eq!(Base::Bin.format(0xa1, &settings), "b\"1010_0001\"");
+        settings.width = 16;
+        assert_eq!(Base::Hex.format(0xbe, &settings), "x\"00BE\"");
+        assert_eq!(Base::Dec.format(0xbe, &settings), "190");
+        settings.width = 10;
+        assert_eq!(Base::Hex.format(0x1ff, &settings), "10x\"1FF\"");
+        settings.group = 0;
+        assert_eq!(Base::Bin.format(0x1ff, &settings), "\"0111111111\"");
+        settings.others = true;
+        settings.width = 16;
+        assert_eq!(Base::Hex.format(0xbe, &settings), "(15 downto 8 => '0') & x\"BE\"");
+        assert_eq!(Base::Bin.format(0x5, &settings), "(15 downto 3 => '0') & \"101\"");
+        assert_eq!(Base::Hex.format(0, &settings), "(others => '0')");
+        assert_eq!(Base::Hex.format(0xbeef, &settings), "x\"BEEF\"");
+        let oct = Base::from_name("oct").unwrap();
+        settings.others = false;
+        assert_eq!(oct.format(15, &settings), "16o\"000017\"");
+    }
+
+    #[test]
+    fn test_parse_grouped() {
+        let ones = vec!["1111"; 16].join("_");
+        assert_eq!(Base::Bin.to_num(&ones), Ok(u64::MAX));
+        assert!(matches!(Base::Bin.to_num(&format!("1_{}", ones)), Err(ConvertError::Overflow { .. })));
+        assert!(matches!(Base::Bin.to_num("1_2"), Err(ConvertError::InvalidDigit { .. })));
+        assert_eq!(Base::Bin.to_num("_"), Err(ConvertError::Empty));
+        let long = format!("0b{}", "1".repeat(1000));
+        assert!(matches!(Base::Bin.to_num(&long), Err(ConvertError::Overflow { .. })));
+        let bad = format!("{}2", long);
+        assert!(matches!(Base::Bin.to_num(&bad), Err(ConvertError::InvalidDigit { position: Some(1002), .. })));
+        assert!(matches!(Base::Hex.to_num("0xfg"), Err(ConvertError::InvalidDigit { position: Some(3), .. })));
+        assert!(matches!(Base::Hex.to_num("de:ad:bg"), Err(ConvertError::InvalidDigit { position: Some(7), .. })));
+        assert!(matches!(Base::Hex.to_num("0x1ffffffffffffffffg"), Err(ConvertError::InvalidDigit { position: Some(19), .. })));
+        assert!(matches!(Base::Dec.to_num("123456789012345678901234x"), Err(ConvertError::InvalidDigit { position: Some(24), .. })));
+        assert!(matches!(Base::from_name("oct").unwrap().to_num("0o7777777777777777777777778"), Err(ConvertError::InvalidDigit { position: Some(26), .. })));
+    }
+
+    #[test]
+    fn test_format_into() {
+        let mut out = String::from("x=");
+        Base::Hex.format_into(0xff, &Settings::default(), &mut out);
+        assert_eq!(out, "x=0xff");
+        let settings = Settings { width: 6, group: 4, ..Default::default() };
+        for num in [0, 1, 0x3f, 0x1ff, u64::MAX] {
+            for base in [Base::Bin, Base::Dec, Base::Hex, Base::from_name("oct").unwrap()] {
+                let mut out = String::new();
+                base.format_into(num, &settings, &mut out);
+                assert_eq!(out, base.format(num, &settings));
+            }
+        }
+        assert_eq!(Base::Bin.format(0x1ff, &settings), "1_1111_1111");
+        assert_eq!(Base::Bin.format(0, &Settings::default()), "0");
+    }
+}
@@ -0,0 +1,322 @@
+//! Conversion of every value in a file, one value per line.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::str;
+use std::thread;
+use memmap2::Mmap;
+use crate::app::{App, Snapshot};
+use crate::cancel;
+use crate::error::{CommandError, ConvertError};
+use crate::exit;
+use crate::progress::Progress;
+use crate::token;
+
+/// Size of the buffers of the input and output.
+const BUFFER: usize = 1 << 16;
+
+/// Number of lines converted at once, shared between threads.
+const CHUNK: usize = 1 << 14;
+
+/// Outcome of a batch run.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub converted: usize,
+    pub failed: usize,
+    /// Exit code of the first failure, 0 if none.
+    pub code: i32,
+}
+
+/// Convert each line of `input` and write the results to `out`. Failing lines
+/// are reported with their line number through `App::print_error` and do not
+/// stop the run. If `numbered`, results are written as `<line>: <result>`;
+/// otherwise one line is written per input line, left empty for blank or
+/// failing lines, so that line numbers are preserved.
+///
+/// Lines are read in chunks, converted by as many threads as cores into
+/// buffers reused across chunks, and written in order through a buffer, so
+/// that large inputs stream quickly.
+pub fn run(app: &App, input: impl BufRead, out: &mut dyn Write, numbered: bool) -> io::Result<Summary> {
+    read_lines(Batch::new(app, numbered, Progress::hidden()), input, out)
+}
+
+/// Like `run`, on a text already in memory, e.g. a mapped file. Lines are
+/// converted in place, without copying them.
+pub fn run_text(app: &App, text: &str, out: &mut dyn Write, numbered: bool) -> io::Result<Summary> {
+    split_lines(Batch::new(app, numbered, Progress::hidden()), text, out)
+}
+
+fn read_lines(mut batch: Batch, mut input: impl BufRead, out: &mut dyn Write) -> io::Result<Summary> {
+    let mut out = batch.start(out)?;
+    let mut lines = vec![String::new(); CHUNK];
+    loop {
+        let (mut count, mut bytes) = (0, 0);
+        while count < CHUNK {
+            lines[count].clear();
+            let read = input.read_line(&mut lines[count])?;
+            if read == 0 {
+                break;
+            }
+            count += 1;
+            bytes += read;
+        }
+        if count == 0 {
+            break;
+        }
+        let lines: Vec<&str> = lines[..count].iter().map(String::as_str).collect();
+        batch.convert(&lines, &mut out)?;
+        batch.progress.advance(bytes as u64);
+    }
+    out.flush()?;
+    Ok(batch.summary)
+}
+
+fn split_lines(mut batch: Batch, text: &str, out: &mut dyn Write) -> io::Result<Summary> {
+    let mut out = batch.start(out)?;
+    let mut lines = text.split_terminator('\n');
+    let mut chunk: Vec<&str> = Vec::with_capacity(CHUNK);
+    loop {
+        chunk.clear();
+        chunk.extend(lines.by_ref().take(CHUNK));
+        if chunk.is_empty() {
+            break;
+        }
+        batch.convert(&chunk, &mut out)?;
+        batch.progress.advance(chunk.iter().map(|line| line.len() as u64 + 1).sum());
+    }
+    out.flush()?;
+    Ok(batch.summary)
+}
+
+/// An input file of `run`, mapped in memory when possible so that huge
+/// captures are scanned without being read first, and otherwise read
+/// through a buffer, e.g. for pipes.
+pub enum Input {
+    Mapped(Mmap),
+    Buffered(BufReader<File>),
+}
+
+impl Input {
+    /// Convert the lines of the input like `run` or `run_text`. If
+    /// `progress`, the share of the file done is drawn on stderr when it is a
+    /// terminal.
+    pub fn run(self, app: &App, out: &mut dyn Write, numbered: bool, progress: bool) -> io::Result<Summary> {
+        match self {
+            Input::Mapped(map) => {
+                let text = str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                let progress = Progress::new(Some(text.len() as u64), progress);
+                split_lines(Batch::new(app, numbered, progress), text, out)
+            }
+            Input::Buffered(reader) => {
+                let progress = Progress::new(reader.get_ref().metadata().ok().map(|m| m.len()), progress);
+                read_lines(Batch::new(app, numbered, progress), reader, out)
+            }
+        }
+    }
+}
+
+/// State of a batch run across chunks of lines.
+struct Batch<'a> {
+    app: &'a App,
+    snapshot: Snapshot,
+    numbered: bool,
+    /// Results of each thread.
+    chunks: Vec<Chunk>,
+    /// Number of the next line, from 0.
+    first: usize,
+    summary: Summary,
+    progress: Progress,
+}
+
+impl<'a> Batch<'a> {
+    fn new(app: &'a App, numbered: bool, progress: Progress) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Batch {
+            app,
+            snapshot: app.snapshot(),
+            numbered,
+            chunks: (0..threads).map(|_| Chunk::default()).collect(),
+            first: 0,
+            summary: Summary { converted: 0, failed: 0, code: 0 },
+            progress,
+        }
+    }
+
+    /// Buffer `out` and write the header of the output format, if any.
+    fn start<'w>(&self, out: &'w mut dyn Write) -> io::Result<BufWriter<&'w mut dyn Write>> {
+        let mut out = BufWriter::with_capacity(BUFFER, out);
+        if let Some(header) = self.app.header() {
+            writeln!(out, "{}", header)?;
+        }
+        Ok(out)
+    }
+
+    /// Convert the next lines and write their results in order, unless
+    /// cancelled.
+    fn convert(&mut self, lines: &[&str], out: &mut impl Write) -> io::Result<()> {
+        if cancel::cancelled() {
+            out.flush()?;
+            return Err(io::Error::new(io::ErrorKind::Interrupted, format!("cancelled at line {}", self.first + 1)));
+        }
+        let (first, numbered, snapshot) = (self.first, self.numbered, &self.snapshot);
+        // Threads only pay off for full chunks.
+        let size = if lines.len() < CHUNK { lines.len() } else { lines.len().div_ceil(self.chunks.len()) };
+        let used = lines.len().div_ceil(size);
+        let chunks = &mut self.chunks;
+        if used == 1 {
+            chunks[0].convert(snapshot, lines, first, numbered);
+        } else {
+            thread::scope(|scope| {
+                for (i, (chunk, lines)) in chunks.iter_mut().zip(lines.chunks(size)).enumerate() {
+                    scope.spawn(move || chunk.convert(snapshot, lines, first + i * size, numbered));
+                }
+            });
+        }
+        let summary = &mut self.summary;
+        for (i, chunk) in self.chunks[..used].iter_mut().enumerate() {
+            let mut start = 0;
+            for (j, outcome) in chunk.outcomes.drain(..).enumerate() {
+                match outcome {
+                    Outcome::Converted(end) => {
+                        out.write_all(&chunk.text.as_bytes()[start..end])?;
+                        start = end;
+                        summary.converted += 1;
+                    }
+                    Outcome::Blank if numbered => {}
+                    Outcome::Blank => out.write_all(b"\n")?,
+                    Outcome::Failed(e) => {
+                        if !numbered {
+                            out.write_all(b"\n")?;
+                        }
+                        // Errors go to another stream, keep the order of lines.
+                        out.flush()?;
+                        self.progress.clear();
+                        self.app.print_error(&format!("line {}: Error: {}", first + i * size + j + 1, e));
+                        summary.failed += 1;
+                        if summary.code == 0 {
+                            summary.code = exit::code(&e);
+                        }
+                    }
+                }
+            }
+        }
+        self.first += lines.len();
+        Ok(())
+    }
+}
+
+/// Outcome of the conversion of a line.
+#[derive(Debug)]
+enum Outcome {
+    Blank,
+    /// Converted, with the end of its result in the text of the chunk.
+    Converted(usize),
+    Failed(ConvertError),
+}
+
+/// Results of a slice of lines, converted by one thread.
+#[derive(Debug, Default)]
+struct Chunk {
+    /// Results of the converted lines, each ending with a newline.
+    text: String,
+    outcomes: Vec<Outcome>,
+}
+
+impl Chunk {
+    /// Convert `lines`, the first being line `first` from 0, replacing the
+    /// previous results.
+    fn convert(&mut self, snapshot: &Snapshot, lines: &[&str], first: usize, numbered: bool) {
+        self.text.clear();
+        self.outcomes.clear();
+        for (i, line) in lines.iter().enumerate() {
+            let value = token::strip_comment(line.trim());
+            if value.is_empty() {
+                self.outcomes.push(Outcome::Blank);
+                continue;
+            }
+            let start = self.text.len();
+            let converted = if numbered {
+                let _ = write!(self.text, "{}: ", first + i + 1);
+                snapshot.convert(value).map(|conversion| self.text.push_str(&snapshot.render(&conversion, true)))
+            } else {
+                snapshot.render_into(value, &mut self.text)
+            };
+            match converted {
+                Ok(()) => {
+                    self.text.push('\n');
+                    self.outcomes.push(Outcome::Converted(self.text.len()));
+                }
+                Err(e) => {
+                    self.text.truncate(start);
+                    self.outcomes.push(Outcome::Failed(e));
+                }
+            }
+        }
+    }
+}
+
+/// Open a file for `run`.
+pub fn open(path: &str) -> Result<Input, CommandError> {
+    let file = File::open(path).map_err(|e| CommandError::Io(format!("cannot open {}: {}", path, e)))?;
+    // SAFETY: the map is only read during the run, and batch inputs are not
+    // expected to change meanwhile.
+    match unsafe { Mmap::map(&file) } {
+        Ok(map) => Ok(Input::Mapped(map)),
+        Err(_) => Ok(Input::Buffered(BufReader::with_capacity(BUFFER, file))),
+    }
+}
+
+#[cfg(test)]
+mod test_batch {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let app = App::new();
+        let mut out = Vec::new();
+        let summary = run(&app, "ff\n\nzz\n10\n".as_bytes(), &mut out, false).unwrap();
+        assert_eq!(summary, Summary { converted: 2, failed: 1, code: exit::INVALID });
+        assert_eq!(String::from_utf8(out).unwrap(), "1111_1111\n\n\n0001_0000\n");
+
+        let mut out = Vec::new();
+        let summary = run(&app, "ff\nzz\n10".as_bytes(), &mut out, true).unwrap();
+        assert_eq!(summary, Summary { converted: 2, failed: 1, code: exit::INVALID });
+        assert_eq!(String::from_utf8(out).unwrap(), "1: <bin> 1111_1111\n3: <bin> 0001_0000\n");
+
+        let mut out = Vec::new();
+        let summary = run(&app, "# masks\nff // low byte\n10 # bit 4\n".as_bytes(), &mut out, false).unwrap();
+        assert_eq!(summary, Summary { converted: 2, failed: 0, code: 0 });
+        assert_eq!(String::from_utf8(out).unwrap(), "\n1111_1111\n0001_0000\n");
+    }
+
+    #[test]
+    fn test_run_chunks() {
+        let app = App::new();
+        let input: String = (0..CHUNK * 2 + 3).map(|i| if i == CHUNK + 1 { "zz\n".to_string() } else { format!("{:x}\n", i % 4) }).collect();
+        let mut out = Vec::new();
+        let summary = run(&app, input.as_bytes(), &mut out, false).unwrap();
+        assert_eq!(summary, Summary { converted: CHUNK * 2 + 2, failed: 1, code: exit::INVALID });
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), CHUNK * 2 + 3);
+        assert_eq!(&lines[CHUNK - 1..CHUNK + 3], &["11", "0", "", "10"]);
+        assert_eq!(lines[CHUNK * 2 + 2], "10");
+    }
+
+    #[test]
+    fn test_open() {
+        let app = App::new();
+        let path = std::env::temp_dir().join(format!("base_converter_batch_{}.txt", std::process::id()));
+        for (input, expected) in [("ff\r\n\nzz\n10", "1111_1111\n\n\n0001_0000\n"), ("", "")] {
+            std::fs::write(&path, input).unwrap();
+            let input = open(path.to_str().unwrap()).unwrap();
+            assert!(matches!(input, Input::Mapped(_)));
+            let mut out = Vec::new();
+            input.run(&app, &mut out, false, false).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert!(open("/nonexistent/in.txt").is_err());
+    }
+}
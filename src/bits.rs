@@ -0,0 +1,123 @@
+//! Analysis of the bits of a value: the runs of `:runs` and the mask check
+//! of `:check`.
+
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+/// Register the `:runs` and `:check` commands.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "runs",
+        help: &[(":runs <value> [<bits>]", "list runs of consecutive 1s and 0s, from the top bit")],
+        completions: &[],
+        run: runs_command,
+    });
+    registry.register(Plugin {
+        name: "check",
+        help: &[(":check <value> <mask>", "fail if the value sets bits outside the mask")],
+        completions: &[],
+        run: check_command,
+    });
+}
+
+/// Handle `:check <value> <mask>`: the bits of the value outside the mask,
+/// e.g. reserved bits, are an error.
+pub fn check_command(args: &[&str], parse: ArgParser) -> Result<String, String> {
+    let (value, mask) = match args {
+        [value, mask] => (parse(value).map_err(|e| e.to_string())?, parse(mask).map_err(|e| e.to_string())?),
+        _ => return Err("Usage: :check <value> <mask>".to_string()),
+    };
+    let outside = value & !mask;
+    if outside == 0 {
+        return Ok(format!("ok, 0x{:x} is within mask 0x{:x}", value, mask));
+    }
+    let positions: Vec<String> = (0..64).rev().filter(|i| outside >> i & 1 == 1).map(|i: u32| i.to_string()).collect();
+    let plural = if positions.len() == 1 { "" } else { "s" };
+    Err(format!("0x{:x} sets bit{} {} outside mask 0x{:x} (0x{:x})", value, plural, positions.join(", "), mask, outside))
+}
+
+/// Handle `:runs <value> [<bits>]`.
+pub fn runs_command(args: &[&str], parse: ArgParser) -> Result<String, String> {
+    let (value, bits) = match args {
+        [value] => (parse(value).map_err(|e| e.to_string())?, None),
+        [value, bits] => {
+            let bits = bits.parse::<u32>().ok().filter(|bits| (1..=64).contains(bits))
+                .ok_or_else(|| format!("Invalid width {}, expect 1 to 64", bits))?;
+            (parse(value).map_err(|e| e.to_string())?, Some(bits))
+        }
+        _ => return Err("Usage: :runs <value> [<bits>]".to_string()),
+    };
+    let bits = bits.unwrap_or((64 - value.leading_zeros()).max(1));
+    if bits < 64 && value >> bits != 0 {
+        return Err(format!("0x{:x} does not fit in {} bits", value, bits));
+    }
+    Ok(describe_runs(value, bits))
+}
+
+/// Runs of equal bits in the `bits` low bits of `value`, from the top, as
+/// (bit, msb, lsb).
+pub fn runs(value: u64, bits: u32) -> Vec<(u64, u32, u32)> {
+    let mut runs: Vec<(u64, u32, u32)> = Vec::new();
+    for i in (0..bits).rev() {
+        let bit = value >> i & 1;
+        match runs.last_mut() {
+            Some((last, _, lsb)) if *last == bit => *lsb = i,
+            _ => runs.push((bit, i, i)),
+        }
+    }
+    runs
+}
+
+/// One line per run, e.g. `31:28  4 ones`, and a summary.
+fn describe_runs(value: u64, bits: u32) -> String {
+    let runs = runs(value, bits);
+    let mut out = String::new();
+    for (bit, msb, lsb) in &runs {
+        let range = if msb == lsb { msb.to_string() } else { format!("{}:{}", msb, lsb) };
+        let len = msb - lsb + 1;
+        let name = match (bit, len) {
+            (1, 1) => "one",
+            (1, _) => "ones",
+            (_, 1) => "zero",
+            _ => "zeros",
+        };
+        out += &format!("{:6} {} {}\n", range, len, name);
+    }
+    let summary = |bit: u64, name: &str| {
+        let lens: Vec<u32> = runs.iter().filter(|run| run.0 == bit).map(|(_, msb, lsb)| msb - lsb + 1).collect();
+        format!("{} runs of {}, longest {}", lens.len(), name, lens.iter().max().unwrap_or(&0))
+    };
+    out + &format!("{}; {}", summary(1, "ones"), summary(0, "zeros"))
+}
+
+#[cfg(test)]
+mod test_bits {
+    use super::*;
+    use crate::testing::run;
+
+    #[test]
+    fn test_runs() {
+        assert_eq!(runs(0b11001, 5), vec![(1, 4, 3), (0, 2, 1), (1, 0, 0)]);
+        assert_eq!(runs(0, 3), vec![(0, 2, 0)]);
+        assert_eq!(runs(u64::MAX, 64), vec![(1, 63, 0)]);
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(run(check_command, "31 f3"), Ok("ok, 0x31 is within mask 0xf3".to_string()));
+        assert_eq!(run(check_command, "235 f3"), Err("0x235 sets bits 9, 2 outside mask 0xf3 (0x204)".to_string()));
+        assert_eq!(run(check_command, "8 7"), Err("0x8 sets bit 3 outside mask 0x7 (0x8)".to_string()));
+        assert!(run(check_command, "1").is_err());
+    }
+
+    #[test]
+    fn test_command() {
+        let out = run(runs_command, "f0f0f0f0").unwrap();
+        assert!(out.starts_with("31:28  4 ones\n27:24  4 zeros\n"));
+        assert!(out.ends_with("3:0    4 zeros\n4 runs of ones, longest 4; 4 runs of zeros, longest 4"));
+        assert_eq!(run(runs_command, "1 4").unwrap(), "3:1    3 zeros\n0      1 one\n1 runs of ones, longest 1; 1 runs of zeros, longest 3");
+        assert!(run(runs_command, "1ff 8").is_err());
+        assert!(run(runs_command, "1 65").is_err());
+        assert!(run(runs_command, "").is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! Conversion between short (16/32-bit) and full 128-bit Bluetooth UUIDs.
+
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+/// The Bluetooth Base UUID, `00000000-0000-1000-8000-00805f9b34fb`.
+const BASE_UUID: u128 = 0x0000_0000_0000_1000_8000_0080_5f9b_34fb;
+
+/// Register the `:uuid` command.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "uuid",
+        help: &[(":uuid <uuid>", "convert a BLE UUID between short and 128-bit form")],
+        completions: &[],
+        run: command,
+    });
+}
+
+/// Handle `:uuid <uuid>`. A 128-bit UUID is recognized by its dashes or its
+/// 32 hex digits, anything else is read as a short UUID.
+pub fn command(args: &[&str], parse: ArgParser) -> Result<String, String> {
+    let arg = match args {
+        [arg] => *arg,
+        _ => return Err("Usage: :uuid <16/32-bit or 128-bit uuid>".to_string()),
+    };
+    let digits = arg.replace("-", "");
+    if arg.contains('-') || digits.len() == 32 {
+        if digits.len() != 32 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("{} is not a 128-bit UUID", arg));
+        }
+        let uuid = u128::from_str_radix(&digits, 16).map_err(|e| e.to_string())?;
+        match to_short(uuid) {
+            Some(short) => Ok(format!("{}\n{}", format_long(uuid), format_short(short))),
+            None => Ok(format!("{}\nnot based on the Bluetooth Base UUID", format_long(uuid))),
+        }
+    } else {
+        let short = parse(arg).map_err(|e| e.to_string())?;
+        if short > 0xffff_ffff {
+            return Err("short UUID must fit in 32 bits".to_string());
+        }
+        Ok(format!("{}\n{}", format_short(short as u32), format_long(to_long(short as u32))))
+    }
+}
+
+/// Expand a short UUID to 128 bits.
+fn to_long(short: u32) -> u128 {
+    BASE_UUID | ((short as u128) << 96)
+}
+
+/// Shorten a 128-bit UUID, if it is derived from the Base UUID.
+fn to_short(uuid: u128) -> Option<u32> {
+    if uuid & !(0xffff_ffff << 96) == BASE_UUID {
+        Some((uuid >> 96) as u32)
+    } else {
+        None
+    }
+}
+
+fn format_short(short: u32) -> String {
+    if short <= 0xffff {
+        format!("0x{:04x} (16-bit)", short)
+    } else {
+        format!("0x{:08x} (32-bit)", short)
+    }
+}
+
+fn format_long(uuid: u128) -> String {
+    let hex = format!("{:032x}", uuid);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+#[cfg(test)]
+mod test_ble {
+    use super::*;
+    use crate::testing::hex;
+
+    #[test]
+    fn test_uuid() {
+        assert_eq!(command(&["0x180D"], &hex).unwrap(), "0x180d (16-bit)\n0000180d-0000-1000-8000-00805f9b34fb");
+        assert_eq!(command(&["12345678"], &hex).unwrap(), "0x12345678 (32-bit)\n12345678-0000-1000-8000-00805f9b34fb");
+        assert_eq!(command(&["0000180D-0000-1000-8000-00805F9B34FB"], &hex).unwrap(), "0000180d-0000-1000-8000-00805f9b34fb\n0x180d (16-bit)");
+        assert_eq!(command(&["6e400001b5a3f393e0a9e50e24dcca9e"], &hex).unwrap(), "6e400001-b5a3-f393-e0a9-e50e24dcca9e\nnot based on the Bluetooth Base UUID");
+        assert!(command(&["0000180d-0000-1000"], &hex).is_err());
+        assert!(command(&["+000180d-0000-1000-8000-00805f9b34fb"], &hex).is_err());
+        assert!(command(&["+000180d00001000800000805f9b34fb"], &hex).is_err());
+        assert!(command(&["123456789"], &hex).is_err());
+        assert!(command(&[], &hex).is_err());
+    }
+}
@@ -0,0 +1,438 @@
+//! Byte sequences given as hex, e.g. `de ad be ef`, `0xde,0xad` or
+//! `deadbeef`, and the commands on them: `:stats`, `:detect`, which guesses
+//! the format of a pasted blob, and `:diffbytes`.
+
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+/// Width of the longest histogram bar.
+const BAR: usize = 32;
+
+/// Formats of blobs recognized by `:detect`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    IntelHex,
+    Hex,
+    Decimal,
+    Base64,
+    Ascii,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::IntelHex => "Intel HEX",
+            Format::Hex => "hex bytes",
+            Format::Decimal => "decimal list",
+            Format::Base64 => "Base64",
+            Format::Ascii => "ASCII",
+        }
+    }
+}
+
+/// Register the `:stats`, `:detect` and `:diffbytes` commands.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "stats",
+        help: &[(":stats <bytes>", "byte statistics and entropy, e.g. :stats de ad be ef")],
+        completions: &[],
+        run: stats_command,
+    });
+    registry.register(Plugin {
+        name: "detect",
+        help: &[(":detect <blob>", "guess hex, Base64, ASCII, Intel HEX or decimal bytes")],
+        completions: &[],
+        run: detect_command,
+    });
+    registry.register(Plugin {
+        name: "diffbytes",
+        help: &[(":diffbytes <bytes> <bytes>", "offsets where byte sequences differ, quote bytes with spaces")],
+        completions: &[],
+        run: diff_command,
+    });
+}
+
+/// Split words further at spaces and commas.
+fn tokens<'a>(words: &'a [&str]) -> impl Iterator<Item = &'a str> {
+    words.iter().flat_map(|word| word.split(|c: char| c == ',' || c.is_whitespace())).filter(|word| !word.is_empty())
+}
+
+/// Read hex bytes from words separated by spaces or commas. Words of 1 or 2
+/// digits are single bytes, longer ones are split in pairs of digits.
+pub fn parse_hex(words: &[&str]) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for word in tokens(words) {
+        let digits = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")).unwrap_or(word);
+        let invalid = || format!("invalid hex bytes {}", word);
+        match digits.len() {
+            0 => return Err(invalid()),
+            1 => bytes.push(u8::from_str_radix(digits, 16).map_err(|_| invalid())?),
+            len if len % 2 == 1 => return Err(invalid()),
+            _ => decode_hex(digits, &mut bytes).map_err(|_| invalid())?,
+        }
+    }
+    Ok(bytes)
+}
+
+/// Digit pairs of every byte, e.g. `ff` at 510.
+const HEX_PAIRS: [u8; 512] = hex_pairs();
+
+/// Value of every character as a hex digit, 0xff if it is not one.
+const NIBBLES: [u8; 256] = nibbles();
+
+const fn hex_pairs() -> [u8; 512] {
+    let digits = b"0123456789abcdef";
+    let mut pairs = [0; 512];
+    let mut i = 0;
+    while i < 256 {
+        pairs[2 * i] = digits[i >> 4];
+        pairs[2 * i + 1] = digits[i & 0xf];
+        i += 1;
+    }
+    pairs
+}
+
+const fn nibbles() -> [u8; 256] {
+    let mut nibbles = [0xff; 256];
+    let mut i = 0;
+    while i < 16 {
+        nibbles[b"0123456789abcdef"[i] as usize] = i as u8;
+        nibbles[b"0123456789ABCDEF"[i] as usize] = i as u8;
+        i += 1;
+    }
+    nibbles
+}
+
+/// Append the bytes of an even number of hex digits to `out`, 16 digits at
+/// a time and checked once per chunk. On error, return the position of the
+/// first invalid digit.
+pub fn decode_hex(digits: &str, out: &mut Vec<u8>) -> Result<(), usize> {
+    let digits = digits.as_bytes();
+    if digits.len() % 2 == 1 {
+        return Err(digits.len() - 1);
+    }
+    out.reserve(digits.len() / 2);
+    for (i, chunk) in digits.chunks(16).enumerate() {
+        let mut bytes = [0; 8];
+        // Any invalid digit sets the high bits.
+        let mut invalid = 0;
+        for (byte, pair) in bytes.iter_mut().zip(chunk.chunks_exact(2)) {
+            let (high, low) = (NIBBLES[pair[0] as usize], NIBBLES[pair[1] as usize]);
+            invalid |= high | low;
+            *byte = high << 4 | low;
+        }
+        if invalid & 0xf0 != 0 {
+            let position = chunk.iter().position(|digit| NIBBLES[*digit as usize] == 0xff).unwrap_or(0);
+            return Err(i * 16 + position);
+        }
+        out.extend_from_slice(&bytes[..chunk.len() / 2]);
+    }
+    Ok(())
+}
+
+/// Append bytes as pairs of hex digits to `out`, with `separator` between
+/// bytes, through a table of all pairs.
+pub fn encode_hex(bytes: &[u8], separator: &str, out: &mut String) {
+    out.reserve(bytes.len() * (2 + separator.len()));
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push_str(separator);
+        }
+        let pair = &HEX_PAIRS[*byte as usize * 2..*byte as usize * 2 + 2];
+        out.push(pair[0] as char);
+        out.push(pair[1] as char);
+    }
+}
+
+/// Read bytes written in decimal, e.g. `222, 173` or `222 173`.
+pub fn parse_decimal(words: &[&str]) -> Result<Vec<u8>, String> {
+    tokens(words)
+        .map(|word| word.parse::<u8>().map_err(|_| format!("invalid decimal byte {}", word)))
+        .collect()
+}
+
+/// Decode Base64, with or without `=` padding.
+pub fn parse_base64(text: &str) -> Result<Vec<u8>, String> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let data = text.trim_end_matches('=');
+    if data.is_empty() || text.len() - data.len() > 2 || data.len() % 4 == 1 {
+        return Err(format!("invalid Base64 {}", text));
+    }
+    let mut bytes = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in data.bytes() {
+        let digit = ALPHABET.iter().position(|a| *a == c).ok_or_else(|| format!("invalid Base64 {}", text))?;
+        acc = acc << 6 | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Read the data of Intel HEX records, e.g. `:0300300002337A1E`, checking
+/// their checksums. Only data records (type 00) give bytes.
+pub fn parse_intel_hex(words: &[&str]) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    for word in words {
+        let invalid = || format!("invalid Intel HEX record {}", word);
+        let record = word.strip_prefix(':').ok_or_else(invalid)?;
+        let bytes = parse_hex(&[record]).map_err(|_| invalid())?;
+        if record.len() % 2 == 1 || bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(invalid());
+        }
+        if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(format!("bad checksum in Intel HEX record {}", word));
+        }
+        if bytes[3] == 0 {
+            data.extend_from_slice(&bytes[4..bytes.len() - 1]);
+        }
+    }
+    Ok(data)
+}
+
+/// Interpretations of a blob, the most likely first, with their bytes.
+pub fn detect(words: &[&str]) -> Vec<(Format, Vec<u8>)> {
+    let text = words.join(" ");
+    let tokens: Vec<&str> = tokens(words).collect();
+    let mut found: Vec<(u32, Format, Vec<u8>)> = Vec::new();
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+    if let Ok(bytes) = parse_intel_hex(words) {
+        found.push((100, Format::IntelHex, bytes));
+    }
+    if let Ok(bytes) = parse_hex(words) {
+        let letters = text.contains("0x") || text.contains(|c: char| c.is_ascii_alphabetic());
+        found.push((if letters { 80 } else { 50 }, Format::Hex, bytes));
+    }
+    if let Ok(bytes) = parse_decimal(words) {
+        let pairs = tokens.iter().all(|token| token.len() == 2);
+        found.push((if pairs { 40 } else { 60 }, Format::Decimal, bytes));
+    }
+    if let Ok(bytes) = parse_base64(&text) {
+        let mixed = text.contains(|c: char| c.is_ascii_uppercase()) && text.contains(|c: char| c.is_ascii_lowercase());
+        let symbols = text.contains(['=', '+', '/']);
+        found.push((if mixed || symbols { 70 } else { 30 }, Format::Base64, bytes));
+    }
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        found.push((10, Format::Ascii, text.into_bytes()));
+    }
+    found.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+    found.into_iter().map(|(_, format, bytes)| (format, bytes)).collect()
+}
+
+/// Handle `:detect <blob>`.
+pub fn detect_command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    let found = detect(args);
+    let (format, bytes) = found.first().ok_or("Usage: :detect <blob>, e.g. :detect 3q2+7w==")?;
+    let mut out = format!("detected    {}\nbytes       {}\nascii       {}", format.name(), hex_list(bytes), ascii(bytes));
+    for (format, bytes) in &found[1..] {
+        out += &format!("\nalso        {}: {}", format.name(), hex_list(bytes));
+    }
+    Ok(out)
+}
+
+/// Bytes as a hex list, e.g. `de ad be ef`.
+pub fn hex_list(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    encode_hex(bytes, " ", &mut out);
+    out
+}
+
+/// Bytes as text, with `.` for the bytes that are not printable.
+pub fn ascii(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' }).collect()
+}
+
+/// Handle `:diffbytes <bytes> <bytes>`.
+pub fn diff_command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    match args {
+        [a, b] => Ok(diff(&parse_hex(&[a])?, &parse_hex(&[b])?)),
+        _ => Err("Usage: :diffbytes <bytes> <bytes>, e.g. :diffbytes deadbeef \"de ad be ff\"".to_string()),
+    }
+}
+
+/// Byte in binary, with its nibbles separated, e.g. `1011_1110`.
+fn bin(byte: u8) -> String {
+    format!("{:04b}_{:04b}", byte >> 4, byte & 0xf)
+}
+
+/// One line per offset where `a` and `b` differ, with both bytes and their
+/// XOR, and a summary. Bytes past the end of the shorter one are `--`.
+fn diff(a: &[u8], b: &[u8]) -> String {
+    let mut out = String::from("offset  a   b   a (bin)    b (bin)    xor\n");
+    let mut count = 0;
+    for offset in 0..a.len().max(b.len()) {
+        let (x, y) = (a.get(offset), b.get(offset));
+        if x == y {
+            continue;
+        }
+        count += 1;
+        let hex = |byte: Option<&u8>| byte.map_or("--".to_string(), |byte| format!("{:02x}", byte));
+        let binary = |byte: Option<&u8>| byte.map_or("-".repeat(9), |byte| bin(*byte));
+        let xor = match (x, y) {
+            (Some(x), Some(y)) => bin(x ^ y),
+            _ => String::new(),
+        };
+        let line = format!("0x{:04x}  {}  {}  {}  {}  {}", offset, hex(x), hex(y), binary(x), binary(y), xor);
+        out += line.trim_end();
+        out += "\n";
+    }
+    if count == 0 {
+        return format!("identical, {} bytes", a.len());
+    }
+    out += &format!("{} of {} bytes differ", count, a.len().max(b.len()));
+    if a.len() != b.len() {
+        out += &format!(", lengths {} and {}", a.len(), b.len());
+    }
+    out
+}
+
+/// Shannon entropy in bits per byte, from 0 for a repeated byte to 8 for
+/// uniformly random bytes.
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    let len = bytes.len() as f64;
+    counts.iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Handle `:stats <bytes>`.
+pub fn stats_command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    let bytes = parse_hex(args)?;
+    if bytes.is_empty() {
+        return Err("Usage: :stats <bytes>, e.g. :stats de ad be ef".to_string());
+    }
+    Ok(stats(&bytes))
+}
+
+/// Length, range, entropy and histogram of bytes in 16 buckets.
+fn stats(bytes: &[u8]) -> String {
+    let mut distinct = [false; 256];
+    let mut buckets = [0usize; 16];
+    for byte in bytes {
+        distinct[*byte as usize] = true;
+        buckets[*byte as usize >> 4] += 1;
+    }
+    let entropy = entropy(bytes);
+    let guess = if entropy < 1.0 {
+        "mostly one value, e.g. padding or erased flash"
+    } else if entropy > 7.5 {
+        "close to random, e.g. encrypted or compressed"
+    } else {
+        "structured, e.g. code, text or tables"
+    };
+    let mut out = format!("length      {} bytes\n", bytes.len());
+    out += &format!("min         0x{:02x}\n", bytes.iter().min().unwrap_or(&0));
+    out += &format!("max         0x{:02x}\n", bytes.iter().max().unwrap_or(&0));
+    out += &format!("distinct    {}\n", distinct.iter().filter(|seen| **seen).count());
+    out += &format!("entropy     {:.3} bits/byte, {}\n", entropy, guess);
+    out += "histogram";
+    let most = *buckets.iter().max().unwrap_or(&1);
+    for (i, count) in buckets.iter().enumerate() {
+        let bar = "#".repeat((count * BAR).div_ceil(most));
+        out += &format!("\n  {:02x}-{:02x}  {:5} {}", i << 4, i << 4 | 0xf, count, bar);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test_bytes {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex(&["de", "ad"]), Ok(vec![0xde, 0xad]));
+        assert_eq!(parse_hex(&["0xde,0xad,", "beef"]), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(parse_hex(&["a", "0x1"]), Ok(vec![0xa, 0x1]));
+        assert!(parse_hex(&["abc"]).is_err());
+        assert!(parse_hex(&["xy"]).is_err());
+        assert!(parse_hex(&["0x"]).is_err());
+    }
+
+    #[test]
+    fn test_hex_codec() {
+        let bytes: Vec<u8> = (0..1 << 16).map(|i: u32| ((i * 31) >> 5) as u8).collect();
+        let mut hex = String::new();
+        encode_hex(&bytes, "", &mut hex);
+        assert_eq!(hex, bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>());
+        let mut decoded = Vec::new();
+        assert_eq!(decode_hex(&hex.to_uppercase(), &mut decoded), Ok(()));
+        assert_eq!(decoded, bytes);
+        assert_eq!(decode_hex("00112233445566778899aabbccddeeffg0", &mut decoded), Err(32));
+        assert_eq!(decode_hex("0011223344556677x899", &mut decoded), Err(16));
+        assert_eq!(decode_hex("0g", &mut decoded), Err(1));
+        assert_eq!(decode_hex("012", &mut decoded), Err(2));
+        assert_eq!(hex_list(&[0xde, 0xad]), "de ad");
+    }
+
+    #[test]
+    fn test_parsers() {
+        assert_eq!(parse_decimal(&["222,", "173"]), Ok(vec![222, 173]));
+        assert!(parse_decimal(&["256"]).is_err());
+        assert_eq!(parse_base64("3q2+7w=="), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(parse_base64("3q2+7w"), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(parse_base64("TWFu"), Ok(b"Man".to_vec()));
+        assert!(parse_base64("3q2+7").is_err());
+        assert!(parse_base64("3q-+").is_err());
+        assert_eq!(parse_intel_hex(&[":0300300002337A1E", ":00000001FF"]), Ok(vec![0x02, 0x33, 0x7a]));
+        assert!(parse_intel_hex(&[":0300300002337A1F"]).is_err());
+        assert!(parse_intel_hex(&[":03003000"]).is_err());
+    }
+
+    #[test]
+    fn test_detect() {
+        let formats = |blob: &str| detect(&blob.split_whitespace().collect::<Vec<_>>()).into_iter().map(|(format, _)| format).collect::<Vec<_>>();
+        assert_eq!(formats(":0300300002337A1E :00000001FF")[0], Format::IntelHex);
+        assert_eq!(formats("de ad be ef")[0], Format::Hex);
+        assert_eq!(formats("3q2+7w==")[0], Format::Base64);
+        assert_eq!(formats("222, 173, 190, 239")[0], Format::Decimal);
+        assert_eq!(formats("12 34"), vec![Format::Hex, Format::Decimal, Format::Ascii]);
+        assert_eq!(formats("hello world!")[0], Format::Ascii);
+        assert!(formats("").is_empty());
+
+        let out = detect_command(&["3q2+7w=="], &|_| Ok(0)).unwrap();
+        assert_eq!(out, "detected    Base64\nbytes       de ad be ef\nascii       ....\nalso        ASCII: 33 71 32 2b 37 77 3d 3d");
+    }
+
+    #[test]
+    fn test_diff() {
+        let out = diff_command(&["deadbeef", "de ad be ff"], &|_| Ok(0)).unwrap();
+        assert_eq!(out, "offset  a   b   a (bin)    b (bin)    xor\n0x0003  ef  ff  1110_1111  1111_1111  0001_0000\n1 of 4 bytes differ");
+        let out = diff_command(&["0xde,0xad", "dead01"], &|_| Ok(0)).unwrap();
+        assert!(out.ends_with("0x0002  --  01  ---------  0000_0001\n1 of 3 bytes differ, lengths 2 and 3"));
+        assert_eq!(diff_command(&["dead", "de,ad"], &|_| Ok(0)), Ok("identical, 2 bytes".to_string()));
+        assert!(diff_command(&["dead"], &|_| Ok(0)).is_err());
+        assert!(diff_command(&["dead", "xx"], &|_| Ok(0)).is_err());
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy(&[0xff; 16]), 0.0);
+        assert_eq!(entropy(&[0, 1]), 1.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(entropy(&all), 8.0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let out = stats_command(&["00", "00", "ff", "10"], &|_| Ok(0)).unwrap();
+        assert!(out.starts_with("length      4 bytes\nmin         0x00\nmax         0xff\ndistinct    3\n"));
+        assert!(out.contains("entropy     1.500 bits/byte, structured"));
+        assert!(out.contains("\n  00-0f      2 ################################\n  10-1f      1 ################\n"));
+        assert!(out.ends_with("\n  f0-ff      1 ################"));
+        assert!(stats_command(&[], &|_| Ok(0)).is_err());
+    }
+}
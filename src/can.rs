@@ -0,0 +1,134 @@
+//! Decoder for CAN identifiers, with optional J1939 and ISO-TP interpretation.
+
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+/// Largest standard (11-bit) identifier.
+const STD_MAX: u64 = 0x7ff;
+/// Largest extended (29-bit) identifier.
+const EXT_MAX: u64 = 0x1fff_ffff;
+
+/// Register the `:can` command.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "can",
+        help: &[(":can <id> [j1939|isotp]", "decode a CAN identifier")],
+        completions: &["j1939", "isotp"],
+        run: command,
+    });
+}
+
+/// Handle `:can <id> [j1939|isotp]`.
+pub fn command(args: &[&str], parse: ArgParser) -> Result<String, String> {
+    let (id, mode) = match args {
+        [id] => (id, None),
+        [id, mode] => (id, Some(*mode)),
+        _ => return Err("Usage: :can <id> [j1939|isotp]".to_string()),
+    };
+    let id = parse(id).map_err(|e| e.to_string())?;
+    match mode {
+        None => describe(id),
+        Some("j1939") => j1939(id),
+        Some("isotp") => isotp(id),
+        Some(m) => Err(format!("No CAN decode mode {}", m)),
+    }
+}
+
+/// Tell whether an identifier is standard or extended.
+fn describe(id: u64) -> Result<String, String> {
+    if id <= STD_MAX {
+        Ok(format!("standard id 0x{:03x} (11-bit)", id))
+    } else if id <= EXT_MAX {
+        Ok(format!("extended id 0x{:08x} (29-bit)", id))
+    } else {
+        Err(format!("0x{:x} is not a valid CAN identifier (max 29 bits)", id))
+    }
+}
+
+/// Split an extended identifier into J1939 fields.
+fn j1939(id: u64) -> Result<String, String> {
+    let mut out = describe(id)?;
+    if id <= STD_MAX {
+        return Err("J1939 requires an extended (29-bit) identifier".to_string());
+    }
+    let priority = (id >> 26) & 0x7;
+    let dp = (id >> 24) & 0x3;
+    let pf = (id >> 16) & 0xff;
+    let ps = (id >> 8) & 0xff;
+    let sa = id & 0xff;
+    out += &format!("\npriority    {}", priority);
+    if pf < 240 {
+        let pgn = (dp << 16) | (pf << 8);
+        out += &format!("\npgn         0x{:05x} ({})", pgn, pgn);
+        out += &format!("\npdu format  0x{:02x} (PDU1, destination 0x{:02x})", pf, ps);
+    } else {
+        let pgn = (dp << 16) | (pf << 8) | ps;
+        out += &format!("\npgn         0x{:05x} ({})", pgn, pgn);
+        out += &format!("\npdu format  0x{:02x} (PDU2, group extension 0x{:02x})", pf, ps);
+    }
+    out += &format!("\nsource      0x{:02x}", sa);
+    Ok(out)
+}
+
+/// Show the ISO-TP (ISO 15765-2) addressing encoded in an identifier.
+fn isotp(id: u64) -> Result<String, String> {
+    let mut out = describe(id)?;
+    let pf = (id >> 16) & 0xff;
+    let kind = match pf {
+        _ if id <= STD_MAX => None,
+        0xda => Some("normal fixed addressing, physical"),
+        0xdb => Some("normal fixed addressing, functional"),
+        0xce => Some("mixed addressing, physical"),
+        0xcd => Some("mixed addressing, functional"),
+        _ => None,
+    };
+    match kind {
+        Some(kind) => {
+            out += &format!("\nisotp       {}", kind);
+            out += &format!("\ntarget      0x{:02x}", (id >> 8) & 0xff);
+            out += &format!("\nsource      0x{:02x}", id & 0xff);
+        }
+        None => {
+            out += "\nisotp       normal addressing, no address information in the identifier";
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test_can {
+    use super::*;
+    use crate::testing::run;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(run(command, "7df").unwrap(), "standard id 0x7df (11-bit)");
+        assert_eq!(run(command, "18daf110").unwrap(), "extended id 0x18daf110 (29-bit)");
+        assert!(run(command, "20000000").is_err());
+        assert!(run(command, "7df obd").is_err());
+    }
+
+    #[test]
+    fn test_j1939() {
+        let out = run(command, "18daf110 j1939").unwrap();
+        assert!(out.contains("priority    6"));
+        assert!(out.contains("pgn         0x0da00 (55808)"));
+        assert!(out.contains("destination 0xf1"));
+        assert!(out.contains("source      0x10"));
+
+        let out = run(command, "0cf00400 j1939").unwrap();
+        assert!(out.contains("pgn         0x0f004 (61444)"));
+        assert!(out.contains("PDU2"));
+
+        assert!(run(command, "7df j1939").is_err());
+    }
+
+    #[test]
+    fn test_isotp() {
+        let out = run(command, "18daf110 isotp").unwrap();
+        assert!(out.contains("normal fixed addressing, physical"));
+        assert!(out.contains("target      0xf1"));
+        let out = run(command, "7e0 isotp").unwrap();
+        assert!(out.contains("normal addressing"));
+    }
+}
@@ -0,0 +1,29 @@
+//! Cancellation of long operations, e.g. a batch conversion or the CRC of a
+//! large file, with Ctrl+C. At the prompt, Ctrl+C sets a flag that these
+//! operations poll, and they return to the prompt instead of the program
+//! being killed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Handle Ctrl+C by setting the flag instead of stopping the program.
+#[cfg(feature = "cli")]
+pub fn install() -> Result<(), String> {
+    ctrlc::set_handler(cancel).map_err(|e| format!("cannot handle Ctrl+C: {}", e))
+}
+
+/// Ask the running operation to stop.
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether the running operation should stop.
+pub fn cancelled() -> bool {
+    CANCELLED.load(Ordering::Relaxed)
+}
+
+/// Clear the flag before an operation.
+pub fn reset() {
+    CANCELLED.store(false, Ordering::SeqCst);
+}
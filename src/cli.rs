@@ -0,0 +1,268 @@
+//! Command line arguments. With values to convert, the program prints the
+//! results and exits instead of starting the interactive prompt.
+
+/// Name of the program, as used in completion scripts.
+const BIN: &str = "base_converter";
+/// Shells `completions` can generate a script for.
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// A flag taking a value, or a switch turning a setting on if `arg` is empty.
+pub struct Flag {
+    pub name: &'static str,
+    /// Setting the flag changes, or the name of a flag that is not a setting,
+    /// e.g. "file" for `--file`.
+    pub key: &'static str,
+    pub arg: &'static str,
+    pub desc: &'static str,
+    /// Accepted values, for completion. Empty if free-form.
+    pub values: &'static [&'static str],
+}
+
+pub const FLAGS: &[Flag] = &[
+    Flag { name: "--file", key: "file", arg: "<path>", desc: "convert every line of a file, - for stdin", values: &[] },
+    Flag { name: "--from", key: "from", arg: "<base>", desc: "input base: hex, dec, bin, oct", values: &["hex", "dec", "bin", "oct"] },
+    Flag { name: "--to", key: "to", arg: "<base>", desc: "output base: hex, dec, bin, oct", values: &["hex", "dec", "bin", "oct"] },
+    Flag { name: "--width", key: "width", arg: "<bits>", desc: "pad output to <bits> bits", values: &[] },
+    Flag { name: "--style", key: "style", arg: "<style>", desc: "number syntax: plain, c, verilog, vhdl", values: &["plain", "c", "verilog", "vhdl"] },
+    Flag {
+        name: "--output",
+        key: "format",
+        arg: "<format>",
+        desc: "result format: text, json, csv, tsv",
+        values: &["text", "json", "csv", "tsv"],
+    },
+    Flag { name: "--raw", key: "quiet", arg: "", desc: "print only the converted values", values: &[] },
+    Flag { name: "--no-rc", key: "no-rc", arg: "", desc: "do not run ~/.baseconvrc", values: &[] },
+    Flag { name: "--tui", key: "tui", arg: "", desc: "start the full-screen interface", values: &[] },
+    Flag { name: "--serve", key: "serve", arg: "", desc: "answer JSON-RPC requests on stdin", values: &[] },
+];
+
+/// Parsed command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    /// Values to convert.
+    pub values: Vec<String>,
+    /// File to convert.
+    pub file: Option<String>,
+    /// Settings given by flags, as (key, value) pairs.
+    pub settings: Vec<(String, String)>,
+    pub help: bool,
+    /// Skip the startup file, with `--no-rc`.
+    pub no_rc: bool,
+    /// Start the full-screen interface, with `--tui`.
+    pub tui: bool,
+    /// Run the JSON-RPC server, with `--serve`.
+    pub serve: bool,
+    /// Shell to print a completion script for, with the `completions` subcommand.
+    pub completions: Option<String>,
+}
+
+impl Args {
+    /// Whether the arguments start an interactive session, the full-screen
+    /// interface or the prompt when stdin is a `terminal`. Only these run
+    /// the startup file, whose output would mix with the results otherwise.
+    pub fn interactive(&self, terminal: bool) -> bool {
+        !self.serve && (self.tui || (self.file.is_none() && self.values.is_empty() && terminal))
+    }
+}
+
+/// Build the usage message from the flag table.
+pub fn usage() -> String {
+    let mut ret = format!("Usage: {} [options] [values...]\n", BIN);
+    ret += &format!("       {} completions <{}>\n\n", BIN, SHELLS.join("|"));
+    ret += "Without values, start the interactive prompt.\n\nOptions:\n";
+    for flag in FLAGS {
+        ret += &format!("    {:24}{}\n", format!("{} {}", flag.name, flag.arg).trim_end(), flag.desc);
+    }
+    ret += &format!("    {:24}{}\n", "-h, --help", "print this message");
+    ret
+}
+
+/// Parse the arguments, without the program name. Flags accept their value
+/// as the next argument or after `=`, e.g. `--to bin` or `--to=bin`.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut ret = Args::default();
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("completions") {
+        args.next();
+        let shell = args.next().ok_or("Error: missing shell for completions")?;
+        if !SHELLS.contains(&shell.as_str()) {
+            return Err(format!("Error: no completions for shell {}", shell));
+        }
+        ret.completions = Some(shell);
+    }
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            ret.help = true;
+            continue;
+        }
+        if arg == "--" {
+            ret.values.extend(args.by_ref());
+            break;
+        }
+        if !arg.starts_with("--") {
+            ret.values.push(arg);
+            continue;
+        }
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let flag = FLAGS.iter()
+            .find(|flag| flag.name == name)
+            .ok_or(format!("Error: unknown option {}", name))?;
+        let value = match value {
+            Some(_) if flag.arg.is_empty() => return Err(format!("Error: {} takes no value", name)),
+            None if flag.arg.is_empty() => "on".to_string(),
+            Some(value) => value,
+            None => args.next().ok_or(format!("Error: missing value for {}", name))?,
+        };
+        if flag.key == "file" {
+            ret.file = Some(value);
+        } else if flag.key == "no-rc" {
+            ret.no_rc = true;
+        } else if flag.key == "tui" {
+            ret.tui = true;
+        } else if flag.key == "serve" {
+            ret.serve = true;
+        } else {
+            ret.settings.push((flag.key.to_string(), value));
+        }
+    }
+    Ok(ret)
+}
+
+/// Generate a completion script for a shell, from the flag table.
+pub fn completions(shell: &str) -> String {
+    match shell {
+        "bash" => bash(),
+        "zsh" => zsh(),
+        _ => fish(),
+    }
+}
+
+fn bash() -> String {
+    let mut cases = String::new();
+    for flag in FLAGS.iter().filter(|flag| !flag.arg.is_empty()) {
+        let reply = if flag.key == "file" {
+            "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()
+        } else {
+            format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", flag.values.join(" "))
+        };
+        cases += &format!("        {}) {}; return ;;\n", flag.name, reply);
+    }
+    cases += &format!("        completions) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n", SHELLS.join(" "));
+    let flags: Vec<&str> = FLAGS.iter().map(|flag| flag.name).chain(vec!["-h", "--help"]).collect();
+    format!(r#"_{bin}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+{cases}    esac
+    local words="{flags}"
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        words="$words completions"
+    fi
+    COMPREPLY=($(compgen -W "$words" -- "$cur"))
+}}
+complete -F _{bin} {bin}
+"#, bin = BIN, cases = cases, flags = flags.join(" "))
+}
+
+fn zsh() -> String {
+    let mut ret = format!("#compdef {}\n\n_arguments -s \\\n", BIN);
+    for flag in FLAGS {
+        if flag.arg.is_empty() {
+            ret += &format!("    '{}[{}]' \\\n", flag.name, flag.desc);
+            continue;
+        }
+        let action = if flag.key == "file" {
+            "_files".to_string()
+        } else if flag.values.is_empty() {
+            " ".to_string()
+        } else {
+            format!("({})", flag.values.join(" "))
+        };
+        ret += &format!("    '{}=[{}]:{}:{}' \\\n", flag.name, flag.desc, flag.key, action);
+    }
+    ret += "    '(-h --help)'{-h,--help}'[print help message]' \\\n";
+    ret += &format!("    '1:value or subcommand:(completions)' \\\n    '2:shell:({})' \\\n", SHELLS.join(" "));
+    ret += "    '*:value: '\n";
+    ret
+}
+
+fn fish() -> String {
+    let mut ret = String::new();
+    for flag in FLAGS {
+        let args = if flag.arg.is_empty() {
+            String::new()
+        } else if flag.key == "file" {
+            "-r -F".to_string()
+        } else if flag.values.is_empty() {
+            "-x".to_string()
+        } else {
+            format!("-xa '{}'", flag.values.join(" "))
+        };
+        ret += &format!("{}\n", format!("complete -c {} -l {} -d '{}' {}", BIN, &flag.name[2..], flag.desc, args).trim_end());
+    }
+    ret += &format!("complete -c {} -s h -l help -d 'print help message'\n", BIN);
+    ret += &format!("complete -c {} -n '__fish_use_subcommand' -a completions -d 'print a completion script'\n", BIN);
+    ret += &format!("complete -c {} -n '__fish_seen_subcommand_from completions' -xa '{}'\n", BIN, SHELLS.join(" "));
+    ret
+}
+
+#[cfg(test)]
+mod test_cli {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Args, String> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse() {
+        let args = parse_str("0xff --to bin --width=16 12").unwrap();
+        assert_eq!(args.values, vec!["0xff", "12"]);
+        assert_eq!(args.settings, vec![("to".to_string(), "bin".to_string()), ("width".to_string(), "16".to_string())]);
+        assert!(!args.help);
+        assert!(parse_str("-h").unwrap().help);
+        assert_eq!(parse_str("-- --to").unwrap().values, vec!["--to"]);
+        assert_eq!(parse_str("").unwrap(), Args::default());
+        assert_eq!(parse_str("--file in.txt").unwrap().file, Some("in.txt".to_string()));
+
+        assert_eq!(parse_str("--raw 1").unwrap().settings, vec![("quiet".to_string(), "on".to_string())]);
+        assert!(parse_str("--raw=off").is_err());
+        assert!(parse_str("--serve --from dec").unwrap().serve);
+        assert!(parse_str("--no-rc").unwrap().no_rc);
+        assert!(parse_str("--tui").unwrap().tui);
+        assert!(parse_str("0xff --to").is_err());
+        assert!(parse_str("0xff --base hex").is_err());
+    }
+
+    #[test]
+    fn test_interactive() {
+        assert!(parse_str("").unwrap().interactive(true));
+        assert!(!parse_str("").unwrap().interactive(false));
+        assert!(parse_str("--tui").unwrap().interactive(false));
+        assert!(!parse_str("--serve").unwrap().interactive(true));
+        assert!(!parse_str("--serve --tui").unwrap().interactive(true));
+        assert!(!parse_str("--file in.txt").unwrap().interactive(true));
+        assert!(!parse_str("0xff").unwrap().interactive(true));
+    }
+
+    #[test]
+    fn test_completions() {
+        assert_eq!(parse_str("completions zsh").unwrap().completions, Some("zsh".to_string()));
+        assert!(parse_str("completions").is_err());
+        assert!(parse_str("completions tcsh").is_err());
+        assert_eq!(parse_str("0xff completions").unwrap().completions, None);
+
+        let script = completions("bash");
+        assert!(script.contains("--from) COMPREPLY=($(compgen -W \"hex dec bin oct\" -- \"$cur\")); return ;;"));
+        assert!(script.contains("complete -F _base_converter base_converter"));
+        let script = completions("zsh");
+        assert!(script.contains("'--raw[print only the converted values]'"));
+        assert!(script.contains("'--to=[output base: hex, dec, bin, oct]:to:(hex dec bin oct)'"));
+        let script = completions("fish");
+        assert!(script.contains("complete -c base_converter -l output -d 'result format: text, json, csv, tsv' -xa 'text json csv tsv'"));
+    }
+}
@@ -0,0 +1,113 @@
+//! System clipboard, through the clipboard tools of the platform: `pbcopy`
+//! and `pbpaste` on macOS, `clip.exe` and PowerShell on Windows, and
+//! `wl-clipboard`, `xclip` or `xsel` on Linux. Without a working tool, e.g. on
+//! a headless system over SSH, the terminal can still be asked to set its
+//! clipboard with an OSC 52 sequence.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use crate::error::CommandError;
+
+/// Commands writing their input to the clipboard, tried in order.
+const COPY_TOOLS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Commands printing the clipboard, tried in order.
+const PASTE_TOOLS: &[(&str, &[&str])] = &[
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("powershell.exe", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+/// Put a text on the clipboard with the first tool that works.
+pub fn copy(text: &str) -> Result<(), CommandError> {
+    for (tool, args) in COPY_TOOLS {
+        let child = Command::new(tool)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        // A missing tool fails to spawn, a tool without display fails to run.
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        if child.wait().is_ok_and(|status| status.success()) && written {
+            return Ok(());
+        }
+    }
+    Err(CommandError::Io("no clipboard available, install wl-clipboard, xclip or xsel".to_string()))
+}
+
+/// Read the text of the clipboard with the first tool that works.
+pub fn paste() -> Result<String, CommandError> {
+    for (tool, args) in PASTE_TOOLS {
+        let output = Command::new(tool).args(*args).stdin(Stdio::null()).stderr(Stdio::null()).output();
+        match output {
+            Ok(output) if output.status.success() => return Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            _ => continue,
+        }
+    }
+    Err(CommandError::Io("no clipboard available, install wl-clipboard, xclip or xsel".to_string()))
+}
+
+/// Words of a text that look like numbers, i.e. contain a decimal digit,
+/// e.g. `0x4000_1000` and `12` in `Base 0x4000_1000, 12 bits.`.
+pub fn numbers(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .map(|word| word.trim_matches('_'))
+        .filter(|word| word.contains(|c: char| c.is_ascii_digit()))
+        .collect()
+}
+
+/// Escape sequence asking the terminal to put a text on its clipboard.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Encode bytes in standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut ret = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test_clipboard {
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(osc52("0x1234"), "\x1b]52;c;MHgxMjM0\x07");
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(numbers("Base 0x4000_1000, 12 bits (reset: ffh)."), vec!["0x4000_1000", "12"]);
+        assert_eq!(numbers("_10_\n\tdead 3f;"), vec!["10", "3f"]);
+        assert!(numbers("no numbers here").is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+//! Commands of the prompt and their parser. A command is given without the
+//! leading `:`, e.g. `from dec to hex`.
+
+use crate::base::Base;
+use crate::error::CommandError;
+use crate::field::Field;
+use crate::list::{self, ListOp};
+use crate::plugin::Registry;
+use crate::random;
+use crate::settings;
+
+/// Names of the built-in commands, besides settings.
+pub const NAMES: &[&str] = &[
+    "help", "quit", "from", "to", "set", "show", "undo", "reset", "history", "export", "batch", "config", "save", "load", "alias", "bookmark", "bookmarks", "enum", "whois", "deffield", "copy", "paste", "log", "tui", "bench", "rand", "seq", "combos", "alignreport", "dups", "sort", "agg", "bitfreq",
+];
+
+/// A parsed command.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Quit,
+    From(Base),
+    To(Base),
+    /// Change settings, as (key, value) pairs, e.g. `:from hex to dec width 32`.
+    Set(Vec<(String, String)>),
+    Show,
+    Undo,
+    Reset,
+    History,
+    /// Write the conversions of the session to a file.
+    Export(String),
+    Batch { input: String, output: Option<String> },
+    ConfigSave,
+    ConfigLoad,
+    /// Save the settings and definitions to a workspace file.
+    Save(String),
+    /// Restore the settings and definitions of a workspace file.
+    Load(String),
+    /// List the aliases.
+    Aliases,
+    Alias { name: String, command: String },
+    /// Save the last result under a name.
+    Bookmark(String),
+    /// List the bookmarks.
+    Bookmarks,
+    /// Bookmark the members of a C enum, or of the enum of the clipboard
+    /// if empty.
+    Enum(String),
+    /// List the names of the bookmarks with a value.
+    Whois(String),
+    /// Add fields to the register layout.
+    DefField(Vec<Field>),
+    /// List the fields with their values in the last result.
+    Fields,
+    /// Remove all fields.
+    ClearFields,
+    /// Copy the last result to the clipboard, in `base` or the output base,
+    /// without `_` separators unless `separators`.
+    Copy { base: Option<Base>, separators: bool },
+    /// Convert the numbers of the clipboard.
+    Paste,
+    /// Start logging the session to a file, or stop with `None`.
+    Log(Option<String>),
+    /// Open the full-screen interface.
+    Tui,
+    /// Time the conversion of an input, or a decoder command, repeated
+    /// `iterations` times or a default number.
+    Bench { input: String, iterations: Option<u64> },
+    /// Print `count` random values of `width` bits, or of the width setting.
+    Rand { width: Option<u32>, count: usize },
+    /// Print the values from `start` to `end`, `step` apart, after their
+    /// index if `index`.
+    Seq { start: String, end: String, step: Option<String>, index: bool },
+    /// Print the values of `width` bits with `bits` bits set.
+    Combos { width: u32, bits: u32 },
+    /// Report on a list of values, or on the numbers of the clipboard
+    /// without values.
+    List(ListOp, Vec<String>),
+    /// Run a command of the plugin registry.
+    Plugin { name: String, args: Vec<String> },
+}
+
+/// Parse a single command. Words are separated by spaces, and can be quoted
+/// with double quotes to contain spaces. Commands of `plugins` are
+/// recognized by name.
+pub fn parse(cmd: &str, plugins: &Registry) -> Result<Command, CommandError> {
+    let words = split_quoted(cmd.trim(), ' ')
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .map(unquote)
+        .collect::<Result<Vec<&str>, CommandError>>()?;
+    let base = |name: &str| Base::from_name(name).ok_or_else(|| CommandError::UnknownBase(name.to_string()));
+    let command = match words[..] {
+        ["h"] | ["help"] => Command::Help,
+        ["q"] | ["quit"] => Command::Quit,
+        ["from", name] => Command::From(base(name)?),
+        ["to", name] => Command::To(base(name)?),
+        ["set", key, value] => Command::Set(vec![(key.to_string(), value.to_string())]),
+        ["show"] => Command::Show,
+        ["undo"] => Command::Undo,
+        ["reset"] => Command::Reset,
+        ["history"] => Command::History,
+        ["history", "size", size] => Command::Set(vec![("history".to_string(), size.to_string())]),
+        ["export", path] => Command::Export(path.to_string()),
+        ["batch", input] => Command::Batch { input: input.to_string(), output: None },
+        ["batch", input, output] => Command::Batch { input: input.to_string(), output: Some(output.to_string()) },
+        ["config", "save"] => Command::ConfigSave,
+        ["config", "load"] => Command::ConfigLoad,
+        ["save", path] => Command::Save(path.to_string()),
+        ["load", path] => Command::Load(path.to_string()),
+        ["alias"] => Command::Aliases,
+        ["alias", name, command] => Command::Alias { name: name.to_string(), command: command.to_string() },
+        ["bookmark", name] => Command::Bookmark(name.to_string()),
+        ["bookmarks"] => Command::Bookmarks,
+        ["enum", ref code @ ..] => Command::Enum(code.join(" ")),
+        ["whois", value] => Command::Whois(value.to_string()),
+        ["deffield"] => Command::Fields,
+        ["deffield", "clear"] => Command::ClearFields,
+        ["deffield", ref specs @ ..] => Command::DefField(specs.iter().map(|spec| Field::parse(spec)).collect::<Result<_, _>>()?),
+        ["copy", ref args @ ..] if args.len() <= 2 => {
+            let mut copy = (None, true);
+            for arg in args {
+                match *arg {
+                    "nosep" => copy.1 = false,
+                    name => copy.0 = Some(base(name)?),
+                }
+            }
+            Command::Copy { base: copy.0, separators: copy.1 }
+        }
+        ["paste"] => Command::Paste,
+        ["tui"] => Command::Tui,
+        ["log", "off"] => Command::Log(None),
+        ["log", path] => Command::Log(Some(path.to_string())),
+        ["bench", input] => Command::Bench { input: input.to_string(), iterations: None },
+        ["bench", input, iterations] => {
+            let iterations = iterations.parse().ok().filter(|n| *n > 0)
+                .ok_or_else(|| CommandError::invalid("iterations", iterations, "a positive number"))?;
+            Command::Bench { input: input.to_string(), iterations: Some(iterations) }
+        }
+        ["rand", ref args @ ..] if args.len() <= 2 => {
+            let width = args.first().map(|width| {
+                width.parse().ok().filter(|width| (1..=64).contains(width)).ok_or_else(|| CommandError::invalid("width", width, "1 to 64"))
+            }).transpose()?;
+            let count = args.get(1).map_or(Ok(1), |count| {
+                count.parse().ok().filter(|count| (1..=random::MAX_COUNT).contains(count))
+                    .ok_or_else(|| CommandError::invalid("count", count, &format!("1 to {}", random::MAX_COUNT)))
+            })?;
+            Command::Rand { width, count }
+        }
+        ["seq", ref args @ ..] => {
+            let index = args.contains(&"--index");
+            let args: Vec<&str> = args.iter().copied().filter(|arg| *arg != "--index").collect();
+            match args[..] {
+                [start, end] => Command::Seq { start: start.to_string(), end: end.to_string(), step: None, index },
+                [start, end, step] => Command::Seq { start: start.to_string(), end: end.to_string(), step: Some(step.to_string()), index },
+                _ => return Err(CommandError::Syntax),
+            }
+        }
+        ["combos", ref args @ ..] => {
+            let (mut width, mut bits) = (None, None);
+            for arg in args {
+                match arg.split_once('=') {
+                    Some(("width", value)) => {
+                        width = Some(value.parse().ok().filter(|width| (1..=64).contains(width))
+                            .ok_or_else(|| CommandError::invalid("width", value, "1 to 64"))?)
+                    }
+                    Some(("bits", value)) => {
+                        bits = Some(value.parse().map_err(|_| CommandError::invalid("bits", value, "a number"))?)
+                    }
+                    _ => return Err(CommandError::Syntax),
+                }
+            }
+            match (width, bits) {
+                (Some(width), Some(bits)) if bits <= width => Command::Combos { width, bits },
+                (Some(_), Some(bits)) => return Err(CommandError::invalid("bits", &bits.to_string(), "at most the width")),
+                _ => return Err(CommandError::Syntax),
+            }
+        }
+        [name, ref args @ ..] if list::NAMES.contains(&name) => {
+            let (op, values) = list::parse(name, args)?;
+            Command::List(op, values)
+        }
+        [name, ref args @ ..] if plugins.find(name).is_some() => Command::Plugin {
+            name: name.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        },
+        // Any other command is a list of settings, e.g. `:from hex to dec`.
+        _ if !words.is_empty() && words.len().is_multiple_of(2)
+            && words.iter().step_by(2).all(|key| settings::KEYS.contains(key)) => {
+            Command::Set(words.chunks(2).map(|pair| (pair[0].to_string(), pair[1].to_string())).collect())
+        }
+        _ => return Err(CommandError::Syntax),
+    };
+    Ok(command)
+}
+
+/// Split a command on a separator, except inside double quotes.
+pub fn split_quoted(cmd: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in cmd.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && (c == sep || sep == ' ' && c.is_whitespace()) {
+            parts.push(&cmd[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&cmd[start..]);
+    parts
+}
+
+/// Remove the double quotes around a word.
+fn unquote(word: &str) -> Result<&str, CommandError> {
+    if !word.contains('"') {
+        return Ok(word);
+    }
+    match word.strip_prefix('"').and_then(|w| w.strip_suffix('"')) {
+        Some(inner) if !inner.contains('"') => Ok(inner),
+        _ => Err(CommandError::Quoting(word.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test_command {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Command {
+        Command::Set(pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect())
+    }
+
+    #[test]
+    fn test_parse() {
+        let plugins = Registry::new();
+        assert_eq!(parse("help", &plugins), Ok(Command::Help));
+        assert_eq!(parse(" q ", &plugins), Ok(Command::Quit));
+        assert_eq!(parse("from dec", &plugins), Ok(Command::From(Base::Dec)));
+        assert_eq!(parse("to hx", &plugins), Err(CommandError::UnknownBase("hx".to_string())));
+        assert_eq!(parse("from dec to hex", &plugins), Ok(pairs(&[("from", "dec"), ("to", "hex")])));
+        assert_eq!(parse("history size 10", &plugins), Ok(pairs(&[("history", "10")])));
+        assert_eq!(parse("prompt \"{in} > \"", &plugins), Ok(pairs(&[("prompt", "{in} > ")])));
+        assert_eq!(parse("batch in.txt", &plugins), Ok(Command::Batch { input: "in.txt".to_string(), output: None }));
+        assert_eq!(parse("can 7df isotp", &plugins), Ok(Command::Plugin {
+            name: "can".to_string(),
+            args: vec!["7df".to_string(), "isotp".to_string()],
+        }));
+        assert_eq!(parse("bench \"htons(1)\" 1000", &plugins), Ok(Command::Bench { input: "htons(1)".to_string(), iterations: Some(1000) }));
+        assert!(parse("bench 1 0", &plugins).is_err());
+        assert_eq!(parse("seq 0 10 --index", &plugins), Ok(Command::Seq {
+            start: "0".to_string(),
+            end: "10".to_string(),
+            step: None,
+            index: true,
+        }));
+        assert!(parse("seq 0", &plugins).is_err());
+        assert_eq!(parse("combos bits=2 width=8", &plugins), Ok(Command::Combos { width: 8, bits: 2 }));
+        assert_eq!(parse("combos width=8 bits=9", &plugins), Err(CommandError::invalid("bits", "9", "at most the width")));
+        assert!(parse("combos width=8", &plugins).is_err());
+        assert_eq!(parse("rand", &plugins), Ok(Command::Rand { width: None, count: 1 }));
+        assert_eq!(parse("rand 12 4", &plugins), Ok(Command::Rand { width: Some(12), count: 4 }));
+        assert_eq!(parse("rand 65", &plugins), Err(CommandError::invalid("width", "65", "1 to 64")));
+        assert_eq!(parse("rand 8 0", &plugins), Err(CommandError::invalid("count", "0", "1 to 1048576")));
+        assert_eq!(parse("alignreport 8 1000,1004", &plugins), Ok(Command::List(ListOp::Align(8), vec!["1000".to_string(), "1004".to_string()])));
+        assert_eq!(parse("alias fd \"from dec\"", &plugins), Ok(Command::Alias {
+            name: "fd".to_string(),
+            command: "from dec".to_string(),
+        }));
+        assert_eq!(parse("log session.txt", &plugins), Ok(Command::Log(Some("session.txt".to_string()))));
+        assert_eq!(parse("log off", &plugins), Ok(Command::Log(None)));
+        assert_eq!(parse("bookmark baud_reg", &plugins), Ok(Command::Bookmark("baud_reg".to_string())));
+        assert_eq!(parse("enum enum {  A, B = 3 }", &plugins), Ok(Command::Enum("enum { A, B = 3 }".to_string())));
+        assert_eq!(parse("enum", &plugins), Ok(Command::Enum(String::new())));
+        assert_eq!(parse("whois 3", &plugins), Ok(Command::Whois("3".to_string())));
+        assert_eq!(parse("copy", &plugins), Ok(Command::Copy { base: None, separators: true }));
+        assert_eq!(parse("copy bin nosep", &plugins), Ok(Command::Copy { base: Some(Base::Bin), separators: false }));
+        assert_eq!(parse("copy hx", &plugins), Err(CommandError::UnknownBase("hx".to_string())));
+        assert_eq!(parse("load work.toml", &plugins), Ok(Command::Load("work.toml".to_string())));
+        assert_eq!(parse("deffield en[0] mode[3:1]", &plugins),
+            Ok(Command::DefField(vec![Field::parse("en[0]").unwrap(), Field::parse("mode[3:1]").unwrap()])));
+        assert_eq!(parse("", &plugins), Err(CommandError::Syntax));
+        assert_eq!(parse("from", &plugins), Err(CommandError::Syntax));
+        assert_eq!(parse("width 8 to", &plugins), Err(CommandError::Syntax));
+        assert_eq!(parse("prompt \"a", &plugins), Err(CommandError::Quoting("\"a".to_string())));
+    }
+
+    #[test]
+    fn test_split_quoted() {
+        assert_eq!(split_quoted("to hex; prompt \"a;b\"", ';'), vec!["to hex", " prompt \"a;b\""]);
+        assert_eq!(split_quoted("a  b", ' '), vec!["a", "", "b"]);
+    }
+}
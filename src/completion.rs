@@ -0,0 +1,107 @@
+//! Tab completion of commands in the prompt.
+
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use crate::app::START_CMD;
+use crate::base;
+use crate::command;
+use crate::plugin::Registry;
+use crate::settings;
+
+/// Completion words of the prompt, collected from the built-in commands,
+/// the settings and the plugin registry.
+pub struct CommandHelper {
+    /// Command names.
+    names: Vec<String>,
+    /// Words completed after a command name.
+    args: Vec<(String, Vec<String>)>,
+}
+
+impl CommandHelper {
+    pub fn new(plugins: &Registry) -> Self {
+        let mut names: Vec<String> = command::NAMES.iter().chain(settings::KEYS).map(|name| name.to_string()).collect();
+        names.extend(plugins.iter().map(|plugin| plugin.name.to_string()));
+        names.sort();
+        names.dedup();
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+        let mut args: Vec<(String, Vec<String>)> = vec![
+            ("from".to_string(), base::names()),
+            ("to".to_string(), base::names()),
+            ("set".to_string(), words(settings::KEYS)),
+            ("config".to_string(), words(&["save", "load"])),
+            ("history".to_string(), words(&["size"])),
+            ("copy".to_string(), base::names().into_iter().chain(Some("nosep".to_string())).collect()),
+            ("log".to_string(), words(&["off"])),
+        ];
+        args.extend(plugins.iter().map(|plugin| (plugin.name.to_string(), words(plugin.completions))));
+        Self { names, args }
+    }
+
+    /// Start of the word at `pos` and the words it can be completed to.
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+        if !line.starts_with(START_CMD) {
+            return (pos, Vec::new());
+        }
+        let segment_start = line.rfind(';').map_or(START_CMD.len(), |i| i + 1);
+        let segment = &line[segment_start..];
+        let segment = segment.strip_prefix(START_CMD).unwrap_or(segment);
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1).max(pos - segment.len());
+        let word = &line[start..];
+        let previous: Vec<&str> = segment[..segment.len() - word.len()].split_whitespace().collect();
+        let args = |key: &str| self.args.iter().find(|(name, _)| name == key).map(|(_, words)| words);
+        let words = match previous[..] {
+            [] => &self.names,
+            // Settings come in key/value pairs, e.g. `:from hex to <tab>`,
+            // other commands complete all their arguments the same.
+            [first, .., last] | [first @ last] => match args(last).or_else(|| args(first)) {
+                Some(words) => words,
+                None => return (pos, Vec::new()),
+            },
+        };
+        let matches = words.iter().filter(|candidate| candidate.starts_with(word)).cloned().collect();
+        (start, matches)
+    }
+}
+
+impl Completer for CommandHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+#[cfg(test)]
+mod test_completion {
+    use super::*;
+
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        CommandHelper::new(&Registry::new()).candidates(line, line.len())
+    }
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(complete(":fr"), (1, vec!["from".to_string()]));
+        assert_eq!(complete(":to he"), (4, vec!["hex".to_string()]));
+        assert_eq!(complete(":can 7df j"), (9, vec!["j1939".to_string()]));
+        assert_eq!(complete(":to hex; us"), (9, vec!["usb".to_string()]));
+        assert_eq!(complete(":from hex to d"), (13, vec!["dec".to_string()]));
+        assert!(complete(":u").1.contains(&"uuid".to_string()));
+        assert_eq!(complete("ff"), (2, Vec::new()));
+        assert_eq!(complete(":width 1"), (8, Vec::new()));
+    }
+}
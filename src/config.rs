@@ -0,0 +1,296 @@
+//! Configuration file, `~/.config/base-converter/config.toml`. It holds
+//! default settings, with the same keys as `:set`, command aliases,
+//! bookmarks, register fields and the colors of the TUI, see `theme`:
+//! ```toml
+//! to = "hex"
+//! width = 32
+//! pager = false
+//!
+//! [aliases]
+//! fd = "from dec"
+//!
+//! [bookmarks]
+//! baud_reg = 4660
+//!
+//! [fields]
+//! mode = "7:4"
+//! en = "3"
+//!
+//! [theme]
+//! name = "monochrome"
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use crate::error::CommandError;
+use crate::field::{self, Field};
+use crate::settings::{self, Settings};
+use crate::theme::Theme;
+
+/// Content of the configuration file.
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub settings: Settings,
+    /// Command aliases, e.g. `fd` for `from dec`.
+    pub aliases: BTreeMap<String, String>,
+    /// Values saved with `:bookmark`.
+    pub bookmarks: BTreeMap<String, u64>,
+    /// Register fields defined with `:deffield`, the most significant first.
+    pub fields: Vec<Field>,
+    /// Colors of the TUI.
+    pub theme: Theme,
+}
+
+/// Path of the configuration file, `$XDG_CONFIG_HOME/base-converter/config.toml`
+/// or `~/.config/base-converter/config.toml`. `None` if no home directory is set.
+pub fn path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("base-converter").join("config.toml"))
+}
+
+/// Path of the startup file, `~/.baseconvrc`. `None` if no home directory is set.
+pub fn rc_path() -> Option<PathBuf> {
+    Some(PathBuf::from(env::var_os("HOME")?).join(".baseconvrc"))
+}
+
+/// Read the startup file: the commands it lists, with their line numbers.
+/// Empty lines and `#` comments are skipped, and the leading `:` of
+/// commands is optional. Empty if there is no startup file.
+pub fn load_rc() -> Result<Vec<(usize, String)>, CommandError> {
+    let path = rc_path().ok_or_else(|| CommandError::Io("cannot locate home directory".to_string()))?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CommandError::Io(format!("cannot read {}: {}", path.display(), e))),
+    };
+    Ok(rc_commands(&text))
+}
+
+/// Commands of a startup file, see `load_rc`.
+fn rc_commands(text: &str) -> Vec<(usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| (n, line.strip_prefix(':').unwrap_or(line).to_string()))
+        .collect()
+}
+
+/// Read the configuration file. `None` if there is none.
+pub fn load() -> Result<Option<Config>, CommandError> {
+    let path = path().ok_or_else(|| CommandError::Io("cannot locate home directory".to_string()))?;
+    match fs::metadata(&path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        _ => load_file(&path).map(Some),
+    }
+}
+
+/// Write the configuration file. Return its path.
+pub fn save(config: &Config) -> Result<PathBuf, CommandError> {
+    let path = path().ok_or_else(|| CommandError::Io("cannot locate home directory".to_string()))?;
+    save_file(&path, config)?;
+    Ok(path)
+}
+
+/// Read a configuration from any file, e.g. a workspace saved with `:save`.
+pub fn load_file(path: &Path) -> Result<Config, CommandError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| CommandError::Io(format!("cannot read {}: {}", path.display(), e)))?;
+    parse(&text).map_err(|e| CommandError::Config(format!("in {}: {}", path.display(), e)))
+}
+
+/// Write a configuration to any file, creating its directory if needed.
+pub fn save_file(path: &Path, config: &Config) -> Result<(), CommandError> {
+    let save_error = |e| CommandError::Io(format!("cannot save {}: {}", path.display(), e));
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(save_error)?;
+    }
+    fs::write(path, to_toml(config)).map_err(save_error)
+}
+
+/// Prefix of the environment variables overriding settings, e.g. `BASECONV_TO`.
+pub const ENV_PREFIX: &str = "BASECONV_";
+
+/// Settings given by environment variables, as key/value pairs. Variables
+/// that do not name a setting are ignored.
+pub fn env_settings(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    vars.filter_map(|(name, value)| {
+        let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+        settings::KEYS.contains(&key.as_str()).then_some((key, value))
+    }).collect()
+}
+
+/// Read a configuration from TOML. Missing settings keep their default.
+pub fn parse(text: &str) -> Result<Config, CommandError> {
+    let table = text.parse::<Table>().map_err(|e| CommandError::Config(e.message().to_string()))?;
+    let mut config = Config::default();
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("aliases", Value::Table(aliases)) => {
+                for (name, command) in aliases {
+                    let command = command.as_str().ok_or_else(|| CommandError::Config(format!("alias {} must be a string", name)))?;
+                    config.aliases.insert(name, command.to_string());
+                }
+            }
+            ("bookmarks", Value::Table(bookmarks)) => {
+                for (name, value) in bookmarks {
+                    // Values above the TOML integer range are written as strings.
+                    let num = match &value {
+                        Value::Integer(num) => u64::try_from(*num).ok(),
+                        Value::String(text) => text.parse().ok(),
+                        _ => None,
+                    };
+                    let num = num.ok_or_else(|| CommandError::Config(format!("bookmark {} must be a positive integer", name)))?;
+                    config.bookmarks.insert(name, num);
+                }
+            }
+            ("fields", Value::Table(fields)) => {
+                let mut layout = Vec::new();
+                for (name, range) in fields {
+                    let range = range.as_str().ok_or_else(|| CommandError::Config(format!("field {} must be a string", name)))?;
+                    layout.push(Field::parse(&format!("{}[{}]", name, range))?);
+                }
+                field::define(&mut config.fields, layout);
+            }
+            ("theme", Value::Table(mut theme)) => {
+                // The preset comes first, the other keys change its colors.
+                let name = theme.remove("name");
+                for (key, value) in name.map(|name| ("name".to_string(), name)).into_iter().chain(theme) {
+                    let value = value.as_str().ok_or_else(|| CommandError::Config(format!("theme {} must be a string", key)))?;
+                    config.theme.set(&key, value)?;
+                }
+            }
+            (key, Value::String(value)) => config.settings.set(key, &value)?,
+            (key, Value::Integer(value)) => config.settings.set(key, &value.to_string())?,
+            (key, Value::Boolean(value)) => config.settings.set(key, if value { "on" } else { "off" })?,
+            (key, _) => return Err(CommandError::Config(format!("invalid value for {}", key))),
+        }
+    }
+    Ok(config)
+}
+
+/// Write a configuration as TOML. Numbers and on/off values are written as
+/// TOML integers and booleans.
+pub fn to_toml(config: &Config) -> String {
+    let mut table = Table::new();
+    for key in settings::KEYS {
+        let value = config.settings.get(key).unwrap_or_default();
+        let value = match value.as_str() {
+            "on" => Value::Boolean(true),
+            "off" => Value::Boolean(false),
+            _ => match value.parse::<i64>() {
+                Ok(num) => Value::Integer(num),
+                Err(_) => Value::String(value),
+            },
+        };
+        table.insert(key.to_string(), value);
+    }
+    if !config.aliases.is_empty() {
+        let aliases = config.aliases.iter()
+            .map(|(name, command)| (name.clone(), Value::String(command.clone())))
+            .collect();
+        table.insert("aliases".to_string(), Value::Table(aliases));
+    }
+    if !config.bookmarks.is_empty() {
+        let bookmarks = config.bookmarks.iter()
+            .map(|(name, num)| {
+                let value = i64::try_from(*num).map_or_else(|_| Value::String(num.to_string()), Value::Integer);
+                (name.clone(), value)
+            })
+            .collect();
+        table.insert("bookmarks".to_string(), Value::Table(bookmarks));
+    }
+    if !config.fields.is_empty() {
+        let fields = config.fields.iter()
+            .map(|field| (field.name.clone(), Value::String(field.range())))
+            .collect();
+        table.insert("fields".to_string(), Value::Table(fields));
+    }
+    if config.theme != Theme::default() {
+        let theme = config.theme.colors().iter()
+            .map(|(key, color)| (key.to_string(), Value::String(color.name().to_string())))
+            .collect();
+        table.insert("theme".to_string(), Value::Table(theme));
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod test_config {
+    use super::*;
+    use crate::base::Base;
+    use crate::theme::Color;
+
+    #[test]
+    fn test_parse() {
+        let config = parse("to = \"hex\"\nwidth = 32\npager = false\n[aliases]\nfd = \"from dec\"\n").unwrap();
+        assert_eq!(config.settings.out_base, Base::Hex);
+        assert_eq!(config.settings.width, 32);
+        assert!(!config.settings.pager);
+        assert_eq!(config.settings.in_base, Base::Hex);
+        assert_eq!(config.aliases.get("fd"), Some(&"from dec".to_string()));
+
+        assert!(parse("width = 99").is_err());
+        assert!(parse("foo = 1").is_err());
+        assert!(parse("width = [1]").is_err());
+        assert!(parse("[aliases]\nfd = 1").is_err());
+        assert!(parse("[bookmarks]\nreg = -1").is_err());
+        assert!(parse("[fields]\nmode = 7").is_err());
+        assert!(parse("[fields]\nmode = \"4:7\"").is_err());
+        assert!(parse("width = ").is_err());
+
+        let config = parse("[theme]\nselection = \"blue\"\nname = \"monochrome\"\n").unwrap();
+        assert_eq!(config.theme, Theme { selection: Color::Blue, ..Theme::monochrome() });
+        assert!(parse("[theme]\nerror = 1").is_err());
+    }
+
+    #[test]
+    fn test_rc_commands() {
+        let text = "# project symbols\n:alias fd \"from dec\"\n\n  to hex; width 32\n";
+        assert_eq!(rc_commands(text), vec![
+            (2, "alias fd \"from dec\"".to_string()),
+            (4, "to hex; width 32".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_env_settings() {
+        let vars = vec![
+            ("BASECONV_TO".to_string(), "hex".to_string()),
+            ("BASECONV_WIDTH".to_string(), "16".to_string()),
+            ("BASECONV_FOO".to_string(), "1".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        assert_eq!(env_settings(vars.into_iter()), vec![
+            ("to".to_string(), "hex".to_string()),
+            ("width".to_string(), "16".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut config = Config::default();
+        config.settings.set("width", "16").unwrap();
+        config.settings.set("prompt", "{in}> ").unwrap();
+        config.settings.set("color", "off").unwrap();
+        config.aliases.insert("th".to_string(), "to hex".to_string());
+        config.bookmarks.insert("reg".to_string(), 0x1234);
+        config.bookmarks.insert("max".to_string(), u64::MAX);
+        field::define(&mut config.fields, vec![Field::parse("en[3]").unwrap(), Field::parse("mode[7:4]").unwrap()]);
+        config.theme = Theme::monochrome();
+        let text = to_toml(&config);
+        assert!(text.contains("width = 16\n"));
+        assert!(text.contains("color = false\n"));
+        assert!(text.contains("[bookmarks]\nmax = \"18446744073709551615\"\nreg = 4660\n"));
+        assert!(text.contains("[fields]\nen = \"3\"\nmode = \"7:4\"\n"));
+        assert_eq!(parse(&text).unwrap(), config);
+    }
+}
@@ -0,0 +1,190 @@
+//! Result of a conversion, and the formats it can be printed in.
+
+use std::fmt;
+use serde_json::{json, Value};
+use crate::base::{self, Base};
+use crate::settings::Settings;
+
+/// Format of conversion results, changed with `:format` or `--output`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum OutputFormat {
+    /// The value in the output base, e.g. `<bin> 0001_0000`.
+    Text,
+    /// One JSON object per conversion.
+    Json,
+    /// One comma-separated row per conversion, with a column for each base.
+    Csv,
+    /// Like `Csv`, separated by tabs.
+    Tsv,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Get an output format from its name, e.g. "json".
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            "tsv" => Some(OutputFormat::Tsv),
+            _ => None,
+        }
+    }
+
+    /// Header line to print before a list of results, if the format has one.
+    pub fn header(&self) -> Option<String> {
+        let sep = self.separator()?;
+        let names: Vec<String> = std::iter::once("input".to_string()).chain(base::names()).collect();
+        Some(names.join(&sep.to_string()))
+    }
+
+    /// Column separator of tabular formats.
+    fn separator(&self) -> Option<char> {
+        match self {
+            OutputFormat::Csv => Some(','),
+            OutputFormat::Tsv => Some('\t'),
+            _ => None,
+        }
+    }
+}
+
+/// Result of converting one input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversion {
+    pub input: String,
+    /// Base the input was read in.
+    pub base: Base,
+    pub value: u64,
+    /// Width of the function that produced the value, if any.
+    pub width: Option<u32>,
+    /// The value formatted in the output base.
+    pub text: String,
+}
+
+impl Conversion {
+    /// Format the value in a base. The value of a function with a fixed width
+    /// is padded to that width unless `:width` is set, and the width is shown,
+    /// e.g. `0x3412 (u16)`.
+    pub fn format(value: u64, width: Option<u32>, base: &Base, settings: &Settings) -> String {
+        let digits = Conversion::digits(value, width, base, settings);
+        match width {
+            Some(width) => format!("{} (u{})", digits, width),
+            None => digits,
+        }
+    }
+
+    /// The value in the output base alone, without the width note.
+    pub fn raw(&self, settings: &Settings) -> String {
+        self.repr(&settings.out_base, settings)
+    }
+
+    /// The value in any base, without the width note.
+    pub fn repr(&self, base: &Base, settings: &Settings) -> String {
+        Conversion::digits(self.value, self.width, base, settings)
+    }
+
+    /// Format the value in a base like `format`, without showing the width.
+    fn digits(value: u64, width: Option<u32>, base: &Base, settings: &Settings) -> String {
+        match width {
+            Some(width) if settings.width == 0 => base.format(value, &Settings { width, ..settings.clone() }),
+            _ => base.format(value, settings),
+        }
+    }
+
+    /// Render as a JSON object with the value in every base of `base::all`.
+    pub fn to_json(&self, settings: &Settings) -> String {
+        self.to_value(settings).to_string()
+    }
+
+    /// The JSON object of `to_json`, as a value.
+    pub fn to_value(&self, settings: &Settings) -> Value {
+        let repr = |base: &Base| Conversion::digits(self.value, self.width, base, settings);
+        let mut value = json!({
+            "input": self.input,
+            "base": self.base.to_string(),
+            "value": self.value,
+            "width": self.width,
+            "output": repr(&settings.out_base),
+        });
+        for base in base::all() {
+            value[base.to_string()] = Value::String(repr(&base));
+        }
+        value
+    }
+
+    /// Render as a row of `format`, with the columns of `OutputFormat::header`.
+    /// Fields containing the separator or quotes are quoted.
+    pub fn to_row(&self, format: &OutputFormat, settings: &Settings) -> String {
+        let sep = format.separator().unwrap_or(',');
+        let mut fields = vec![self.input.clone()];
+        for base in base::all() {
+            fields.push(Conversion::digits(self.value, self.width, &base, settings));
+        }
+        let fields: Vec<String> = fields.into_iter()
+            .map(|field| if field.contains(sep) || field.contains('"') {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            })
+            .collect();
+        fields.join(&sep.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test_conversion {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let settings = Settings::default();
+        assert_eq!(Conversion::format(0x12, Some(16), &Base::Hex, &settings), "0x0012 (u16)");
+        assert_eq!(Conversion::format(0x12, None, &Base::Hex, &settings), "0x12");
+        let settings = Settings { width: 32, ..Default::default() };
+        assert_eq!(Conversion::format(0x12, Some(16), &Base::Hex, &settings), "0x00000012 (u16)");
+    }
+
+    #[test]
+    fn test_json() {
+        let conversion = Conversion {
+            input: "ff".to_string(),
+            base: Base::Hex,
+            value: 255,
+            width: None,
+            text: "1111_1111".to_string(),
+        };
+        assert!(conversion.to_json(&Settings::default())
+            .starts_with(r#"{"base":"hex","bin":"1111_1111","dec":"255","hex":"0xff","input":"ff","#));
+        let value = conversion.to_value(&Settings::default());
+        assert_eq!((value["oct"].as_str(), value["size"].as_str()), (Some("0o377"), Some("255 B")));
+        assert_eq!((value["output"].as_str(), value["value"].as_u64()), (Some("1111_1111"), Some(255)));
+    }
+
+    #[test]
+    fn test_row() {
+        let settings = Settings::default();
+        let conversion = Conversion {
+            input: "f(1,2)".to_string(),
+            base: Base::Hex,
+            value: 255,
+            width: Some(16),
+            text: String::new(),
+        };
+        // Bases registered by other tests may add columns at the end.
+        assert!(OutputFormat::Csv.header().unwrap().starts_with("input,hex,dec,bin,oct,size"));
+        assert!(OutputFormat::Tsv.header().unwrap().starts_with("input\thex\tdec\tbin\toct\tsize"));
+        assert_eq!(OutputFormat::Json.header(), None);
+        assert!(conversion.to_row(&OutputFormat::Csv, &settings).starts_with("\"f(1,2)\",0x00ff,255,0000_0000_1111_1111,0o000377,255 B"));
+        assert!(conversion.to_row(&OutputFormat::Tsv, &settings).starts_with("f(1,2)\t0x00ff\t255\t0000_0000_1111_1111\t0o000377\t255 B"));
+    }
+}
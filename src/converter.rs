@@ -0,0 +1,223 @@
+//! Conversion of input values with given settings, the entry point for using
+//! the crate as a library.
+//!
+//! ```
+//! use base_converter::{Base, Converter, Settings};
+//!
+//! let converter = Converter::new(Settings { in_base: Base::Dec, ..Settings::default() });
+//! assert_eq!(converter.convert("16").unwrap().text, "0001_0000");
+//! assert_eq!(converter.convert("htons(1)").unwrap().value, 256);
+//! ```
+
+use crate::conversion::Conversion;
+use crate::date;
+use crate::float;
+use crate::error::ConvertError;
+use crate::func::{self, Value};
+use crate::settings::Settings;
+use crate::units;
+
+/// Converter from the input base to the output base of its settings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Converter {
+    pub settings: Settings,
+}
+
+impl Converter {
+    pub fn new(settings: Settings) -> Self {
+        Self { settings }
+    }
+
+    /// Convert an input value or function call, see `convert`.
+    pub fn convert(&self, input: &str) -> Result<Conversion, ConvertError> {
+        convert(input, &self.settings)
+    }
+
+    /// Format a number in the output base.
+    pub fn format(&self, num: u64) -> String {
+        self.settings.out_base.format(num, &self.settings)
+    }
+}
+
+/// Read an input value or function call in the input base of `settings` and
+/// format it in the output base.
+pub fn convert(input: &str, settings: &Settings) -> Result<Conversion, ConvertError> {
+    convert_with(input, settings, &|_| None)
+}
+
+/// Like `convert`, with `lookup` giving the value of names, e.g. bookmarks,
+/// that can be used as the input or as function arguments.
+pub fn convert_with(input: &str, settings: &Settings, lookup: &dyn Fn(&str) -> Option<u64>) -> Result<Conversion, ConvertError> {
+    let parse = |arg: &str| read(arg, settings, lookup);
+    let (value, width, note) = if func::is_call(input) {
+        let value = func::eval(input, &parse, settings)?;
+        (value.num, value.width, value.note)
+    } else if let Some(value) = read_float(input, settings) {
+        (value.num, value.width, value.note)
+    } else if let Some(magnitude) = input.trim().strip_prefix('-') {
+        let (value, note) = negate(input, parse(magnitude)?, settings.width)?;
+        (value, None, Some(note))
+    } else if let Some(epoch) = date::parse(input) {
+        (epoch, None, Some(format!("{} UTC", date::format(epoch))))
+    } else {
+        (parse(input)?, None, None)
+    };
+    check_width(value, settings.width)?;
+    let text = Conversion::format(value, width, &settings.out_base, settings);
+    Ok(Conversion {
+        input: input.to_string(),
+        base: settings.in_base.clone(),
+        value,
+        width,
+        text: match note {
+            Some(note) => format!("{} ({})", text, note),
+            None => text,
+        },
+    })
+}
+
+/// Like `convert_with`, appending the text of the conversion to `out`
+/// instead of returning a `Conversion`, and returning the value. Values
+/// other than function calls, negative values, dates and floats are
+/// converted without allocating.
+pub fn convert_into(input: &str, settings: &Settings, lookup: &dyn Fn(&str) -> Option<u64>, out: &mut String) -> Result<u64, ConvertError> {
+    if func::is_call(input) || input.trim_start().starts_with('-') || date::parse(input).is_some() || read_float(input, settings).is_some() {
+        let conversion = convert_with(input, settings, lookup)?;
+        out.push_str(&conversion.text);
+        return Ok(conversion.value);
+    }
+    let value = read(input, settings, lookup)?;
+    check_width(value, settings.width)?;
+    settings.out_base.format_into(value, settings, out);
+    Ok(value)
+}
+
+/// Read a name of `lookup` or a value in the input base. Byte sizes with a
+/// unit, e.g. `256K`, are read in any base unless they are numbers of it, the
+/// shorthand `256K` as set by `kilo`.
+fn read(arg: &str, settings: &Settings, lookup: &dyn Fn(&str) -> Option<u64>) -> Result<u64, ConvertError> {
+    if let Some(value) = lookup(arg.trim()) {
+        return Ok(value);
+    }
+    settings.in_base.to_num(arg).or_else(|e| {
+        let sized = arg.trim_end().ends_with(|c: char| c.is_ascii_alphabetic());
+        units::parse_size_as(arg, settings.binary_shorthand).ok().filter(|_| sized)
+            .or_else(|| read_float(arg, settings).map(|value| value.num))
+            .ok_or(e)
+    })
+}
+
+/// Read a float literal as its bits if the `float` setting is on, unless
+/// its magnitude is a number of the input base, e.g. `-1e3` in hex.
+fn read_float(arg: &str, settings: &Settings) -> Option<Value> {
+    if !settings.float {
+        return None;
+    }
+    let magnitude = arg.trim().trim_start_matches('-');
+    float::parse(arg).filter(|_| settings.in_base.to_num(magnitude).is_err())
+}
+
+/// Two's complement of `-magnitude` in `width` bits, e.g. `-0x10` is 0xf0 in
+/// 8 bits, with a note labeling it, `-16 as 8-bit two's complement`.
+fn negate(input: &str, magnitude: u64, width: u32) -> Result<(u64, String), ConvertError> {
+    if width == 0 {
+        return Err(ConvertError::Domain { call: input.trim().to_string(), expected: "a width for negative values, e.g. :width 16" });
+    }
+    if magnitude > 1 << (width - 1) {
+        return Err(ConvertError::TooWide(width));
+    }
+    let mask = u64::MAX >> (64 - width);
+    Ok((magnitude.wrapping_neg() & mask, format!("-{} as {}-bit two's complement", magnitude, width)))
+}
+
+/// Check that a number fits in `width` bits, 0 meaning any width.
+pub(crate) fn check_width(num: u64, width: u32) -> Result<(), ConvertError> {
+    if width > 0 && width < 64 && num >> width != 0 {
+        return Err(ConvertError::TooWide(width));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_converter {
+    use super::*;
+    use crate::base::Base;
+
+    #[test]
+    fn test_convert() {
+        let mut converter = Converter::default();
+        assert_eq!(converter.convert("ff").unwrap().text, "1111_1111");
+        converter.settings.out_base = Base::Dec;
+        assert_eq!(converter.convert("htonl(1)").unwrap().text, "16777216 (u32)");
+        assert_eq!(converter.format(10), "10");
+        converter.settings.width = 4;
+        assert_eq!(converter.convert("ff"), Err(ConvertError::TooWide(4)));
+        assert!(converter.convert("fg").is_err());
+    }
+
+    #[test]
+    fn test_negative() {
+        let mut converter = Converter::new(Settings { out_base: Base::Hex, width: 8, ..Settings::default() });
+        assert_eq!(converter.convert("-0x10").unwrap().text, "0xf0 (-16 as 8-bit two's complement)");
+        assert_eq!(converter.convert("-80").unwrap().value, 0x80);
+        assert_eq!(converter.convert("-81"), Err(ConvertError::TooWide(8)));
+        converter.settings.in_base = Base::Bin;
+        assert_eq!(converter.convert("-0b101").unwrap().value, 0b1111_1011);
+        converter.settings.width = 64;
+        assert_eq!(converter.convert("-1").unwrap().value, u64::MAX);
+        let mut out = String::new();
+        assert_eq!(convert_into("-1", &converter.settings, &|_| None, &mut out), Ok(u64::MAX));
+        assert_eq!(out, "0xffffffffffffffff (-1 as 64-bit two's complement)");
+        converter.settings.width = 0;
+        assert_eq!(converter.convert("-1").unwrap_err().to_string(), "invalid -1, expect a width for negative values, e.g. :width 16");
+    }
+
+    #[test]
+    fn test_convert_with() {
+        let settings = Settings::default();
+        let lookup = |name: &str| if name == "reg" { Some(0x1234) } else { None };
+        assert_eq!(convert_with(" reg ", &settings, &lookup).unwrap().value, 0x1234);
+        assert_eq!(convert_with("htons(reg)", &settings, &lookup).unwrap().value, 0x3412);
+        assert_eq!(convert_with("12", &settings, &lookup).unwrap().value, 0x12);
+        assert!(convert_with("rag", &settings, &lookup).is_err());
+        assert_eq!(convert_with("256K", &settings, &lookup).unwrap().value, 0x40000);
+        assert_eq!(convert_with("htonl(1MiB)", &settings, &lookup).unwrap().value, 0x1000);
+        assert_eq!(convert_with("2B", &settings, &lookup).unwrap().value, 0x2b);
+        let si = Settings { binary_shorthand: false, ..Settings::default() };
+        assert_eq!(convert_with("4k", &si, &lookup).unwrap().value, 4000);
+        assert_eq!(convert_with("2M", &si, &lookup).unwrap().value, 2_000_000);
+        assert_eq!(convert_with("2MiB", &si, &lookup).unwrap().value, 0x200000);
+        assert_eq!(convert_with("gcd(1G, 4k)", &si, &lookup).unwrap().value, 4000);
+        let hex = Settings { out_base: Base::Hex, ..Settings::default() };
+        assert_eq!(convert_with("bytes(180000)", &hex, &lookup).unwrap().text, "0x180000 (1.5 MiB)");
+        assert_eq!(convert_with("size(reg)", &hex, &lookup).unwrap().text, "0x1234");
+        let bin = Settings { in_base: Base::Bin, ..Settings::default() };
+        assert!(convert_with("12", &bin, &lookup).is_err());
+        let dec = Settings { in_base: Base::Dec, out_base: Base::Dec, ..Settings::default() };
+        assert_eq!(convert_with("2024-06-01T12:00:00Z", &dec, &lookup).unwrap().text, "1717243200 (2024-06-01 12:00:00 UTC)");
+        assert_eq!(convert_with("2024-06-01 14:00 +02:00", &dec, &lookup).unwrap().value, 1_717_243_200);
+        let mut out = String::new();
+        assert_eq!(convert_into("2024/06/01", &dec, &lookup, &mut out), Ok(1_717_200_000));
+        assert_eq!(out, "1717200000 (2024-06-01 00:00:00 UTC)");
+        let float = Settings { out_base: Base::Hex, float: true, ..Settings::default() };
+        assert_eq!(convert_with("3.14f", &float, &lookup).unwrap().text, "0x4048f5c3 (u32) (3.14 as f32, stored as 3.140000104904175)");
+        assert_eq!(convert_with("-2.5e3", &float, &lookup).unwrap().value, 0xc0a3_8800_0000_0000);
+        assert_eq!(convert_with("-1e3", &Settings { width: 16, ..float.clone() }, &lookup).unwrap().value, 0xfe1d);
+        assert_eq!(convert_with("htonl(1f)", &float, &lookup).unwrap().value, 0x1f00_0000);
+        assert_eq!(convert_with("htonl(1.0f)", &float, &lookup).unwrap().value, 0x803f);
+        assert!(convert_with("3.14f", &hex, &lookup).is_err());
+    }
+
+    #[test]
+    fn test_convert_into() {
+        let settings = Settings { out_base: Base::Hex, ..Settings::default() };
+        let mut out = String::new();
+        assert_eq!(convert_into("ff", &settings, &|_| None, &mut out), Ok(0xff));
+        out.push(' ');
+        assert_eq!(convert_into("htons(1)", &settings, &|_| None, &mut out), Ok(0x100));
+        assert_eq!(out, "0xff 0x0100 (u16)");
+        assert!(convert_into("zz", &settings, &|_| None, &mut out).is_err());
+        let narrow = Settings { width: 4, ..settings };
+        assert_eq!(convert_into("ff", &narrow, &|_| None, &mut out), Err(ConvertError::TooWide(4)));
+    }
+}
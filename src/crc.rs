@@ -0,0 +1,272 @@
+//! CRCs of byte sequences with `:crc`, computed with a 256-entry table so
+//! that firmware images of several megabytes are checked at once, or a bit
+//! at a time with `bitwise`, e.g. to compare both with `:bench`. The
+//! algorithms follow the parameters of the CRC catalogue (width, polynomial,
+//! init, reflection and final XOR).
+
+use std::fs::File;
+use std::io::{self, Read};
+use crate::bytes;
+use crate::cancel;
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+use crate::progress::Progress;
+
+/// Parameters of a CRC algorithm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crc {
+    pub name: &'static str,
+    /// Width in bits, 8 to 64.
+    pub width: u32,
+    pub poly: u64,
+    pub init: u64,
+    /// Bytes are read and the CRC written least significant bit first.
+    pub reflect: bool,
+    pub xor_out: u64,
+    /// CRC of `123456789`.
+    pub check: u64,
+}
+
+/// Supported algorithms.
+pub const ALGORITHMS: &[Crc] = &[
+    Crc { name: "crc8", width: 8, poly: 0x07, init: 0, reflect: false, xor_out: 0, check: 0xf4 },
+    Crc { name: "crc8-maxim", width: 8, poly: 0x31, init: 0, reflect: true, xor_out: 0, check: 0xa1 },
+    Crc { name: "crc16-ccitt", width: 16, poly: 0x1021, init: 0xffff, reflect: false, xor_out: 0, check: 0x29b1 },
+    Crc { name: "crc16-xmodem", width: 16, poly: 0x1021, init: 0, reflect: false, xor_out: 0, check: 0x31c3 },
+    Crc { name: "crc16-modbus", width: 16, poly: 0x8005, init: 0xffff, reflect: true, xor_out: 0, check: 0x4b37 },
+    Crc { name: "crc16-arc", width: 16, poly: 0x8005, init: 0, reflect: true, xor_out: 0, check: 0xbb3d },
+    Crc { name: "crc32", width: 32, poly: 0x04c1_1db7, init: 0xffff_ffff, reflect: true, xor_out: 0xffff_ffff, check: 0xcbf4_3926 },
+    Crc { name: "crc32c", width: 32, poly: 0x1edc_6f41, init: 0xffff_ffff, reflect: true, xor_out: 0xffff_ffff, check: 0xe306_9283 },
+    Crc { name: "crc32-mpeg2", width: 32, poly: 0x04c1_1db7, init: 0xffff_ffff, reflect: false, xor_out: 0, check: 0x0376_e6e7 },
+    Crc {
+        name: "crc64-xz",
+        width: 64,
+        poly: 0x42f0_e1eb_a9ea_3693,
+        init: u64::MAX,
+        reflect: true,
+        xor_out: u64::MAX,
+        check: 0x995d_c9bb_df19_39fa,
+    },
+];
+
+/// Bytes checksummed between checks for cancellation.
+const BLOCK: usize = 1 << 20;
+
+/// Names of `ALGORITHMS`, completed after `:crc`.
+const NAMES: &[&str] = &[
+    "crc8", "crc8-maxim", "crc16-ccitt", "crc16-xmodem", "crc16-modbus", "crc16-arc", "crc32", "crc32c", "crc32-mpeg2", "crc64-xz",
+];
+
+/// Register the `:crc` command.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "crc",
+        help: &[
+            (":crc <algo> <bytes>|@<file>", "CRC of hex bytes or of a file, e.g. :crc crc32 @fw.bin"),
+            (":crc <algo> bitwise ...", "CRC computed a bit at a time, without the table, e.g. to :bench"),
+        ],
+        completions: NAMES,
+        run: command,
+    });
+}
+
+/// Find an algorithm by name.
+pub fn find(name: &str) -> Option<&'static Crc> {
+    ALGORITHMS.iter().find(|crc| crc.name == name)
+}
+
+/// Reverse the `width` low bits of `value`.
+fn reverse(value: u64, width: u32) -> u64 {
+    value.reverse_bits() >> (64 - width)
+}
+
+impl Crc {
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+
+    /// CRC of each byte value, in the bit order of the algorithm.
+    pub fn table(&self) -> [u64; 256] {
+        let mut table = [0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut crc = i as u64;
+            if self.reflect {
+                let poly = reverse(self.poly, self.width);
+                for _ in 0..8 {
+                    crc = if crc & 1 == 1 { crc >> 1 ^ poly } else { crc >> 1 };
+                }
+            } else {
+                let top = 1 << (self.width - 1);
+                crc <<= self.width - 8;
+                for _ in 0..8 {
+                    crc = if crc & top != 0 { crc << 1 ^ self.poly } else { crc << 1 };
+                }
+            }
+            *entry = crc & self.mask();
+        }
+        table
+    }
+
+    /// CRC of `data`, a byte at a time through `table`.
+    pub fn checksum(&self, data: &[u8]) -> u64 {
+        self.finish(self.update(&self.table(), self.start(), data))
+    }
+
+    /// Register before the first byte, for `update`.
+    pub fn start(&self) -> u64 {
+        if self.reflect { reverse(self.init, self.width) } else { self.init }
+    }
+
+    /// Register after the bytes of `data`, from the register `crc` of the
+    /// previous bytes.
+    pub fn update(&self, table: &[u64; 256], mut crc: u64, data: &[u8]) -> u64 {
+        for byte in data {
+            crc = if self.reflect {
+                crc >> 8 ^ table[((crc ^ *byte as u64) & 0xff) as usize]
+            } else {
+                (crc << 8 & self.mask()) ^ table[((crc >> (self.width - 8) ^ *byte as u64) & 0xff) as usize]
+            };
+        }
+        crc
+    }
+
+    /// Register after the bytes of `data` like `update`, a bit at a time
+    /// without a table.
+    pub fn update_bitwise(&self, mut crc: u64, data: &[u8]) -> u64 {
+        let top = 1 << (self.width - 1);
+        let poly = if self.reflect { reverse(self.poly, self.width) } else { self.poly };
+        for byte in data {
+            if self.reflect {
+                crc ^= *byte as u64;
+                for _ in 0..8 {
+                    crc = if crc & 1 == 1 { crc >> 1 ^ poly } else { crc >> 1 };
+                }
+            } else {
+                crc ^= (*byte as u64) << (self.width - 8);
+                for _ in 0..8 {
+                    crc = (if crc & top != 0 { crc << 1 ^ poly } else { crc << 1 }) & self.mask();
+                }
+            }
+        }
+        crc
+    }
+
+    /// CRC from the register after the last byte.
+    pub fn finish(&self, crc: u64) -> u64 {
+        (crc ^ self.xor_out) & self.mask()
+    }
+
+    /// CRC of `data` a bit at a time, as a reference for `checksum`.
+    pub fn checksum_bitwise(&self, data: &[u8]) -> u64 {
+        let top = 1 << (self.width - 1);
+        let mut crc = self.init;
+        for byte in data {
+            let byte = if self.reflect { byte.reverse_bits() } else { *byte };
+            for i in (0..8).rev() {
+                let bit = (byte >> i & 1) as u64;
+                let feedback = (crc & top != 0) as u64 ^ bit;
+                crc = (crc << 1) & self.mask();
+                if feedback == 1 {
+                    crc ^= self.poly;
+                }
+            }
+        }
+        let crc = if self.reflect { reverse(crc, self.width) } else { crc };
+        (crc ^ self.xor_out) & self.mask()
+    }
+}
+
+/// CRC and size of a file, read in blocks so that Ctrl+C stops early and
+/// progress is drawn for large files. A `bitwise` CRC is computed without
+/// the table.
+pub fn checksum_file(crc: &Crc, path: &str, bitwise: bool) -> Result<(u64, u64), String> {
+    let error = |e: io::Error| format!("cannot read {}: {}", path, e);
+    let mut file = File::open(path).map_err(error)?;
+    let mut progress = Progress::new(file.metadata().ok().map(|m| m.len()), true);
+    let table = (!bitwise).then(|| crc.table());
+    let (mut value, mut size) = (crc.start(), 0);
+    let mut block = vec![0; BLOCK];
+    loop {
+        if cancel::cancelled() {
+            return Err("cancelled".to_string());
+        }
+        let read = match file.read(&mut block) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(error(e)),
+        };
+        value = match &table {
+            Some(table) => crc.update(table, value, &block[..read]),
+            None => crc.update_bitwise(value, &block[..read]),
+        };
+        size += read as u64;
+        progress.advance(read as u64);
+    }
+    Ok((crc.finish(value), size))
+}
+
+/// Handle `:crc <algorithm> [bitwise] <bytes>|@<path>`.
+pub fn command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    let (name, bitwise, data) = match args {
+        [name, "bitwise", data @ ..] if !data.is_empty() => (name, true, data),
+        [name, data @ ..] if !data.is_empty() => (name, false, data),
+        _ => return Err(format!("Usage: :crc <algorithm> [bitwise] <bytes>|@<path>, algorithms: {}", NAMES.join(", "))),
+    };
+    let crc = find(name).ok_or_else(|| format!("No CRC {}, expect one of {}", name, NAMES.join(", ")))?;
+    let (value, size) = match data {
+        [path] if path.starts_with('@') => checksum_file(crc, &path[1..], bitwise)?,
+        _ => {
+            let data = bytes::parse_hex(data)?;
+            let value = if bitwise { crc.finish(crc.update_bitwise(crc.start(), &data)) } else { crc.checksum(&data) };
+            (value, data.len() as u64)
+        }
+    };
+    let digits = crc.width as usize / 4;
+    Ok(format!("{} 0x{:0digits$x} over {} bytes", crc.name, value, size, digits = digits))
+}
+
+#[cfg(test)]
+mod test_crc {
+    use super::*;
+    use crate::testing::hex;
+
+    #[test]
+    fn test_check() {
+        for crc in ALGORITHMS {
+            assert_eq!(crc.checksum(b"123456789"), crc.check, "{}", crc.name);
+            assert_eq!(crc.checksum_bitwise(b"123456789"), crc.check, "{}", crc.name);
+        }
+        assert_eq!(NAMES, ALGORITHMS.iter().map(|crc| crc.name).collect::<Vec<_>>().as_slice());
+    }
+
+    #[test]
+    fn test_large() {
+        let data: Vec<u8> = (0..1 << 20).map(|i: u32| ((i * 7) >> 3) as u8).collect();
+        let crc = find("crc32").unwrap();
+        assert_eq!(crc.checksum(&data), crc.checksum_bitwise(&data));
+        let table = crc.table();
+        let value = data.chunks(1000).fold(crc.start(), |value, block| crc.update(&table, value, block));
+        assert_eq!(crc.finish(value), crc.checksum(&data));
+        for crc in ALGORITHMS {
+            assert_eq!(crc.finish(crc.update_bitwise(crc.start(), &data[..4096])), crc.checksum(&data[..4096]), "{}", crc.name);
+        }
+    }
+
+    #[test]
+    fn test_command() {
+        assert_eq!(command(&["crc32", "31 32 33 34 35 36 37 38 39"], &hex), Ok("crc32 0xcbf43926 over 9 bytes".to_string()));
+        assert_eq!(command(&["crc8", "313233343536373839"], &hex), Ok("crc8 0xf4 over 9 bytes".to_string()));
+        assert_eq!(command(&["crc32", "bitwise", "31 32 33 34 35 36 37 38 39"], &hex), Ok("crc32 0xcbf43926 over 9 bytes".to_string()));
+        assert_eq!(command(&["crc16-modbus", "0x31,0x32"], &hex).unwrap(), format!("crc16-modbus 0x{:04x} over 2 bytes", find("crc16-modbus").unwrap().checksum(b"12")));
+        let path = std::env::temp_dir().join(format!("base_converter_crc_{}.bin", std::process::id()));
+        std::fs::write(&path, b"123456789").unwrap();
+        assert_eq!(command(&["crc32", &format!("@{}", path.display())], &hex), Ok("crc32 0xcbf43926 over 9 bytes".to_string()));
+        assert_eq!(command(&["crc16-xmodem", "bitwise", &format!("@{}", path.display())], &hex), Ok("crc16-xmodem 0x31c3 over 9 bytes".to_string()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(command(&["crc32", "@/nonexistent/fw.bin"], &hex).unwrap_err().starts_with("cannot read /nonexistent/fw.bin"));
+        assert!(command(&["crc7", "00"], &hex).is_err());
+        assert!(command(&["crc32"], &hex).is_err());
+        assert!(command(&["crc32", "bitwise"], &hex).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Dates and times, read as inputs that convert to Unix epoch seconds, e.g.
+//! `2024-06-01T12:00:00Z`, and written in the transcript. Dates are
+//! proleptic Gregorian, in UTC unless an offset is given, without leap
+//! seconds.
+
+use std::convert::TryFrom;
+
+/// Seconds in a day.
+const DAY: u64 = 86400;
+
+/// Days from 1970-01-01 to a civil date, negative before it.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Counted in 400-year eras from 0000-03-01, so that February is last.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Civil date of a number of days from 1970-01-01.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of `digits` digits, e.g. 2 for `06`.
+fn number(text: &str, digits: std::ops::RangeInclusive<usize>) -> Option<u32> {
+    if !digits.contains(&text.len()) || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Offset of a time zone from UTC in seconds: `Z`, `UTC`, or `+02:00`,
+/// `+0200` or `-05` for local times ahead of or behind UTC.
+fn offset(zone: &str) -> Option<i64> {
+    if zone.is_empty() || zone.eq_ignore_ascii_case("z") || zone.eq_ignore_ascii_case("utc") {
+        return Some(0);
+    }
+    let sign = match zone.as_bytes()[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let zone = &zone[1..];
+    let (hours, minutes) = match zone.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if zone.len() == 4 => (zone.get(..2)?, zone.get(2..)?),
+        None => (zone, "00"),
+    };
+    let (hours, minutes) = (number(hours, 2..=2)?, number(minutes, 2..=2)?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60) as i64)
+}
+
+/// Read a date with an optional time and time zone as epoch seconds, e.g.
+/// `2024-06-01T12:00:00Z`, `2024-06-01 12:00`, `2024/06/01` or
+/// `2024-06-01T14:00:00+02:00`. None if the text is not a date, or is
+/// before 1970 in UTC.
+pub fn parse(text: &str) -> Option<u64> {
+    let text = text.trim();
+    // Quick rejection of values, e.g. in batches.
+    if text.len() < 8 || !text.as_bytes()[..4].iter().all(u8::is_ascii_digit) || !matches!(text.as_bytes()[4], b'-' | b'/') {
+        return None;
+    }
+    let split = text.find(['T', 't', ' ']).unwrap_or(text.len());
+    let (date, rest) = text.split_at(split);
+    let separator = if date.contains('/') { '/' } else { '-' };
+    let fields: Vec<&str> = date.split(separator).collect();
+    let [year, month, day] = fields[..] else { return None };
+    let (year, month, day) = (number(year, 4..=4)? as i64, number(month, 1..=2)?, number(day, 1..=2)?);
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    let rest = rest.get(1..).unwrap_or("").trim_start();
+    let zone_at = rest.find(['Z', 'z', 'U', 'u', '+', '-', ' ']).unwrap_or(rest.len());
+    let (time, zone) = rest.split_at(zone_at);
+    let seconds = if time.is_empty() {
+        0
+    } else {
+        let fields: Vec<&str> = time.split(':').collect();
+        let (hours, minutes, seconds) = match fields[..] {
+            [hours, minutes] => (hours, minutes, "00"),
+            [hours, minutes, seconds] => (hours, minutes, seconds),
+            _ => return None,
+        };
+        let (hours, minutes, seconds) = (number(hours, 1..=2)?, number(minutes, 2..=2)?, number(seconds, 2..=2)?);
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        (hours * 3600 + minutes * 60 + seconds) as i64
+    };
+    let epoch = days_from_civil(year, month, day) * DAY as i64 + seconds - offset(zone.trim_start())?;
+    u64::try_from(epoch).ok()
+}
+
+/// Write epoch seconds as `YYYY-MM-DD HH:MM:SS`, in UTC.
+pub fn format(epoch: u64) -> String {
+    let (days, secs) = (epoch / DAY, epoch % DAY);
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod test_date {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("2024-06-01T12:00:00Z"), Some(1_717_243_200));
+        assert_eq!(parse("2024-06-01T12:00:00"), Some(1_717_243_200));
+        assert_eq!(parse("2024-06-01 12:00"), Some(1_717_243_200));
+        assert_eq!(parse("2024-06-01T14:00:00+02:00"), Some(1_717_243_200));
+        assert_eq!(parse("2024-06-01 07:00 -0500"), Some(1_717_243_200));
+        assert_eq!(parse("2024-06-01 12:00:00 UTC"), Some(1_717_243_200));
+        assert_eq!(parse("2024/06/01"), Some(1_717_200_000));
+        assert_eq!(parse("2024-6-1"), Some(1_717_200_000));
+        assert_eq!(parse("1970-01-01"), Some(0));
+        assert_eq!(parse("2000-02-29"), Some(951_782_400));
+        assert_eq!(parse("2001-02-29"), None);
+        assert_eq!(parse("2024-13-01"), None);
+        assert_eq!(parse("2024-06-01T24:00"), None);
+        assert_eq!(parse("2024-06-01T12:00+25:00"), None);
+        assert_eq!(parse("2024-06-01 12:00 +aéb"), None);
+        assert_eq!(parse("2024-06-01 12:00 +éé"), None);
+        assert_eq!(parse("2024-06/01"), None);
+        assert_eq!(parse("1969-12-31"), None);
+        assert_eq!(parse("1970-01-01T00:00+01:00"), None);
+        assert_eq!(parse("20240601"), None);
+        assert_eq!(parse("dead_beef"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(0), "1970-01-01 00:00:00");
+        assert_eq!(format(951_782_400), "2000-02-29 00:00:00");
+        assert_eq!(format(1_700_000_000), "2023-11-14 22:13:20");
+        assert_eq!(parse(&format(1_717_243_200)), Some(1_717_243_200));
+    }
+}
@@ -0,0 +1,200 @@
+//! Search of integer dividers with `:dividers`, e.g. the prescaler and
+//! period of a timer, or a chain of clock dividers: the dividers whose
+//! product best approximates a ratio, or a clock divided down to a target
+//! frequency.
+
+use std::convert::TryFrom;
+use crate::cancel;
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+use crate::units;
+
+/// Number of candidates printed.
+const CANDIDATES: usize = 10;
+
+/// Register the `:dividers` command.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "dividers",
+        help: &[(
+            ":dividers target=<t> ...",
+            "dividers whose product is closest to t, or to clk/t with clk=<f>; options max=<n> (65535), stages=<k> (2)",
+        )],
+        completions: &["target=", "clk=", "max=", "stages="],
+        run: command,
+    });
+}
+
+/// A chain of dividers and the distance of its product to the ratio.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub dividers: Vec<u64>,
+    pub product: u128,
+    /// `|product - ratio|`, times the denominator of the ratio.
+    distance: u128,
+}
+
+/// Search of the dividers of a ratio `num / den`.
+struct Search {
+    num: u128,
+    den: u128,
+    max: u64,
+    /// Best candidates, closest first.
+    best: Vec<Candidate>,
+}
+
+impl Search {
+    fn consider(&mut self, dividers: Vec<u64>) {
+        let product = dividers.iter().map(|d| *d as u128).product::<u128>();
+        let distance = product.saturating_mul(self.den).abs_diff(self.num);
+        // Among chains as close, the most balanced ones come first.
+        let key = |candidate: &Candidate| (candidate.distance, candidate.dividers.iter().max().copied(), candidate.dividers.clone());
+        if self.best.len() == CANDIDATES
+            && self.best.last().is_some_and(|last| key(last) <= (distance, dividers.iter().max().copied(), dividers.clone()))
+        {
+            return;
+        }
+        if self.best.iter().any(|candidate| candidate.dividers == dividers) {
+            return;
+        }
+        let candidate = Candidate { dividers, product, distance };
+        let at = self.best.partition_point(|other| key(other) < key(&candidate));
+        self.best.insert(at, candidate);
+        self.best.truncate(CANDIDATES);
+    }
+
+    /// Try the dividers following `prefix`, `stages` more of them, each at
+    /// least `min` so that each chain is tried in one order only.
+    fn search(&mut self, prefix: &mut Vec<u64>, min: u64, stages: u32) -> Result<(), String> {
+        let done = prefix.iter().map(|d| *d as u128).product::<u128>();
+        // Ratio left for the remaining dividers.
+        let (num, den) = (self.num, self.den.saturating_mul(done));
+        if stages == 1 {
+            let floor = u64::try_from(num / den).unwrap_or(u64::MAX);
+            for divider in [floor, floor.saturating_add(1)] {
+                let divider = divider.clamp(min, self.max);
+                prefix.push(divider);
+                self.consider(prefix.clone());
+                prefix.pop();
+            }
+            return Ok(());
+        }
+        let mut divider = min;
+        // Past the k-th root of the ratio, the next dividers would be smaller.
+        while divider <= self.max && ((divider - 1) as u128).saturating_pow(stages).saturating_mul(den) < num {
+            if prefix.is_empty() && cancel::cancelled() {
+                return Err("cancelled".to_string());
+            }
+            prefix.push(divider);
+            self.search(prefix, divider, stages - 1)?;
+            prefix.pop();
+            divider += 1;
+        }
+        if divider == min {
+            // Already past the root, e.g. a ratio below 1: the smallest chain.
+            prefix.extend((0..stages).map(|_| min));
+            self.consider(prefix.clone());
+            prefix.truncate(prefix.len() - stages as usize);
+        }
+        Ok(())
+    }
+}
+
+/// The chains of `stages` dividers of 1 to `max` whose products are closest
+/// to `num / den`, closest first, then with the smallest largest divider.
+pub fn search(num: u128, den: u128, max: u64, stages: u32) -> Result<Vec<Candidate>, String> {
+    let mut search = Search { num, den, max, best: Vec::new() };
+    search.search(&mut Vec::new(), 1, stages)?;
+    Ok(search.best)
+}
+
+/// Handle `:dividers target=<ratio> [max=<n>] [stages=<k>]` and
+/// `:dividers target=<frequency> clk=<frequency> ...`.
+pub fn command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    const USAGE: &str = "Usage: :dividers target=<ratio>|<frequency> [clk=<frequency>] [max=<n>] [stages=<k>]";
+    let (mut target, mut clk, mut max, mut stages) = (None, None, 65535, 2);
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("target", value)) => target = Some(value),
+            Some(("clk", value)) => clk = Some(value),
+            Some(("max", value)) => {
+                max = value.parse::<u32>().ok().filter(|max| *max > 0).ok_or_else(|| format!("Invalid max {}, expect 1 to {}", value, u32::MAX))?
+                    as u64
+            }
+            Some(("stages", value)) => {
+                stages = value.parse().ok().filter(|stages| (1..=3).contains(stages)).ok_or_else(|| format!("Invalid stages {}, expect 1 to 3", value))?
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let target = target.ok_or_else(|| USAGE.to_string())?;
+    // Frequencies without a unit are in Hz.
+    let frequency = |text: &str| units::parse_frequency(text).or_else(|_| units::scaled(text, 1000)).ok().filter(|mhz| *mhz > 0).ok_or_else(|| format!("Invalid frequency {}", text));
+    let (num, den) = match clk {
+        Some(clk) => (frequency(clk)? as u128, frequency(target)? as u128),
+        None => units::decimal(target).ok().filter(|(num, _)| *num > 0).ok_or_else(|| format!("Invalid ratio {}, expect a positive number", target))?,
+    };
+    let mut out = match clk {
+        Some(clk) => format!("{} / {} = {}, dividers up to {}:\n", clk, target, units::format_decimal(num, den), max),
+        None => format!("ratio {}, dividers up to {}:\n", units::format_decimal(num, den), max),
+    };
+    for candidate in search(num, den, max, stages)? {
+        let chain: Vec<String> = candidate.dividers.iter().map(u64::to_string).collect();
+        out += &format!("{} = {}", chain.join(" x "), candidate.product);
+        if clk.is_some() {
+            out += &format!("  {}", units::format_quantity(num, candidate.product, units::FREQUENCIES));
+        }
+        out += &format!("  {}\n", error(candidate.product, num, den));
+    }
+    Ok(out.trim_end().to_string())
+}
+
+/// Relative error of `product` to the ratio `num / den`, e.g. `+0.0125%`.
+fn error(product: u128, num: u128, den: u128) -> String {
+    if product.saturating_mul(den) == num {
+        return "exact".to_string();
+    }
+    let error = (product as f64 * den as f64 - num as f64) / num as f64 * 100.0;
+    format!("{:+.4}%", error)
+}
+
+#[cfg(test)]
+mod test_divider {
+    use super::*;
+    use crate::testing::run;
+
+    fn chains(num: u128, den: u128, max: u64, stages: u32) -> Vec<Vec<u64>> {
+        search(num, den, max, stages).unwrap().into_iter().map(|candidate| candidate.dividers).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(chains(12, 1, 65535, 2)[..3], [vec![3, 4], vec![2, 6], vec![1, 12]]);
+        assert_eq!(chains(14_000_000, 1, 65535, 2)[0], vec![3500, 4000]);
+        assert_eq!(chains(1000, 1, 16, 3)[0], vec![10, 10, 10]);
+        assert_eq!(chains(1, 2, 100, 2), vec![vec![1, 1]]);
+        assert_eq!(chains(10, 1, 3, 2)[0], vec![3, 3]);
+        // 97 is prime, too large for one divider of at most 16.
+        let best = search(97, 1, 16, 2).unwrap();
+        assert_eq!((best[0].dividers.clone(), best[0].product), (vec![8, 12], 96));
+        assert_eq!((best[1].dividers.clone(), best[1].product), (vec![7, 14], 98));
+        assert_eq!(chains(29, 1, 65535, 1), vec![vec![29], vec![30]]);
+    }
+
+    #[test]
+    fn test_command() {
+        let out = run(command, "target=12Hz clk=168MHz").unwrap();
+        assert!(out.starts_with("168MHz / 12Hz = 14000000, dividers up to 65535:\n3500 x 4000 = 14000000  12 Hz  exact\n"), "{}", out);
+        assert_eq!(out.lines().count(), 1 + CANDIDATES);
+        let out = run(command, "target=9.7 max=4").unwrap();
+        assert!(out.starts_with("ratio 9.7, dividers up to 4:\n3 x 3 = 9  -7.2165%\n2 x 4 = 8  -17.5258%\n"), "{}", out);
+        assert!(run(command, "target=9600 clk=16000000 max=256").unwrap().contains("\n7 x 238 = 1666  ~9.604 kHz  -0.0400%\n"));
+        let out = run(command, &format!("target={}", u128::MAX)).unwrap();
+        assert!(out.starts_with(&format!("ratio {}, dividers up to 65535:\n65535 x 65535 = 4294836225", u128::MAX)), "{}", out);
+        assert!(run(command, "target=12 stages=4").is_err());
+        assert!(run(command, "target=12 max=4294967296").is_err());
+        assert!(run(command, "target=0").is_err());
+        assert!(run(command, "clk=1MHz").is_err());
+        assert!(run(command, "target=1Hz clk=fast").is_err());
+    }
+}
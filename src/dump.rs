@@ -0,0 +1,37 @@
+//! Lines of register dumps pasted from a debugger, e.g. `CR1 = 0x00000081`,
+//! `R0 0xDEADBEEF` or `rax 0x1c 28`, read as a register name and its value.
+
+/// Name and value of a register dump line: a name, then its value after
+/// spaces, `=` or `:`. Anything after the value, e.g. the decimal value
+/// printed by gdb, is ignored. None if the line does not start with a name.
+pub fn parse(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    let end = line.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).filter(|end| *end > 0)?;
+    let (name, rest) = line.split_at(end);
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+    let value = rest.split_whitespace().next()?;
+    let value = value.trim_end_matches([',', ';']);
+    (!value.is_empty()).then_some((name, value))
+}
+
+#[cfg(test)]
+mod test_dump {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("CR1 = 0x00000081"), Some(("CR1", "0x00000081")));
+        assert_eq!(parse("R0 0xDEADBEEF"), Some(("R0", "0xDEADBEEF")));
+        assert_eq!(parse("rax            0x1c                28"), Some(("rax", "0x1c")));
+        assert_eq!(parse("  SR: 0x0000_00c0,"), Some(("SR", "0x0000_00c0")));
+        assert_eq!(parse("x=5"), Some(("x", "5")));
+        assert_eq!(parse("CR1"), None);
+        assert_eq!(parse("CR1 ="), None);
+        assert_eq!(parse("1234 5678"), None);
+        assert_eq!(parse("= 5"), None);
+    }
+}
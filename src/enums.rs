@@ -0,0 +1,134 @@
+//! C enums pasted with `:enum`, e.g. `enum { STATE_IDLE, STATE_RUN = 3 };`,
+//! read as the names and values of their members. Values are integer
+//! literals, previous members, or combinations of them with `|`, `<<` and
+//! `+` as in flag enums, e.g. `FLAG_B = 1 << 1` or `FLAG_AB = FLAG_A |
+//! FLAG_B`.
+
+use std::convert::TryFrom;
+
+/// Remove the `/* */` and `//` comments of C code.
+fn strip_comments(code: &str) -> String {
+    let mut out = String::new();
+    let mut rest = code;
+    loop {
+        let Some(start) = rest.find("/*").into_iter().chain(rest.find("//")).min() else {
+            out.push_str(rest);
+            return out;
+        };
+        out.push_str(&rest[..start]);
+        let comment = &rest[start..];
+        rest = if comment.starts_with("/*") {
+            out.push(' ');
+            comment.find("*/").map_or("", |end| &comment[end + 2..])
+        } else {
+            comment.find('\n').map_or("", |end| &comment[end..])
+        };
+    }
+}
+
+/// Value of an integer literal in C syntax, e.g. `0x10`, `010`, `0b1`,
+/// `16u` or `'A'`.
+fn literal(text: &str) -> Option<u64> {
+    if let Some(c) = text.strip_prefix('\'').and_then(|text| text.strip_suffix('\'')) {
+        let mut chars = c.chars();
+        return chars.next().filter(|c| c.is_ascii() && chars.next().is_none()).map(|c| c as u64);
+    }
+    let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        u64::from_str_radix(bin, 2).ok()
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+/// Value of an expression of a member, with the precedence of C: `+`
+/// before `<<` before `|`. Parentheses are ignored.
+fn eval(expr: &str, members: &[(String, u64)]) -> Option<u64> {
+    let mut value = 0;
+    for shifts in expr.split('|') {
+        let mut shifted: Option<u64> = None;
+        for sum in shifts.split("<<") {
+            let mut total = 0u64;
+            for atom in sum.split('+') {
+                let atom = atom.trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')');
+                let atom = literal(atom).or_else(|| members.iter().rev().find(|(name, _)| name == atom).map(|(_, value)| *value))?;
+                total = total.checked_add(atom)?;
+            }
+            shifted = Some(match shifted {
+                None => total,
+                Some(value) => value.checked_shl(u32::try_from(total).ok()?).filter(|shifted| shifted >> total == value)?,
+            });
+        }
+        value |= shifted?;
+    }
+    Some(value)
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Members of a C enum with their values, in order. The text is the enum
+/// declaration, or only the list of its members.
+pub fn parse(text: &str) -> Result<Vec<(String, u64)>, String> {
+    let code = strip_comments(text);
+    let body = match code.split_once('{') {
+        Some((_, body)) => body.split_once('}').ok_or("missing } at the end of the enum")?.0,
+        None => &code,
+    };
+    let mut members: Vec<(String, u64)> = Vec::new();
+    for member in body.split(',').map(str::trim).filter(|member| !member.is_empty()) {
+        let (name, expr) = match member.split_once('=') {
+            Some((name, expr)) => (name.trim(), Some(expr.trim())),
+            None => (member, None),
+        };
+        if !is_identifier(name) {
+            return Err(format!("invalid enum member {}", member));
+        }
+        let value = match expr {
+            Some(expr) => eval(expr, &members).ok_or_else(|| format!("invalid value {} of {}, expect a 64-bit value", expr, name))?,
+            None => match members.last() {
+                Some((last, value)) => value.checked_add(1).ok_or_else(|| format!("{} after {} does not fit in 64 bits", name, last))?,
+                None => 0,
+            },
+        };
+        members.push((name.to_string(), value));
+    }
+    if members.is_empty() {
+        return Err("no enum members".to_string());
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod test_enums {
+    use super::*;
+
+    fn members(text: &str) -> Vec<(String, u64)> {
+        parse(text).unwrap()
+    }
+
+    fn named(pairs: &[(&str, u64)]) -> Vec<(String, u64)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(members("typedef enum { STATE_IDLE, STATE_RUN = 3, STATE_STOP } state_t;"),
+            named(&[("STATE_IDLE", 0), ("STATE_RUN", 3), ("STATE_STOP", 4)]));
+        assert_eq!(members("enum flags {\n  FLAG_A = 1 << 0, // first\n  FLAG_B = (1u << 1), /* second */\n  FLAG_AB = FLAG_A | FLAG_B,\n};"),
+            named(&[("FLAG_A", 1), ("FLAG_B", 2), ("FLAG_AB", 3)]));
+        assert_eq!(members("A = 0x10, B = 010, C = 'A', D = 1 << 2 + 1, E = 0b11UL"),
+            named(&[("A", 16), ("B", 8), ("C", 65), ("D", 8), ("E", 3)]));
+        assert_eq!(parse("enum { A = -1 }"), Err("invalid value -1 of A, expect a 64-bit value".to_string()));
+        assert_eq!(parse("enum { A = 1 << 64 }"), Err("invalid value 1 << 64 of A, expect a 64-bit value".to_string()));
+        assert_eq!(parse("enum { A = 0xffffffffffffffff, B }"), Err("B after A does not fit in 64 bits".to_string()));
+        assert_eq!(parse("enum { A B }"), Err("invalid enum member A B".to_string()));
+        assert_eq!(parse("enum { A"), Err("missing } at the end of the enum".to_string()));
+        assert_eq!(parse("enum {}"), Err("no enum members".to_string()));
+    }
+}
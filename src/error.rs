@@ -0,0 +1,196 @@
+//! Errors of conversions and commands.
+
+use std::error::Error;
+use std::fmt;
+use std::num::IntErrorKind;
+use crate::base::Base;
+
+/// Error of reading or converting a value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConvertError {
+    /// Nothing to read.
+    Empty,
+    /// A digit that does not belong to the base, with the byte position of
+    /// the first one in the input when known.
+    InvalidDigit { input: String, base: Base, position: Option<usize> },
+    /// Value too large for 64 bits.
+    Overflow { input: String },
+    /// Value too large for a field or the configured width, in bits.
+    TooWide(u32),
+    UnknownFunction(String),
+    /// Function called with a wrong number of arguments.
+    ArgCount { name: String, expected: usize, got: usize },
+    /// Function call without a result, e.g. `freq(0)`.
+    Undefined(String),
+    /// Function argument out of its domain, with the accepted values.
+    Domain { call: String, expected: &'static str },
+}
+
+impl ConvertError {
+    /// Error of reading `input` in `base`.
+    pub fn parse(input: &str, base: &Base, kind: IntErrorKind) -> Self {
+        match kind {
+            IntErrorKind::Empty => ConvertError::Empty,
+            IntErrorKind::PosOverflow => ConvertError::Overflow { input: input.to_string() },
+            _ => ConvertError::InvalidDigit { input: input.to_string(), base: base.clone(), position: None },
+        }
+    }
+
+    /// Set the position of the invalid digit, if the error is one.
+    pub fn at(mut self, at: Option<usize>) -> Self {
+        if let ConvertError::InvalidDigit { position, .. } = &mut self {
+            *position = at;
+        }
+        self
+    }
+}
+
+/// Inputs longer than this, e.g. binary strings of logic analyzer exports,
+/// are shortened in messages.
+const LONG: usize = 40;
+
+/// Characters of a long input shown around a position.
+const CONTEXT: usize = 12;
+
+/// Start of a long input and its length, e.g. `0b0101…(1002 characters)`.
+fn shorten(input: &str) -> String {
+    let count = input.chars().count();
+    if count <= LONG {
+        return input.to_string();
+    }
+    format!("{}…({} characters)", input.chars().take(CONTEXT).collect::<String>(), count)
+}
+
+/// Characters of `input` around the byte `position`, the one there marked,
+/// e.g. `…0110[2]0101…`.
+fn excerpt(input: &str, position: usize) -> String {
+    let before: Vec<char> = input[..position].chars().collect();
+    let mut after = input[position..].chars();
+    let digit = after.next().unwrap_or(' ');
+    let after: String = after.by_ref().take(CONTEXT).collect();
+    let start = before.len().saturating_sub(CONTEXT);
+    format!("{}{}[{}]{}{}",
+        if start > 0 { "…" } else { "" },
+        before[start..].iter().collect::<String>(),
+        digit,
+        after,
+        if input[position..].chars().count() > CONTEXT + 1 { "…" } else { "" })
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Empty => write!(f, "empty value"),
+            ConvertError::InvalidDigit { input, base, position: Some(position) } if input.chars().count() > LONG => {
+                let character = input[..*position].chars().count() + 1;
+                write!(f, "invalid {} digit at character {} of {}: {}", base, character, input.chars().count(), excerpt(input, *position))
+            }
+            ConvertError::InvalidDigit { input, base, .. } => write!(f, "invalid {} digit in {}", base, shorten(input)),
+            ConvertError::Overflow { input } => write!(f, "{} does not fit in 64 bits", shorten(input)),
+            ConvertError::TooWide(bits) => write!(f, "value does not fit in {} bits", bits),
+            ConvertError::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            ConvertError::ArgCount { name, expected, got } => {
+                write!(f, "{} takes {} argument(s), got {}", name, expected, got)
+            }
+            ConvertError::Undefined(call) => write!(f, "{} is undefined", call),
+            ConvertError::Domain { call, expected } => write!(f, "invalid {}, expect {}", call, expected),
+        }
+    }
+}
+
+impl Error for ConvertError {}
+
+/// Error of running a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+    /// Command not understood.
+    Syntax,
+    /// Unbalanced double quotes in a word.
+    Quoting(String),
+    UnknownBase(String),
+    UnknownSetting(String),
+    /// Value not accepted by a setting, with the accepted values.
+    InvalidValue { key: String, value: String, expected: String },
+    NothingToUndo,
+    /// No conversion yet, e.g. for `:bookmark`.
+    NoResult,
+    /// No numbers in the clipboard, for `:paste`.
+    NothingToPaste,
+    /// History expansion, e.g. `!5`, without a matching entry.
+    NoHistory(String),
+    /// `:tui` run from the TUI.
+    TuiRunning,
+    /// Command left out of this build, e.g. `:tui` without the `tui` feature.
+    Unsupported(&'static str),
+    NoConfig,
+    /// Bad content of the config file.
+    Config(String),
+    /// File that could not be read or written.
+    Io(String),
+    /// Failure of a decoder command, e.g. `:can`.
+    Decode(String),
+    /// Stopped with Ctrl+C.
+    Cancelled,
+    /// Failure of one command of a `;`-separated list.
+    Segment { index: usize, segment: String, error: Box<CommandError> },
+}
+
+impl CommandError {
+    pub fn invalid(key: &str, value: &str, expected: &str) -> Self {
+        CommandError::InvalidValue { key: key.to_string(), value: value.to_string(), expected: expected.to_string() }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Syntax => write!(f, "wrong command format"),
+            CommandError::Quoting(word) => write!(f, "bad quoting in {}", word),
+            CommandError::UnknownBase(name) => write!(f, "no base {}", name),
+            CommandError::UnknownSetting(key) => write!(f, "no setting {}", key),
+            CommandError::InvalidValue { key, value, expected } => {
+                write!(f, "invalid {} {}, expect {}", key, value, expected)
+            }
+            CommandError::NothingToUndo => write!(f, "nothing to undo"),
+            CommandError::NoResult => write!(f, "no result yet"),
+            CommandError::NothingToPaste => write!(f, "no numbers in clipboard"),
+            CommandError::NoHistory(event) => write!(f, "no history entry {}", event),
+            CommandError::TuiRunning => write!(f, "already in the TUI"),
+            CommandError::Unsupported(name) => write!(f, "{} is not available in this build", name),
+            CommandError::NoConfig => write!(f, "no config file"),
+            CommandError::Cancelled => write!(f, "cancelled"),
+            CommandError::Config(msg) | CommandError::Io(msg) | CommandError::Decode(msg) => write!(f, "{}", msg),
+            CommandError::Segment { index, segment, error } => {
+                write!(f, "in segment {} `{}`: {}", index, segment, error)
+            }
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+#[cfg(test)]
+mod test_error {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let e = ConvertError::parse("zz", &Base::Dec, IntErrorKind::InvalidDigit);
+        assert_eq!(e.to_string(), "invalid dec digit in zz");
+        let e = ConvertError::parse("99999999999999999999", &Base::Dec, IntErrorKind::PosOverflow);
+        assert_eq!(e, ConvertError::Overflow { input: "99999999999999999999".to_string() });
+        assert_eq!(ConvertError::parse("", &Base::Dec, IntErrorKind::Empty), ConvertError::Empty);
+    }
+
+    #[test]
+    fn test_long_input() {
+        let input = format!("0b{}2{}", "01".repeat(300), "1".repeat(99));
+        let e = ConvertError::parse(&input, &Base::Bin, IntErrorKind::InvalidDigit).at(Some(602));
+        assert_eq!(e.to_string(), "invalid bin digit at character 603 of 702: …010101010101[2]111111111111…");
+        let e = ConvertError::parse("0b012", &Base::Bin, IntErrorKind::InvalidDigit).at(Some(4));
+        assert_eq!(e.to_string(), "invalid bin digit in 0b012");
+        let e = ConvertError::parse(&input, &Base::Bin, IntErrorKind::PosOverflow);
+        assert_eq!(e.to_string(), "0b0101010101…(702 characters) does not fit in 64 bits");
+        assert_eq!(excerpt("12x", 2), "12[x]");
+    }
+}
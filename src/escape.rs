@@ -0,0 +1,139 @@
+//! Strings with C escape sequences and their bytes: `:tobytes "\x02OK\r\n"`
+//! reads the escapes, `\x02`, `\0`, `\t`..., and `:tostr` writes bytes back
+//! as an escaped string, e.g. of a serial protocol frame.
+
+use crate::bytes;
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+/// Register the `:tobytes` and `:tostr` commands.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "tobytes",
+        help: &[(":tobytes \"<string>\"", "bytes of a string with escapes \\x02, \\0, \\t, \\r, \\n...")],
+        completions: &[],
+        run: bytes_command,
+    });
+    registry.register(Plugin {
+        name: "tostr",
+        help: &[(":tostr <bytes>", "bytes as a string, with escapes for the unprintable ones")],
+        completions: &[],
+        run: string_command,
+    });
+}
+
+/// Bytes of a string with C escape sequences: `\xHH` of one or two hex
+/// digits, `\0` to `\377` in octal, `\n`, `\r`, `\t`, `\a`, `\b`, `\f`,
+/// `\v`, `\e` and escaped `\\`, `\"`, `\'` and `\?`. Other characters are
+/// written in UTF-8.
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        let escape = chars.next().ok_or("unfinished escape \\ at the end")?;
+        let byte = match escape {
+            'x' | '0'..='7' => {
+                let (radix, max) = if escape == 'x' { (16, 2) } else { (8, 3) };
+                let mut digits = if escape == 'x' { String::new() } else { escape.to_string() };
+                while digits.len() < max && chars.peek().is_some_and(|c| c.is_digit(radix)) {
+                    digits.extend(chars.next());
+                }
+                u32::from_str_radix(&digits, radix).ok().filter(|value| *value <= 0xff)
+                    .ok_or_else(|| format!("invalid escape \\{}{}", if escape == 'x' { "x" } else { "" }, digits))? as u8
+            }
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            'e' => 0x1b,
+            '\\' | '"' | '\'' | '?' => escape as u8,
+            _ => return Err(format!("unknown escape \\{}", escape)),
+        };
+        out.push(byte);
+    }
+    Ok(out)
+}
+
+/// Bytes as a string, printable ASCII as is and the other bytes escaped,
+/// e.g. `\x02OK\r\n`, so that `unescape` reads them back.
+pub fn escape(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for (i, byte) in bytes.iter().enumerate() {
+        // `\0` before a digit would read as an octal escape of several digits.
+        let digit_next = bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+        match byte {
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            b'\0' if !digit_next => out.push_str("\\0"),
+            b' ' => out.push(' '),
+            byte if byte.is_ascii_graphic() => out.push(*byte as char),
+            byte => out += &format!("\\x{:02x}", byte),
+        }
+    }
+    out
+}
+
+/// Handle `:tobytes "<string>"`.
+pub fn bytes_command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    if args.is_empty() {
+        return Err("Usage: :tobytes \"<string>\", e.g. :tobytes \"\\x02OK\\r\\n\"".to_string());
+    }
+    let bytes = unescape(&args.join(" "))?;
+    Ok(format!("{} ({} bytes)", bytes::hex_list(&bytes), bytes.len()))
+}
+
+/// Handle `:tostr <bytes>`.
+pub fn string_command(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+    let bytes = bytes::parse_hex(args)?;
+    if bytes.is_empty() {
+        return Err("Usage: :tostr <bytes>, e.g. :tostr 02 4f 4b 0d 0a".to_string());
+    }
+    Ok(format!("\"{}\"", escape(&bytes)))
+}
+
+#[cfg(test)]
+mod test_escape {
+    use super::*;
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("\\x02OK\\r\\n"), Ok(vec![0x02, b'O', b'K', b'\r', b'\n']));
+        assert_eq!(unescape("a\\0b\\tc"), Ok(vec![b'a', 0, b'b', b'\t', b'c']));
+        assert_eq!(unescape("\\x7\\101\\377\\e"), Ok(vec![0x07, b'A', 0xff, 0x1b]));
+        assert_eq!(unescape("\\\\\\\"\\'"), Ok(b"\\\"'".to_vec()));
+        assert_eq!(unescape("é"), Ok(vec![0xc3, 0xa9]));
+        assert_eq!(unescape("\\x"), Err("invalid escape \\x".to_string()));
+        assert_eq!(unescape("\\400"), Err("invalid escape \\400".to_string()));
+        assert_eq!(unescape("\\q"), Err("unknown escape \\q".to_string()));
+        assert_eq!(unescape("a\\"), Err("unfinished escape \\ at the end".to_string()));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(&[0x02, b'O', b'K', b'\r', b'\n']), "\\x02OK\\r\\n");
+        assert_eq!(escape(b"say \"hi\"\\\0\t\xff"), "say \\\"hi\\\"\\\\\\0\\t\\xff");
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(unescape(&escape(&bytes)), Ok(bytes));
+        assert_eq!(escape(b"\x001"), "\\x001");
+    }
+
+    #[test]
+    fn test_commands() {
+        let parse = |_: &str| Ok(0);
+        assert_eq!(bytes_command(&["\\x02OK\\r\\n"], &parse), Ok("02 4f 4b 0d 0a (5 bytes)".to_string()));
+        assert_eq!(bytes_command(&["a", "b"], &parse), Ok("61 20 62 (3 bytes)".to_string()));
+        assert!(bytes_command(&[], &parse).is_err());
+        assert_eq!(string_command(&["02 4f 4b 0d 0a"], &parse), Ok("\"\\x02OK\\r\\n\"".to_string()));
+        assert!(string_command(&["zz"], &parse).is_err());
+    }
+}
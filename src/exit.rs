@@ -0,0 +1,33 @@
+//! Exit codes of the non-interactive modes.
+
+use crate::error::ConvertError;
+
+/// An input could not be read as a number.
+pub const INVALID: i32 = 1;
+/// Bad command line arguments or command.
+pub const USAGE: i32 = 2;
+/// A value is too large, for 64 bits or for the configured width.
+pub const OVERFLOW: i32 = 3;
+/// A file or stream could not be read or written.
+pub const IO: i32 = 4;
+
+/// Exit code for a conversion error.
+pub fn code(e: &ConvertError) -> i32 {
+    match e {
+        ConvertError::Overflow { .. } | ConvertError::TooWide(_) => OVERFLOW,
+        _ => INVALID,
+    }
+}
+
+#[cfg(test)]
+mod test_exit {
+    use super::*;
+
+    #[test]
+    fn test_code() {
+        assert_eq!(code(&ConvertError::Overflow { input: "99999999999999999999".to_string() }), OVERFLOW);
+        assert_eq!(code(&ConvertError::TooWide(8)), OVERFLOW);
+        assert_eq!(code(&ConvertError::Empty), INVALID);
+        assert_eq!(code(&ConvertError::UnknownFunction("foo".to_string())), INVALID);
+    }
+}
@@ -0,0 +1,106 @@
+//! Export of the session's conversions with `:export <path>`, in a format
+//! chosen by the file extension: a Markdown table (`.md`), CSV (`.csv`),
+//! TSV (`.tsv`) or a JSON array (`.json`).
+
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use crate::base;
+use crate::conversion::{Conversion, OutputFormat};
+use crate::error::CommandError;
+use crate::settings::Settings;
+
+/// Format of an export file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Markdown,
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl Format {
+    /// Format of a path, from its extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "md" => Some(Format::Markdown),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Render conversions, with the value in every base, formatted with `settings`.
+pub fn render(conversions: &[&Conversion], format: Format, settings: &Settings) -> String {
+    match format {
+        Format::Markdown => {
+            let bases = base::all();
+            let names: Vec<String> = bases.iter().map(|base| base.to_string()).collect();
+            let mut ret = format!("| input | {} |\n|{}\n", names.join(" | "), " --- |".repeat(bases.len() + 1));
+            for conversion in conversions {
+                let cells: Vec<String> = std::iter::once(conversion.input.clone())
+                    .chain(bases.iter().map(|base| conversion.repr(base, settings)))
+                    .map(|cell| cell.replace('|', "\\|"))
+                    .collect();
+                ret += &format!("| {} |\n", cells.join(" | "));
+            }
+            ret
+        }
+        Format::Csv | Format::Tsv => {
+            let format = if format == Format::Csv { OutputFormat::Csv } else { OutputFormat::Tsv };
+            let mut ret = format.header().unwrap_or_default() + "\n";
+            for conversion in conversions {
+                ret += &conversion.to_row(&format, settings);
+                ret += "\n";
+            }
+            ret
+        }
+        Format::Json => {
+            let values: Vec<Value> = conversions.iter().map(|conversion| conversion.to_value(settings)).collect();
+            Value::Array(values).to_string() + "\n"
+        }
+    }
+}
+
+/// Write conversions to `path`, in the format of its extension.
+pub fn write(path: &Path, conversions: &[&Conversion], settings: &Settings) -> Result<(), CommandError> {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    let format = Format::from_path(path).ok_or_else(|| CommandError::invalid("export format", &extension, "md, csv, tsv or json"))?;
+    fs::write(path, render(conversions, format, settings))
+        .map_err(|e| CommandError::Io(format!("cannot write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod test_export {
+    use super::*;
+    use crate::converter;
+
+    #[test]
+    fn test_render() {
+        let settings = Settings::default();
+        let first = converter::convert("ff", &settings).unwrap();
+        let second = converter::convert("htons(1)", &settings).unwrap();
+        let conversions = vec![&first, &second];
+        // Bases registered by other tests may add columns at the end.
+        let table = render(&conversions, Format::Markdown, &settings);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| input | hex | dec | bin | oct | size |"));
+        assert!(lines[1].starts_with("| --- | --- | --- | --- | --- | --- |"));
+        assert!(lines[2].starts_with("| ff | 0xff | 255 | 1111_1111 | 0o377 | 255 B |"));
+        assert!(lines[3].starts_with("| htons(1) | 0x0100 | 256 | 0000_0001_0000_0000 | 0o000400 | 256 B |"));
+        let csv = render(&conversions[..1], Format::Csv, &settings);
+        assert!(csv.starts_with("input,hex,dec,bin,oct,size") && csv.contains("\nff,0xff,255,1111_1111,0o377,255 B"));
+        assert!(render(&conversions, Format::Json, &settings).starts_with("[{\"base\":\"hex\""));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::from_path(Path::new("results.MD")), Some(Format::Markdown));
+        assert_eq!(Format::from_path(Path::new("out/results.json")), Some(Format::Json));
+        assert_eq!(Format::from_path(Path::new("results.txt")), None);
+        assert_eq!(Format::from_path(Path::new("results")), None);
+    }
+}
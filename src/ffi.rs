@@ -0,0 +1,178 @@
+//! C interface of the converter, built with the `ffi` feature. The functions
+//! are declared in `include/base_converter.h`.
+//!
+//! Text results are written like `snprintf`: at most `len - 1` bytes and a
+//! terminating nul, and the full length is returned so that a larger buffer
+//! can be retried. Errors are returned as negative `BC_ERR_*` codes.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use crate::base::Base;
+use crate::error::ConvertError;
+use crate::exit;
+use crate::settings::Settings;
+
+/// The input is not a number in its base.
+pub const BC_ERR_INVALID: c_int = -exit::INVALID;
+/// A null pointer, a string that is not UTF-8 or an unknown base name.
+pub const BC_ERR_USAGE: c_int = -exit::USAGE;
+/// The value does not fit in 64 bits or in the width.
+pub const BC_ERR_OVERFLOW: c_int = -exit::OVERFLOW;
+
+/// Read `input` in the base named `base`, e.g. "hex", into `*out`. Return 0
+/// on success.
+///
+/// # Safety
+/// `input` and `base` must be nul-terminated strings and `out` must point to
+/// a writable `uint64_t`.
+#[no_mangle]
+pub unsafe extern "C" fn bc_parse(input: *const c_char, base: *const c_char, out: *mut u64) -> c_int {
+    if out.is_null() {
+        return BC_ERR_USAGE;
+    }
+    let base = match read_base(base) {
+        Ok(base) => base,
+        Err(code) => return code,
+    };
+    match read_str(input).map(|input| base.to_num(input)) {
+        Some(Ok(num)) => {
+            *out = num;
+            0
+        }
+        Some(Err(e)) => error_code(&e),
+        None => BC_ERR_USAGE,
+    }
+}
+
+/// Format `value` in the base named `base`, padded to `width` bits (0 for no
+/// padding), into `buf` of `len` bytes. Return the length of the text.
+///
+/// # Safety
+/// `base` must be a nul-terminated string and `buf` must point to `len`
+/// writable bytes, or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn bc_format(value: u64, base: *const c_char, width: u32, buf: *mut c_char, len: usize) -> c_int {
+    let base = match read_base(base) {
+        Ok(base) => base,
+        Err(code) => return code,
+    };
+    if width > 64 {
+        return BC_ERR_USAGE;
+    }
+    if let Err(e) = crate::converter::check_width(value, width) {
+        return error_code(&e);
+    }
+    let settings = Settings { width, ..Settings::default() };
+    write_str(&base.format(value, &settings), buf, len)
+}
+
+/// Convert `input` from the base named `from` to the base named `to`, padded
+/// to `width` bits (0 for no padding), into `buf` of `len` bytes. Function
+/// calls like `htonl(1)` are accepted. Return the length of the text.
+///
+/// # Safety
+/// `input`, `from` and `to` must be nul-terminated strings and `buf` must
+/// point to `len` writable bytes, or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn bc_convert(
+    input: *const c_char,
+    from: *const c_char,
+    to: *const c_char,
+    width: u32,
+    buf: *mut c_char,
+    len: usize,
+) -> c_int {
+    let (in_base, out_base) = match (read_base(from), read_base(to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(code), _) | (_, Err(code)) => return code,
+    };
+    let input = match read_str(input) {
+        Some(input) => input,
+        None => return BC_ERR_USAGE,
+    };
+    if width > 64 {
+        return BC_ERR_USAGE;
+    }
+    let settings = Settings { in_base, out_base, width, ..Settings::default() };
+    match crate::converter::convert(input, &settings) {
+        Ok(conversion) => write_str(&conversion.text, buf, len),
+        Err(e) => error_code(&e),
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+unsafe fn read_base(name: *const c_char) -> Result<Base, c_int> {
+    read_str(name).and_then(Base::from_name).ok_or(BC_ERR_USAGE)
+}
+
+/// Copy `text` to `buf` like `snprintf`.
+unsafe fn write_str(text: &str, buf: *mut c_char, len: usize) -> c_int {
+    if len > 0 && !buf.is_null() {
+        let n = text.len().min(len - 1);
+        ptr::copy_nonoverlapping(text.as_ptr() as *const c_char, buf, n);
+        *buf.add(n) = 0;
+    }
+    text.len() as c_int
+}
+
+fn error_code(e: &ConvertError) -> c_int {
+    -exit::code(e)
+}
+
+#[cfg(test)]
+mod test_ffi {
+    use super::*;
+    use std::ffi::CString;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    fn text(buf: &[c_char]) -> String {
+        unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut num = 0;
+        unsafe {
+            assert_eq!(bc_parse(c("0xff").as_ptr(), c("hex").as_ptr(), &mut num), 0);
+            assert_eq!(num, 255);
+            assert_eq!(bc_parse(c("12").as_ptr(), c("bin").as_ptr(), &mut num), BC_ERR_INVALID);
+            assert_eq!(bc_parse(c("1").as_ptr(), c("hx").as_ptr(), &mut num), BC_ERR_USAGE);
+            assert_eq!(bc_parse(ptr::null(), c("hex").as_ptr(), &mut num), BC_ERR_USAGE);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let mut buf = [0 as c_char; 8];
+        unsafe {
+            assert_eq!(bc_format(16, c("bin").as_ptr(), 0, buf.as_mut_ptr(), buf.len()), 9);
+            assert_eq!(text(&buf), "0001_00");
+            assert_eq!(bc_format(0x12, c("hex").as_ptr(), 16, buf.as_mut_ptr(), buf.len()), 6);
+            assert_eq!(text(&buf), "0x0012");
+            assert_eq!(bc_format(0x100, c("hex").as_ptr(), 8, buf.as_mut_ptr(), buf.len()), BC_ERR_OVERFLOW);
+            assert_eq!(bc_format(1, c("hex").as_ptr(), 0, ptr::null_mut(), 0), 3);
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        let mut buf = [0 as c_char; 32];
+        unsafe {
+            let n = bc_convert(c("htons(0x1234)").as_ptr(), c("hex").as_ptr(), c("hex").as_ptr(), 0, buf.as_mut_ptr(), buf.len());
+            assert_eq!(text(&buf), "0x3412 (u16)");
+            assert_eq!(n, 12);
+            let n = bc_convert(c("99999999999999999999").as_ptr(), c("dec").as_ptr(), c("hex").as_ptr(), 0, buf.as_mut_ptr(), buf.len());
+            assert_eq!(n, BC_ERR_OVERFLOW);
+        }
+    }
+}
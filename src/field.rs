@@ -0,0 +1,111 @@
+//! Register field layouts, defined with `:deffield`, e.g.
+//! `:deffield mode[7:4] en[3] div[2:0]`. Fields split the last result into
+//! named bit ranges, listed by `:deffield` and drawn by the TUI.
+
+use std::fmt;
+use crate::error::CommandError;
+
+/// Named range of bits, from `msb` down to `lsb`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub msb: u32,
+    pub lsb: u32,
+}
+
+impl Field {
+    /// Parse a field as `<name>[<msb>:<lsb>]`, or `<name>[<bit>]` for a
+    /// single bit.
+    pub fn parse(spec: &str) -> Result<Field, CommandError> {
+        let invalid = || CommandError::invalid("field", spec, "<name>[<msb>:<lsb>] or <name>[<bit>]");
+        let (name, range) = spec.strip_suffix(']').and_then(|spec| spec.split_once('[')).ok_or_else(invalid)?;
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let bit = |bit: &str| bit.trim().parse::<u32>().ok().filter(|bit| *bit < 64);
+        let (msb, lsb) = match range.split_once(':') {
+            Some((msb, lsb)) => (bit(msb), bit(lsb)),
+            None => (bit(range), bit(range)),
+        };
+        match (msb, lsb) {
+            (Some(msb), Some(lsb)) if valid_name && msb >= lsb => Ok(Field { name: name.to_string(), msb, lsb }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Number of bits.
+    pub fn width(&self) -> u32 {
+        self.msb - self.lsb + 1
+    }
+
+    /// Bits of the field in `num`, shifted down.
+    pub fn value(&self, num: u64) -> u64 {
+        let mask = if self.width() == 64 { u64::MAX } else { (1 << self.width()) - 1 };
+        num >> self.lsb & mask
+    }
+
+    /// Check whether the field contains `bit`.
+    pub fn contains(&self, bit: u32) -> bool {
+        (self.lsb..=self.msb).contains(&bit)
+    }
+
+    /// Bit range, e.g. `7:4`, or `3` for a single bit.
+    pub fn range(&self) -> String {
+        if self.msb == self.lsb {
+            self.msb.to_string()
+        } else {
+            format!("{}:{}", self.msb, self.lsb)
+        }
+    }
+
+    /// Field value as shown in layouts: the bit for single bits, else hex.
+    pub fn format(&self, num: u64) -> String {
+        if self.width() == 1 {
+            self.value(num).to_string()
+        } else {
+            format!("{:#x}", self.value(num))
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.name, self.range())
+    }
+}
+
+/// Add fields to a layout, replacing the fields of the same name. The layout
+/// is kept ordered from the most significant field.
+pub fn define(layout: &mut Vec<Field>, fields: Vec<Field>) {
+    for field in fields {
+        layout.retain(|old| old.name != field.name);
+        layout.push(field);
+    }
+    layout.sort_by(|a, b| b.msb.cmp(&a.msb).then(b.lsb.cmp(&a.lsb)));
+}
+
+#[cfg(test)]
+mod test_field {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Field::parse("mode[7:4]"), Ok(Field { name: "mode".to_string(), msb: 7, lsb: 4 }));
+        assert_eq!(Field::parse("en[3]"), Ok(Field { name: "en".to_string(), msb: 3, lsb: 3 }));
+        assert_eq!(Field::parse("all[63:0]").unwrap().value(u64::MAX), u64::MAX);
+        for spec in ["mode", "mode[4:7]", "mode[64]", "1x[3]", "mode[a]", "[3]"] {
+            assert!(Field::parse(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut layout = Vec::new();
+        let parse = |specs: &[&str]| specs.iter().map(|spec| Field::parse(spec).unwrap()).collect();
+        define(&mut layout, parse(&["div[2:0]", "mode[7:4]", "en[3]"]));
+        define(&mut layout, parse(&["div[1:0]"]));
+        let names: Vec<String> = layout.iter().map(|field| field.to_string()).collect();
+        assert_eq!(names, vec!["mode[7:4]", "en[3]", "div[1:0]"]);
+        assert_eq!(layout.iter().map(|field| field.format(0x3a)).collect::<Vec<_>>(), vec!["0x3", "1", "0x2"]);
+        assert!(layout[0].contains(4) && !layout[0].contains(3));
+    }
+}
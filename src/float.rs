@@ -0,0 +1,68 @@
+//! Float literals read as their IEEE 754 bit patterns when the `float`
+//! setting is on: `3.14f` is the f32 0x4048f5c3 and `2.5e3` the f64
+//! 0x40a3880000000000. Literals need a fraction, an exponent or the `f`
+//! suffix, so that plain integers are still read as integers.
+
+use crate::func::Value;
+
+/// Bits of a float literal, f32 with the `f` suffix, else f64, with a note
+/// of the value and of the value stored when the f32 is rounded, e.g. `3.14
+/// as f32, stored as 3.140000104904175`. None if the text is not a float
+/// literal or is out of range.
+pub fn parse(text: &str) -> Option<Value> {
+    let text = text.trim();
+    let (digits, single) = match text.strip_suffix(['f', 'F']) {
+        Some(digits) => (digits, true),
+        None => (text, false),
+    };
+    let unsigned = digits.strip_prefix(['-', '+']).unwrap_or(digits);
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        || !unsigned.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'))
+        || !(single || unsigned.contains(['.', 'e', 'E']))
+    {
+        return None;
+    }
+    let exact: f64 = digits.parse().ok().filter(|x: &f64| x.is_finite())?;
+    if single {
+        let x = exact as f32;
+        if !x.is_finite() {
+            return None;
+        }
+        let mut note = format!("{} as f32", x);
+        if x as f64 != exact {
+            note += &format!(", stored as {}", x as f64);
+        }
+        Some(Value { num: x.to_bits() as u64, width: Some(32), note: Some(note) })
+    } else {
+        Some(Value { num: exact.to_bits(), width: Some(64), note: Some(format!("{} as f64", exact)) })
+    }
+}
+
+#[cfg(test)]
+mod test_float {
+    use super::*;
+
+    fn bits(text: &str) -> Option<u64> {
+        parse(text).map(|value| value.num)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(bits("3.14f"), Some(0x4048_f5c3));
+        assert_eq!(bits("2.5e3"), Some(0x40a3_8800_0000_0000));
+        assert_eq!(bits("-2.5"), Some(0xc004_0000_0000_0000));
+        assert_eq!(bits("1f"), Some(0x3f80_0000));
+        assert_eq!(bits(".5F"), Some(0x3f00_0000));
+        assert_eq!(bits("1e-3"), Some(0x3f50_624d_d2f1_a9fc));
+        assert_eq!(parse("3.14f").unwrap().note.as_deref(), Some("3.14 as f32, stored as 3.140000104904175"));
+        assert_eq!(parse("0.5f").unwrap().note.as_deref(), Some("0.5 as f32"));
+        assert_eq!(parse("2.5e3").unwrap().note.as_deref(), Some("2500 as f64"));
+        assert_eq!(bits("12"), None);
+        assert_eq!(bits("1e39f"), None);
+        assert_eq!(bits("1e309"), None);
+        assert_eq!(bits("inf"), None);
+        assert_eq!(bits("nanf"), None);
+        assert_eq!(bits("ff"), None);
+        assert_eq!(bits("1.2.3"), None);
+    }
+}
@@ -0,0 +1,757 @@
+//! Functions that can be called on input values, e.g. `htonl(0x12345678)`.
+//! Arguments are parsed with the current input base and can themselves be
+//! function calls.
+
+use std::convert::TryFrom;
+use crate::base::Base;
+use crate::converter;
+use crate::error::ConvertError;
+use crate::prime;
+use crate::settings::Settings;
+use crate::units::{self, Rounding};
+
+/// Result of a function call.
+pub struct Value {
+    pub num: u64,
+    /// Bit width the function works on, if any. Used to pad the output.
+    pub width: Option<u32>,
+    /// Text shown after the result, e.g. `1.5 MiB` for `bytes(0x180000)`.
+    pub note: Option<String>,
+}
+
+impl Value {
+    fn new(num: u64) -> Self {
+        Value { num, width: None, note: None }
+    }
+}
+
+type FuncImpl = fn(&[u64], &Settings) -> Result<Value, ConvertError>;
+
+/// Implementation of a function reading its single argument as text, e.g. a
+/// quantity with a unit.
+type TextFuncImpl = fn(&str, ArgParser, &Settings) -> Result<Value, ConvertError>;
+
+/// Parser used for literal arguments.
+pub type ArgParser<'a> = &'a dyn Fn(&str) -> Result<u64, ConvertError>;
+
+/// Kind of a function argument.
+#[derive(Clone, Copy, PartialEq)]
+enum Arg {
+    /// Value read in the input base.
+    Value,
+    /// Count, e.g. of bits, read in decimal unless it has a prefix.
+    Count,
+    /// Value read in the input base with an optional `-` sign, passed in
+    /// two's complement.
+    Signed,
+    /// Any number of counts, at least one. Only last.
+    Counts,
+}
+
+const VALUE: &[Arg] = &[Arg::Value];
+const COUNT: &[Arg] = &[Arg::Count];
+const VALUE_COUNT: &[Arg] = &[Arg::Value, Arg::Count];
+const SIGNED_COUNT: &[Arg] = &[Arg::Signed, Arg::Count];
+const COUNTS: &[Arg] = &[Arg::Counts];
+const VALUES: &[Arg] = &[Arg::Value, Arg::Value];
+
+/// Table of available functions: name, arguments, implementation.
+const FUNCTIONS: &[(&str, &[Arg], FuncImpl)] = &[
+    ("htons", VALUE, swap16),
+    ("ntohs", VALUE, swap16),
+    ("htonl", VALUE, swap32),
+    ("ntohl", VALUE, swap32),
+    ("bytes", VALUE, bytes),
+    ("ratio", VALUE, ratio),
+    ("duration", VALUE, duration),
+    ("onehot", COUNT, onehot),
+    ("decode_onehot", VALUE, decode_onehot),
+    ("bits", VALUE, bits),
+    ("gcd", VALUES, gcd),
+    ("lcm", VALUES, lcm),
+    ("isprime", VALUE, isprime),
+    ("roundto", VALUE_COUNT, roundto),
+    ("floorto", VALUE_COUNT, floorto),
+    ("ceilto", VALUE_COUNT, ceilto),
+    ("factor", VALUE, factor),
+    ("frombits", COUNTS, frombits),
+    ("ones", VALUE_COUNT, ones),
+    ("bias", SIGNED_COUNT, bias),
+    ("unbias", VALUE_COUNT, unbias),
+];
+
+/// Table of functions reading their argument as text.
+const TEXT_FUNCTIONS: &[(&str, TextFuncImpl)] = &[
+    ("size", size),
+    ("freq", freq),
+    ("period", period),
+    ("pct", pct),
+    ("frac", frac),
+    ("ticks", ticks),
+];
+
+/// Check if an input looks like a function call, i.e. `name(...)`.
+pub fn is_call(input: &str) -> bool {
+    split_call(input).is_some()
+}
+
+/// Evaluate a function call. `parse` is used to read literal arguments, and
+/// `settings` give the full scale of `pct`, `frac` and `ratio` and the tick
+/// rate of `ticks` and `duration`.
+pub fn eval(input: &str, parse: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    let (name, args) = match split_call(input) {
+        Some(call) => call,
+        None => {
+            return Ok(Value::new(parse(input)?));
+        }
+    };
+    // Unlike `ratio(x)`, `ratio(x, maxden=n)` approximates a real number.
+    if let ("ratio", [arg, max]) = (name, &args[..]) {
+        return approximate(arg, max);
+    }
+    if let Some((_, func)) = TEXT_FUNCTIONS.iter().find(|(n, _)| *n == name) {
+        return match args[..] {
+            [arg] => func(arg, parse, settings),
+            _ => Err(ConvertError::ArgCount { name: name.to_string(), expected: 1, got: args.len() }),
+        };
+    }
+    let (_, kinds, func) = FUNCTIONS.iter()
+        .find(|(n, _, _)| *n == name)
+        .ok_or_else(|| ConvertError::UnknownFunction(name.to_string()))?;
+    let variadic = kinds.last() == Some(&Arg::Counts);
+    if args.len() != kinds.len() && !(variadic && args.len() > kinds.len()) {
+        return Err(ConvertError::ArgCount { name: name.to_string(), expected: kinds.len(), got: args.len() });
+    }
+    let mut nums = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let kind = kinds.get(i).unwrap_or(&Arg::Counts);
+        let decimal = matches!(kind, Arg::Count | Arg::Counts) && arg.chars().all(|c| c.is_ascii_digit());
+        let negative = match arg.strip_prefix('-') {
+            Some(magnitude) if *kind == Arg::Signed => Some(magnitude.trim_start()),
+            _ => None,
+        };
+        nums.push(match (decimal, negative) {
+            (true, _) => Base::Dec.to_num(arg)?,
+            (_, Some(magnitude)) => eval(magnitude, parse, settings)?.num.wrapping_neg(),
+            _ => eval(arg, parse, settings)?.num,
+        });
+    }
+    func(&nums, settings)
+}
+
+/// Read a numeric command argument in the input base. Function calls are
+/// evaluated, and a `0x` or `0b` prefix takes precedence over the base.
+pub fn read_arg(arg: &str, settings: &Settings) -> Result<u64, ConvertError> {
+    let base = &settings.in_base;
+    let value = eval(arg, &|lit| {
+        let prefix = lit.trim().get(..2).unwrap_or("");
+        if prefix.eq_ignore_ascii_case("0x") {
+            Base::Hex.to_num(lit)
+        } else if prefix.eq_ignore_ascii_case("0b") && *base != Base::Hex {
+            Base::Bin.to_num(lit)
+        } else {
+            base.to_num(lit)
+        }
+    }, settings)?;
+    Ok(value.num)
+}
+
+/// Split `name(a, b)` into its name and arguments. Commas inside nested calls
+/// are not treated as separators.
+fn split_call(input: &str) -> Option<(&str, Vec<&str>)> {
+    let input = input.trim();
+    let open = input.find('(')?;
+    let inner = input.strip_suffix(')')?.get(open + 1..)?;
+    let name = input[..open].trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return None,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+    if !inner.trim().is_empty() {
+        args.push(inner[start..].trim());
+    }
+    Some((name, args))
+}
+
+/// Swap the bytes of a 16-bit value.
+fn swap16(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let num = u16::try_from(args[0]).map_err(|_| ConvertError::TooWide(16))?;
+    Ok(Value { num: num.swap_bytes() as u64, width: Some(16), note: None })
+}
+
+/// Swap the bytes of a 32-bit value.
+fn swap32(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let num = u32::try_from(args[0]).map_err(|_| ConvertError::TooWide(32))?;
+    Ok(Value { num: num.swap_bytes() as u64, width: Some(32), note: None })
+}
+
+/// Read a byte size, e.g. `size(1.5MiB)`. A prefix that could be binary or SI,
+/// as in `1MB` or `1M`, is noted with both readings. Arguments that are not
+/// sizes, e.g. `size(0x400)`, are evaluated as values.
+fn size(arg: &str, parse: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    match units::parse_size_as(arg, settings.binary_shorthand) {
+        Ok(num) => Ok(Value { note: units::ambiguity(arg, settings.binary_shorthand), ..Value::new(num) }),
+        Err(_) => eval(arg, parse, settings).map(|value| Value::new(value.num)),
+    }
+}
+
+/// Write a value as a byte size, e.g. `bytes(0x180000)` is `1.5 MiB`, with
+/// the SI size too when one is exact, e.g. `1953.125 KiB = 2 MB`.
+fn bytes(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let binary = units::format_size(args[0]);
+    let note = match units::format_si_size(args[0]) {
+        Some(si) => format!("{} = {}", binary, si),
+        None => binary,
+    };
+    Ok(Value { note: Some(note), ..Value::new(args[0]) })
+}
+
+/// Frequency of a period, e.g. `freq(10us)` is 100 kHz, in Hz rounded by
+/// `:round`. Periods without a unit are in ns, the unit of `period`.
+fn freq(arg: &str, parse: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    let ps = match units::parse_time(arg) {
+        Ok(ps) => ps,
+        Err(_) => eval(arg, parse, settings)?.num.checked_mul(1000)
+            .ok_or_else(|| ConvertError::Overflow { input: arg.to_string() })?,
+    };
+    invert(ps, 1_000_000_000_000, units::FREQUENCIES, &format!("freq({})", arg), settings)
+}
+
+/// Period of a frequency, e.g. `period(1kHz)` is 1 ms, in ns rounded by
+/// `:round`. Frequencies without a unit are in Hz, the unit of `freq`.
+fn period(arg: &str, parse: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    let mhz = match units::parse_frequency(arg) {
+        Ok(mhz) => mhz,
+        Err(_) => eval(arg, parse, settings)?.num.checked_mul(1000)
+            .ok_or_else(|| ConvertError::Overflow { input: arg.to_string() })?,
+    };
+    invert(mhz, 1_000_000_000_000, units::TIMES, &format!("period({})", arg), settings)
+}
+
+/// Result `one / num` of `freq` and `period`, noted with its unit in `units`,
+/// where `one` is 1 in the units of the result times those of `num`. Results,
+/// in Hz or ns, are 1000 of the smallest unit of `units`, mHz or ps.
+fn invert(num: u64, one: u128, units: &[(&str, u64)], call: &str, settings: &Settings) -> Result<Value, ConvertError> {
+    if num == 0 {
+        return Err(ConvertError::Undefined(call.to_string()));
+    }
+    let num = num as u128;
+    let note = units::format_quantity(one * 1000, num, units);
+    Ok(Value { note: Some(note), ..Value::new(settings.rounding.divide(one, num) as u64) })
+}
+
+/// Value of a percentage of the full scale, e.g. `pct(75%)` is 0xc000 with
+/// the default full scale of 0x10000, rounded by `:round`.
+fn pct(arg: &str, _: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    let number = arg.trim().strip_suffix('%').unwrap_or(arg).trim_end();
+    let (num, den) = units::decimal(number).map_err(|kind| ConvertError::parse(arg, &Base::Dec, kind))?;
+    of_full_scale(num, den * 100, arg, settings)
+}
+
+/// Value of a real fraction of the full scale, e.g. `frac(0.75)` or
+/// `frac(3/4)`, rounded by `:round`.
+fn frac(arg: &str, _: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    let decimal = |number: &str| units::decimal(number.trim()).map_err(|kind| ConvertError::parse(arg, &Base::Dec, kind));
+    let (num, den) = match arg.split_once('/') {
+        Some((num, den)) => {
+            let ((num, num_den), (den, den_den)) = (decimal(num)?, decimal(den)?);
+            num.checked_mul(den_den).zip(den.checked_mul(num_den))
+                .ok_or_else(|| ConvertError::Overflow { input: arg.to_string() })?
+        }
+        None => decimal(arg)?,
+    };
+    if den == 0 {
+        return Err(ConvertError::Undefined(format!("frac({})", arg)));
+    }
+    of_full_scale(num, den, arg, settings)
+}
+
+/// Value of `num / den` times the full scale, noted as a fraction and a
+/// percentage, with the quantization error when it is rounded: the value
+/// minus the exact one, as a fraction of the full scale and in LSBs.
+fn of_full_scale(num: u128, den: u128, arg: &str, settings: &Settings) -> Result<Value, ConvertError> {
+    let overflow = || ConvertError::Overflow { input: arg.to_string() };
+    let scaled = num.checked_mul(settings.full_scale as u128).ok_or_else(overflow)?;
+    let value = u64::try_from(settings.rounding.divide(scaled, den)).map_err(|_| overflow())?;
+    let mut result = ratio(&[value], settings)?;
+    let error = value as u128 * den;
+    if error != scaled {
+        let lsb = if error > scaled { (error - scaled) as f64 } else { -((scaled - error) as f64) } / den as f64;
+        let lsb_text = format!("{:+.3}", lsb);
+        let lsb_text = lsb_text.trim_end_matches('0').trim_end_matches('.');
+        let note = result.note.take().unwrap_or_default();
+        result.note = Some(format!("{}, error {:+.3e} ({} LSB)", note, lsb / settings.full_scale as f64, lsb_text));
+    }
+    Ok(result)
+}
+
+/// Note a value as a fraction and a percentage of the full scale, e.g.
+/// `ratio(0xc000)` is `0.75 = 75%` with the default full scale of 0x10000.
+fn ratio(args: &[u64], settings: &Settings) -> Result<Value, ConvertError> {
+    let (num, full_scale) = (args[0] as u128, settings.full_scale as u128);
+    let note = format!("{} = {}%", units::format_decimal(num, full_scale), units::format_decimal(num * 100, full_scale));
+    Ok(Value { note: Some(note), ..Value::new(args[0]) })
+}
+
+/// Best fraction of a real number with a denominator of at most `maxden`,
+/// e.g. `ratio(0.3072, maxden=4096)` is 192, noted `192/625`. The result is
+/// the numerator, noted with the fraction and its error.
+fn approximate(arg: &str, max: &str) -> Result<Value, ConvertError> {
+    let call = format!("ratio({}, {})", arg, max);
+    let (num, den) = units::decimal(arg).map_err(|kind| ConvertError::parse(arg, &Base::Dec, kind))?;
+    let max_den = max.strip_prefix("maxden=").unwrap_or(max).trim();
+    let max_den = Base::Dec.to_num(max_den).ok().filter(|max_den| *max_den > 0)
+        .ok_or_else(|| ConvertError::Domain { call: call.clone(), expected: "maxden=<n> with n of at least 1" })?;
+    if num / den > u64::MAX as u128 {
+        return Err(ConvertError::Overflow { input: call });
+    }
+    let (p, q) = best_fraction(num, den, max_den as u128).ok_or_else(|| ConvertError::Overflow { input: call.clone() })?;
+    let p = u64::try_from(p).map_err(|_| ConvertError::Overflow { input: call })?;
+    let note = if Some(p as u128 * den) == num.checked_mul(q) {
+        format!("{}/{} exactly", p, q)
+    } else {
+        let error = (p as f64 * den as f64 - num as f64 * q as f64) / (den as f64 * q as f64);
+        format!("{}/{} = {}, error {:+.3e}", p, q, units::format_decimal(p as u128, q), error)
+    };
+    Ok(Value { note: Some(note), ..Value::new(p) })
+}
+
+/// Closest fraction to `num / den` with a denominator of at most `max_den`,
+/// from the convergents and semiconvergents of its continued fraction. None
+/// if the terms overflow, i.e. the numerator is far above 64 bits.
+fn best_fraction(mut num: u128, mut den: u128, max_den: u128) -> Option<(u128, u128)> {
+    let (x_num, x_den) = (num, den);
+    // `p + a * q` of the next convergent.
+    let next = |p: u128, a: u128, q: u128| a.checked_mul(q).and_then(|aq| aq.checked_add(p));
+    let (mut p0, mut q0, mut p1, mut q1) = (0, 1, 1, 0);
+    while den != 0 {
+        let a = num / den;
+        let q2 = match next(q0, a, q1) {
+            Some(q2) if q2 <= max_den => q2,
+            _ => break,
+        };
+        (p0, q0, p1, q1) = (p1, q1, next(p0, a, p1)?, q2);
+        (num, den) = (den, num - a * den);
+    }
+    if den == 0 {
+        return Some((p1, q1));
+    }
+    // The last convergent, or the largest semiconvergent below `max_den`.
+    let k = (max_den - q0) / q1;
+    let (p2, q2) = (next(p0, k, p1)?, q0 + k * q1);
+    let distance = |p: u128, q: u128| Some(p.checked_mul(x_den)?.abs_diff(x_num.checked_mul(q)?) as f64 / q as f64);
+    Some(if distance(p1, q1)? <= distance(p2, q2)? { (p1, q1) } else { (p2, q2) })
+}
+
+/// Number of ticks in a duration, e.g. `ticks(1h 2m 3.45s)`, at the tick
+/// rate, rounded by `:round`. Durations without a unit are in ns.
+fn ticks(arg: &str, parse: ArgParser, settings: &Settings) -> Result<Value, ConvertError> {
+    let ps = match units::parse_duration(arg) {
+        Ok(ps) => ps,
+        Err(_) => eval(arg, parse, settings)?.num as u128 * 1000,
+    };
+    let second = 1_000_000_000_000;
+    let ticks = ps.checked_mul(settings.tick_rate as u128)
+        .map(|ticks| settings.rounding.divide(ticks, second))
+        .and_then(|ticks| u64::try_from(ticks).ok())
+        .ok_or_else(|| ConvertError::Overflow { input: arg.to_string() })?;
+    duration(&[ticks], settings)
+}
+
+/// Note a count of ticks at the tick rate as a duration, e.g. `1h 2m 3.45s`.
+fn duration(args: &[u64], settings: &Settings) -> Result<Value, ConvertError> {
+    let note = units::format_duration(args[0] as u128 * 1_000_000_000_000, settings.tick_rate as u128);
+    Ok(Value { note: Some(note), ..Value::new(args[0]) })
+}
+
+/// Value with only bit `n` set, e.g. `onehot(5)` is 0b10_0000.
+fn onehot(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    if args[0] >= 64 {
+        let call = format!("onehot({})", args[0]);
+        return Err(ConvertError::Domain { call, expected: "a bit number below 64" });
+    }
+    Ok(Value::new(1 << args[0]))
+}
+
+/// Number of the bit set in a one-hot value, e.g. `decode_onehot(0x20)` is 5.
+fn decode_onehot(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    if !args[0].is_power_of_two() {
+        let call = format!("decode_onehot({:#x})", args[0]);
+        return Err(ConvertError::Domain { call, expected: "a value with exactly one bit set" });
+    }
+    Ok(Value::new(args[0].trailing_zeros() as u64))
+}
+
+/// Note the positions of the bits set in a value, e.g. `bits(0xb6)` is
+/// noted `1,2,4,5,7`.
+fn bits(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let positions: Vec<String> = (0..64).filter(|i| args[0] >> i & 1 == 1).map(|i: u32| i.to_string()).collect();
+    let note = if positions.is_empty() { "no bits set".to_string() } else { positions.join(",") };
+    Ok(Value { note: Some(note), ..Value::new(args[0]) })
+}
+
+/// Value with the bits at the given positions set, e.g. `frombits(1, 2, 4)`
+/// is 0b1_0110.
+fn frombits(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let mut num = 0;
+    for position in args {
+        if *position >= 64 {
+            let call = format!("frombits({})", args.iter().map(u64::to_string).collect::<Vec<_>>().join(", "));
+            return Err(ConvertError::Domain { call, expected: "bit numbers below 64" });
+        }
+        num |= 1 << position;
+    }
+    Ok(Value::new(num))
+}
+
+/// Greatest common divisor, e.g. `gcd(48000, 44100)` is 300.
+fn gcd(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    Ok(Value::new(prime::gcd(args[0], args[1])))
+}
+
+/// Least common multiple, e.g. `lcm(48000, 44100)` is 7056000. 0 if an
+/// argument is 0.
+fn lcm(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let (a, b) = (args[0], args[1]);
+    if a == 0 || b == 0 {
+        return Ok(Value::new(0));
+    }
+    let lcm = (a / prime::gcd(a, b)).checked_mul(b).ok_or_else(|| ConvertError::Overflow { input: format!("lcm({:#x}, {:#x})", a, b) })?;
+    Ok(Value::new(lcm))
+}
+
+/// Nearest multiple of `n`, e.g. `roundto(100, 6)` is 102, halves rounded up.
+fn roundto(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    multiple("roundto", args, Rounding::Nearest)
+}
+
+/// Multiple of `n` at or below a value, e.g. `floorto(100, 6)` is 96.
+fn floorto(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    multiple("floorto", args, Rounding::Floor)
+}
+
+/// Multiple of `n` at or above a value, e.g. `ceilto(100, 6)` is 102.
+fn ceilto(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    multiple("ceilto", args, Rounding::Ceil)
+}
+
+/// A value rounded to a multiple of `n` by `rounding`, noted with the count
+/// of multiples.
+fn multiple(name: &str, args: &[u64], rounding: Rounding) -> Result<Value, ConvertError> {
+    let (num, n) = (args[0], args[1]);
+    let call = format!("{}({:#x}, {})", name, num, n);
+    if n == 0 {
+        return Err(ConvertError::Domain { call, expected: "a multiple of at least 1" });
+    }
+    let count = rounding.divide(num as u128, n as u128);
+    let result = u64::try_from(count * n as u128).map_err(|_| ConvertError::Overflow { input: call })?;
+    Ok(Value { note: Some(format!("{} x {}", count, n)), ..Value::new(result) })
+}
+
+/// 1 if a value is prime, else 0, noted with its smallest prime factor,
+/// e.g. `isprime(91)` is 0, noted `divisible by 7`.
+fn isprime(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let n = args[0];
+    let is_prime = prime::is_prime(n);
+    let note = match prime::factor(n).first() {
+        _ if is_prime => format!("{} is prime", n),
+        Some((p, _)) => format!("{} is divisible by {}", n, p),
+        None => format!("{} is not prime", n),
+    };
+    Ok(Value { note: Some(note), ..Value::new(is_prime as u64) })
+}
+
+/// Note the prime factorization of a value, e.g. `factor(360)` is noted
+/// `2^3 * 3^2 * 5`.
+fn factor(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let factors = prime::factor(args[0]);
+    let note = if factors.is_empty() { format!("{} has no prime factors", args[0]) } else { prime::describe(&factors) };
+    Ok(Value { note: Some(note), ..Value::new(args[0]) })
+}
+
+/// Read a ones' complement value of `n` bits, e.g. `ones(0xfff0, 16)` is
+/// -15. The result is the same number in two's complement, noted in decimal.
+/// All ones is negative zero, 0.
+fn ones(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let (num, bits) = (args[0], args[1]);
+    if !(1..=64).contains(&bits) {
+        let call = format!("ones({:#x}, {})", num, bits);
+        return Err(ConvertError::Domain { call, expected: "a width of 1 to 64 bits" });
+    }
+    let bits = bits as u32;
+    converter::check_width(num, bits)?;
+    let mask = if bits == 64 { u64::MAX } else { (1 << bits) - 1 };
+    let negative = num >> (bits - 1) & 1 == 1;
+    let (result, note) = match negative {
+        true if num == mask => (0, "-0".to_string()),
+        true => (num.wrapping_add(1) & mask, format!("-{}", !num & mask)),
+        false => (num, num.to_string()),
+    };
+    Ok(Value { num: result, width: Some(bits), note: Some(note) })
+}
+
+/// Encode a signed value in excess-N, e.g. `bias(-3, 127)` is 124, the
+/// float exponent of 2^-3.
+fn bias(args: &[u64], _: &Settings) -> Result<Value, ConvertError> {
+    let (num, bias) = (args[0] as i64, args[1]);
+    let raw = u64::try_from(num as i128 + bias as i128).map_err(|_| {
+        let call = format!("bias({}, {})", num, bias);
+        ConvertError::Domain { call, expected: "a value whose biased form is not negative" }
+    })?;
+    Ok(excess(raw, num as i128))
+}
+
+/// Decode an excess-N value, e.g. `unbias(124, 127)` is -3, given in two's
+/// complement of the `:width` setting when negative.
+fn unbias(args: &[u64], settings: &Settings) -> Result<Value, ConvertError> {
+    let num = args[0] as i128 - args[1] as i128;
+    let mut value = excess(args[0], num);
+    value.num = match settings.width {
+        width @ 1..=63 if num < 0 => {
+            value.width = Some(width);
+            num as u64 & ((1 << width) - 1)
+        }
+        _ => num as u64,
+    };
+    Ok(value)
+}
+
+/// Value of `bias` and `unbias`, noted with its raw and unbiased forms.
+fn excess(raw: u64, unbiased: i128) -> Value {
+    Value { note: Some(format!("raw {}, unbiased {}", raw, unbiased)), ..Value::new(raw) }
+}
+
+#[cfg(test)]
+mod test_func {
+    use super::*;
+    use crate::testing::hex;
+    use crate::base::Base;
+    use crate::units::Rounding;
+
+    fn eval_hex(input: &str) -> Result<Value, ConvertError> {
+        eval(input, &hex, &Settings::default())
+    }
+
+    #[test]
+    fn test_byte_order() {
+        assert_eq!(eval_hex("htons(0x1234)").unwrap().num, 0x3412);
+        assert_eq!(eval_hex("ntohs(0x3412)").unwrap().num, 0x1234);
+        assert_eq!(eval_hex("htonl(0x12345678)").unwrap().num, 0x78563412);
+        assert_eq!(eval_hex("ntohl(htonl(0x12345678))").unwrap().num, 0x12345678);
+        assert_eq!(eval_hex("htonl(0x12)").unwrap().width, Some(32));
+
+        assert_eq!(eval_hex("htons(0x12345)").err(), Some(ConvertError::TooWide(16)));
+        assert!(eval_hex("htons(1, 2)").is_err());
+        assert_eq!(eval_hex("htonx(1)").err(), Some(ConvertError::UnknownFunction("htonx".to_string())));
+    }
+
+    #[test]
+    fn test_sizes() {
+        let size = eval_hex("size(1.5MiB)").unwrap();
+        assert_eq!((size.num, size.note), (0x180000, None));
+        let size = eval_hex("size(1MB)").unwrap();
+        assert_eq!(size.num, 1_000_000);
+        assert_eq!(size.note.as_deref(), Some("1 MB = 1000000 B, 1 MiB = 1048576 B"));
+        assert_eq!(eval_hex("size(htons(4))").unwrap().num, 0x400);
+        assert!(eval_hex("size(1, 2)").is_err());
+
+        assert_eq!(eval_hex("bytes(180000)").unwrap().note.as_deref(), Some("1.5 MiB"));
+        assert_eq!(eval_hex("bytes(1e8480)").unwrap().note.as_deref(), Some("1953.125 KiB = 2 MB"));
+        assert_eq!(eval_hex("bytes(size(256K))").unwrap().note.as_deref(), Some("256 KiB"));
+        assert_eq!(eval_hex("bytes(3)").unwrap().note.as_deref(), Some("3 B"));
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let freq = eval_hex("freq(10us)").unwrap();
+        assert_eq!((freq.num, freq.note.as_deref()), (100_000, Some("100 kHz")));
+        let freq = eval_hex("freq(3us)").unwrap();
+        assert_eq!((freq.num, freq.note.as_deref()), (333_333, Some("~333.333 kHz")));
+        let period = eval_hex("period(32.768kHz)").unwrap();
+        assert_eq!((period.num, period.note.as_deref()), (30_518, Some("~30.518 us")));
+        assert_eq!(eval_hex("period(1kHz)").unwrap().num, 1_000_000);
+        assert_eq!(eval_hex("freq(period(3e8))").unwrap().num, 0x3e8);
+        assert_eq!(eval_hex("htons(period(1MHz))").unwrap().num, 0x3e8_u16.swap_bytes() as u64);
+        assert_eq!(eval_hex("freq(0)").err(), Some(ConvertError::Undefined("freq(0)".to_string())));
+    }
+
+    #[test]
+    fn test_full_scale() {
+        assert_eq!(eval_hex("pct(75%)").unwrap().num, 0xc000);
+        assert_eq!(eval_hex("pct(12.5)").unwrap().num, 0x2000);
+        assert_eq!(eval_hex("frac(0.75)").unwrap().num, 0xc000);
+        assert_eq!(eval_hex("frac(1/3)").unwrap().num, 0x5555);
+        assert_eq!(eval_hex("ratio(c000)").unwrap().note.as_deref(), Some("0.75 = 75%"));
+        assert_eq!(eval_hex("ratio(5555)").unwrap().note.as_deref(), Some("~0.333 = ~33.333%"));
+
+        let approximate = |input: &str| eval_hex(input).map(|value| (value.num, value.note.unwrap()));
+        assert_eq!(approximate("ratio(0.3072, maxden=4096)"), Ok((192, "192/625 exactly".to_string())));
+        assert_eq!(approximate("ratio(3.14159265358979, maxden=1000)"), Ok((355, "355/113 = ~3.142, error +2.668e-7".to_string())));
+        assert_eq!(approximate("ratio(1.3333, 3)"), Ok((4, "4/3 = ~1.333, error +3.333e-5".to_string())));
+        assert_eq!(approximate("ratio(0.0001, maxden=100)"), Ok((0, "0/1 = 0, error -1.000e-4".to_string())));
+        assert_eq!(approximate("ratio(2, maxden=3)"), Ok((2, "2/1 exactly".to_string())));
+        assert!(eval_hex("ratio(0.5, maxden=0)").is_err());
+        assert!(eval_hex("ratio(18446744073709551616.5, maxden=18446744073709551615)").is_err());
+        assert!(eval_hex("ratio(18446744073709551615.5, maxden=18446744073709551615)").is_err());
+        let large = "ratio(18446744073709551614.999999999999999999, maxden=100)";
+        assert_eq!(eval_hex(large).err(), Some(ConvertError::Overflow { input: large.to_string() }));
+        assert_eq!(approximate("ratio(1844674407370955161.5, maxden=2)"), Ok((3689348814741910323, "3689348814741910323/2 exactly".to_string())));
+        assert!(eval_hex("ratio(x, maxden=10)").is_err());
+        assert_eq!(eval_hex("frac(1/0)").err(), Some(ConvertError::Undefined("frac(1/0)".to_string())));
+        let large = "99999999999999999999999999999999999999/1.5";
+        assert_eq!(eval_hex(&format!("frac({})", large)).err(), Some(ConvertError::Overflow { input: large.to_string() }));
+        assert!(eval_hex("pct(1e)").is_err());
+
+        let q15 = Settings { full_scale: 0x8000, ..Settings::default() };
+        let value = eval("pct(75%)", &hex, &q15).unwrap();
+        assert_eq!((value.num, value.note.as_deref()), (0x6000, Some("0.75 = 75%")));
+        assert_eq!(eval("pct(200%)", &hex, &q15).unwrap().num, 0x10000);
+    }
+
+    #[test]
+    fn test_rounding() {
+        let q15 = Settings { full_scale: 0x8000, ..Settings::default() };
+        let value = eval("frac(0.1)", &hex, &q15).unwrap();
+        assert_eq!((value.num, value.note.as_deref()), (0xccd, Some("~0.1 = ~10.001%, error +6.104e-6 (+0.2 LSB)")));
+        let floor = Settings { rounding: Rounding::Floor, ..q15.clone() };
+        let value = eval("frac(0.1)", &hex, &floor).unwrap();
+        assert_eq!((value.num, value.note.as_deref()), (0xccc, Some("~0.1 = ~9.998%, error -2.441e-5 (-0.8 LSB)")));
+        let ceil = Settings { rounding: Rounding::Ceil, ..Settings::default() };
+        assert_eq!(eval("freq(3us)", &hex, &ceil).unwrap().num, 333_334);
+        assert_eq!(eval("ticks(1.5ms)", &hex, &Settings { tick_rate: 32768, ..floor }).unwrap().num, 49);
+    }
+
+    #[test]
+    fn test_ticks() {
+        let ticks = eval_hex("ticks(1h 2m 3.45s)").unwrap();
+        assert_eq!((ticks.num, ticks.note.as_deref()), (3_723_450, Some("1h 2m 3.45s")));
+        assert_eq!(eval_hex("duration(3e8)").unwrap().note.as_deref(), Some("1s"));
+        assert_eq!(eval_hex("ticks(period(1Hz))").unwrap().num, 1000);
+
+        let rtc = Settings { tick_rate: 32768, ..Settings::default() };
+        let ticks = eval("ticks(1.5ms)", &hex, &rtc).unwrap();
+        assert_eq!((ticks.num, ticks.note.as_deref()), (49, Some("~1.495ms")));
+        assert_eq!(eval("duration(8000)", &hex, &rtc).unwrap().note.as_deref(), Some("1s"));
+        assert!(eval_hex("ticks(5000000h)").is_ok());
+        assert!(eval_hex("ticks(9999999999999h)").is_err());
+    }
+
+    #[test]
+    fn test_onehot() {
+        assert_eq!(eval_hex("onehot(5)").unwrap().num, 0b10_0000);
+        assert_eq!(eval_hex("decode_onehot(20)").unwrap().num, 5);
+        assert_eq!(eval_hex("decode_onehot(onehot(63))").unwrap().num, 63);
+        assert_eq!(eval_hex("onehot(decode_onehot(8000))").unwrap().num, 0x8000);
+        let error = eval_hex("decode_onehot(21)").err().unwrap();
+        assert_eq!(error.to_string(), "invalid decode_onehot(0x21), expect a value with exactly one bit set");
+        assert!(eval_hex("decode_onehot(0)").is_err());
+        assert!(eval_hex("onehot(64)").is_err());
+    }
+
+    #[test]
+    fn test_bits() {
+        let value = eval_hex("bits(b6)").unwrap();
+        assert_eq!((value.num, value.note.as_deref()), (0xb6, Some("1,2,4,5,7")));
+        assert_eq!(eval_hex("bits(0)").unwrap().note.as_deref(), Some("no bits set"));
+        assert_eq!(eval_hex("bits(onehot(63))").unwrap().note.as_deref(), Some("63"));
+        assert_eq!(eval_hex("frombits(1, 2, 4)").unwrap().num, 0b1_0110);
+        assert_eq!(eval_hex("frombits(0x3f, 10)").unwrap().num, 1 << 63 | 1 << 10);
+        assert_eq!(eval_hex("frombits(1, 1)").unwrap().num, 2);
+        assert_eq!(eval_hex("frombits(64)").err().unwrap().to_string(), "invalid frombits(64), expect bit numbers below 64");
+        assert_eq!(eval_hex("frombits()").err(), Some(ConvertError::ArgCount { name: "frombits".to_string(), expected: 1, got: 0 }));
+        assert!(eval_hex("bits(1, 2)").is_err());
+    }
+
+    #[test]
+    fn test_gcd() {
+        let dec = |input: &str| eval(input, &|lit| Base::Dec.to_num(lit), &Settings::default()).map(|value| value.num);
+        assert_eq!(dec("gcd(48000, 44100)"), Ok(300));
+        assert_eq!(dec("lcm(48000, 44100)"), Ok(7_056_000));
+        assert_eq!(dec("gcd(0, 12)"), Ok(12));
+        assert_eq!(dec("gcd(0, 0)"), Ok(0));
+        assert_eq!(dec("lcm(0, 12)"), Ok(0));
+        assert_eq!(dec("lcm(gcd(12, 18), 4)"), Ok(12));
+        assert_eq!(eval_hex("lcm(100000001, 100000003)").err().unwrap().to_string(), "lcm(0x100000001, 0x100000003) does not fit in 64 bits");
+        assert!(eval_hex("gcd(1)").is_err());
+    }
+
+    #[test]
+    fn test_multiples() {
+        let dec = |input: &str| eval(input, &|lit| Base::Dec.to_num(lit), &Settings::default()).map(|value| value.num);
+        assert_eq!(dec("roundto(100, 6)"), Ok(102));
+        assert_eq!(dec("roundto(99, 6)"), Ok(102));
+        assert_eq!(dec("roundto(98, 6)"), Ok(96));
+        assert_eq!(dec("floorto(100, 6)"), Ok(96));
+        assert_eq!(dec("ceilto(100, 6)"), Ok(102));
+        assert_eq!(dec("ceilto(96, 6)"), Ok(96));
+        assert_eq!(eval_hex("ceilto(1001, 0x1000)").unwrap().num, 0x2000);
+        assert_eq!(eval_hex("floorto(1fff, 1000)").unwrap().note.as_deref(), Some("8 x 1000"));
+        assert_eq!(eval_hex("floorto(1fff, 0x1000)").unwrap().num, 0x1000);
+        assert_eq!(eval_hex("ceilto(ffffffffffffffff, 2)").err().unwrap().to_string(), "ceilto(0xffffffffffffffff, 2) does not fit in 64 bits");
+        assert!(eval_hex("roundto(5, 0)").is_err());
+    }
+
+    #[test]
+    fn test_primes() {
+        let dec = |input: &str| eval(input, &|lit| Base::Dec.to_num(lit), &Settings::default()).map(|value| (value.num, value.note.unwrap()));
+        assert_eq!(dec("isprime(65537)"), Ok((1, "65537 is prime".to_string())));
+        assert_eq!(dec("isprime(91)"), Ok((0, "91 is divisible by 7".to_string())));
+        assert_eq!(dec("isprime(1)"), Ok((0, "1 is not prime".to_string())));
+        assert_eq!(dec("factor(360)"), Ok((360, "2^3 * 3^2 * 5".to_string())));
+        assert_eq!(dec("factor(0)"), Ok((0, "0 has no prime factors".to_string())));
+        assert_eq!(eval_hex("factor(ffffffffffffffff)").unwrap().note.as_deref(), Some("3 * 5 * 17 * 257 * 641 * 65537 * 6700417"));
+    }
+
+    #[test]
+    fn test_ones() {
+        let value = eval_hex("ones(fff0, 16)").unwrap();
+        assert_eq!((value.num, value.width, value.note.as_deref()), (0xfff1, Some(16), Some("-15")));
+        assert_eq!(eval_hex("ones(f, 16)").unwrap().note.as_deref(), Some("15"));
+        assert_eq!(eval_hex("ones(ffff, 0x10)").unwrap().note.as_deref(), Some("-0"));
+        assert_eq!(eval_hex("ones(fffffffffffffffe, 64)").unwrap().num, u64::MAX);
+        assert_eq!(eval_hex("ones(1ffff, 16)").err(), Some(ConvertError::TooWide(16)));
+        assert!(eval_hex("ones(1, 0)").is_err());
+    }
+
+    #[test]
+    fn test_bias() {
+        let value = eval_hex("bias(-3, 127)").unwrap();
+        assert_eq!((value.num, value.note.as_deref()), (124, Some("raw 124, unbiased -3")));
+        assert_eq!(eval_hex("bias(a, 127)").unwrap().num, 137);
+        let value = eval_hex("unbias(7c, 127)").unwrap();
+        assert_eq!((value.num, value.note.as_deref()), (-3i64 as u64, Some("raw 124, unbiased -3")));
+        assert_eq!(eval_hex("unbias(bias(-80, 1023), 1023)").unwrap().num, -0x80i64 as u64);
+        assert!(eval_hex("bias(-200, 127)").is_err());
+        assert!(eval_hex("unbias(-1, 127)").is_err());
+        let byte = Settings { width: 8, ..Settings::default() };
+        let value = eval("unbias(7c, 127)", &hex, &byte).unwrap();
+        assert_eq!((value.num, value.width), (0xfd, Some(8)));
+        assert_eq!(eval("unbias(80, 127)", &hex, &byte).unwrap().num, 1);
+    }
+
+    #[test]
+    fn test_is_call() {
+        assert!(is_call("htons(0x12)"));
+        assert!(is_call(" htonl( htons(1) ) "));
+        assert!(!is_call("0x12"));
+        assert!(!is_call("htons(1"));
+        assert!(!is_call("(1)"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Input history: its entries, `!!`/`!n` expansion, and the location of the
+//! persistent REPL history.
+
+use std::env;
+use std::path::PathBuf;
+use crate::conversion::Conversion;
+use crate::error::CommandError;
+
+/// Default maximum number of history entries.
+pub const DEFAULT_SIZE: usize = 1000;
+
+/// Path of the history file, `$XDG_DATA_HOME/base-converter/history` or
+/// `~/.local/share/base-converter/history`. `None` if no home directory is set.
+pub fn path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+    };
+    Some(dir.join("base-converter").join("history"))
+}
+
+/// An input of the history, with its conversion if it converted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub input: String,
+    pub conversion: Option<Conversion>,
+}
+
+/// Expand an input of `!!`, the last entry, or `!n`, entry `n` as numbered by
+/// `:history`. `None` if the input is not an expansion.
+pub fn expand(input: &str, entries: &[Entry]) -> Result<Option<String>, CommandError> {
+    let index = match input.strip_prefix('!') {
+        Some("!") => entries.len().checked_sub(1),
+        Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+            n.parse::<usize>().ok().and_then(|n| n.checked_sub(1))
+        }
+        _ => return Ok(None),
+    };
+    index.and_then(|i| entries.get(i))
+        .map(|entry| Some(entry.input.clone()))
+        .ok_or_else(|| CommandError::NoHistory(input.to_string()))
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let entries: Vec<Entry> = ["ff", ":to dec"].iter()
+            .map(|input| Entry { input: input.to_string(), conversion: None })
+            .collect();
+        assert_eq!(expand("!!", &entries), Ok(Some(":to dec".to_string())));
+        assert_eq!(expand("!1", &entries), Ok(Some("ff".to_string())));
+        assert_eq!(expand("!3", &entries), Err(CommandError::NoHistory("!3".to_string())));
+        assert_eq!(expand("!0", &entries), Err(CommandError::NoHistory("!0".to_string())));
+        assert_eq!(expand("!!", &[]), Err(CommandError::NoHistory("!!".to_string())));
+        assert_eq!(expand("ff", &entries), Ok(None));
+        assert_eq!(expand("!x", &entries), Ok(None));
+    }
+}
@@ -0,0 +1,72 @@
+//! A small program to change between hex, dec, bin with nice format for binary display.
+//! Mainly aim for embedded developer.
+//!
+//! The library exposes the conversion used by the program: `Base` reads and
+//! formats numbers, `Settings` holds the options (width, nibble grouping, ...)
+//! and `Converter` converts input values, including function calls like
+//! `htonl(0x12345678)`.
+
+#[cfg(feature = "cli")]
+pub mod app;
+pub mod base;
+#[cfg(feature = "cli")]
+pub mod batch;
+pub mod bits;
+pub mod ble;
+pub mod bytes;
+pub mod can;
+pub mod cancel;
+pub mod cli;
+#[cfg(feature = "cli")]
+pub mod clipboard;
+pub mod command;
+#[cfg(feature = "cli")]
+pub mod completion;
+pub mod config;
+pub mod conversion;
+pub mod converter;
+pub mod crc;
+pub mod date;
+pub mod divider;
+pub mod dump;
+pub mod enums;
+pub mod error;
+pub mod escape;
+pub mod exit;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod field;
+pub mod float;
+pub mod func;
+pub mod history;
+pub mod list;
+#[cfg(feature = "cli")]
+mod pager;
+pub mod plugin;
+pub mod posit;
+pub mod prime;
+pub mod progress;
+pub mod random;
+pub mod render;
+pub mod rpc;
+pub mod seq;
+pub mod settings;
+pub mod theme;
+#[cfg(test)]
+mod testing;
+pub mod token;
+pub mod units;
+#[cfg(feature = "cli")]
+pub mod transcript;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod usb;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use base::{Base, Style};
+pub use conversion::{Conversion, OutputFormat};
+pub use converter::Converter;
+pub use error::{CommandError, ConvertError};
+pub use settings::Settings;
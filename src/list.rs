@@ -0,0 +1,275 @@
+//! Commands on lists of values, e.g. a table of addresses. The values are
+//! given after the command, separated by spaces or commas, or without them
+//! read from the clipboard like `:paste`.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use crate::error::{CommandError, ConvertError};
+use crate::func::ArgParser;
+use crate::units;
+
+/// Names of the list commands.
+pub const NAMES: &[&str] = &["alignreport", "dups", "sort", "agg", "bitfreq"];
+
+/// Operation of a list command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListOp {
+    /// Flag the values that are not multiples of an alignment.
+    Align(u64),
+    /// Find the values given more than once, or equal once masked by the
+    /// mask, read like the values.
+    Dups { mask: Option<String> },
+    /// Sort the values in increasing order, or decreasing with `reverse`,
+    /// keeping one of equal values with `unique`.
+    Sort { unique: bool, reverse: bool },
+    /// Aggregate the values: min, max, sum, mean and range.
+    Agg,
+    /// Count how often each bit is set across the values, e.g. samples of a
+    /// register.
+    BitFreq,
+}
+
+/// Parse a list command, given as its name and arguments, into its
+/// operation and values. Options start with `--`, e.g. `--mask ff`.
+pub fn parse(name: &str, args: &[&str]) -> Result<(ListOp, Vec<String>), CommandError> {
+    let mut args = args.iter();
+    let mut op = match name {
+        "alignreport" => {
+            let align = args.next().ok_or(CommandError::Syntax)?;
+            let align = count(align).filter(|align| *align > 0)
+                .ok_or_else(|| CommandError::invalid("alignment", align, "a positive number"))?;
+            ListOp::Align(align)
+        }
+        "dups" => ListOp::Dups { mask: None },
+        "sort" => ListOp::Sort { unique: false, reverse: false },
+        "agg" => ListOp::Agg,
+        "bitfreq" => ListOp::BitFreq,
+        _ => return Err(CommandError::Syntax),
+    };
+    let mut values = Vec::new();
+    while let Some(arg) = args.next() {
+        match (&mut op, *arg) {
+            (ListOp::Dups { mask }, "--mask") => *mask = Some(args.next().ok_or(CommandError::Syntax)?.to_string()),
+            (ListOp::Sort { unique, .. }, "--unique") => *unique = true,
+            (ListOp::Sort { reverse, .. }, "--reverse") => *reverse = true,
+            (_, option) if option.starts_with("--") => return Err(CommandError::Syntax),
+            (_, arg) => values.extend(arg.split(',').filter(|value| !value.is_empty()).map(|value| value.to_string())),
+        }
+    }
+    Ok((op, values))
+}
+
+/// Read a count in decimal, or in hex with a `0x` prefix.
+fn count(arg: &str) -> Option<u64> {
+    match arg.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => arg.parse().ok(),
+    }
+}
+
+/// Report of an operation on values, written with `format`. Values of the
+/// options are read with `read`.
+pub fn report(op: &ListOp, values: &[u64], read: ArgParser, format: &dyn Fn(u64) -> String) -> Result<String, ConvertError> {
+    Ok(match op {
+        ListOp::Align(align) => align_report(values, *align, format),
+        ListOp::Dups { mask } => {
+            let mask = mask.as_deref().map(read).transpose()?;
+            dups_report(values, mask, format)
+        }
+        ListOp::Sort { unique, reverse } => {
+            let mut values = values.to_vec();
+            values.sort_unstable();
+            if *unique {
+                values.dedup();
+            }
+            if *reverse {
+                values.reverse();
+            }
+            values.into_iter().map(format).collect::<Vec<String>>().join("\n")
+        }
+        ListOp::Agg => aggregate(values, format),
+        ListOp::BitFreq => bit_frequencies(values),
+    })
+}
+
+/// One line per value, `ok` or how far it is from the alignment, and the
+/// count of misaligned values.
+fn align_report(values: &[u64], align: u64, format: &dyn Fn(u64) -> String) -> String {
+    let mut out = String::new();
+    let mut misaligned = 0;
+    for value in values {
+        let offset = value % align;
+        if offset == 0 {
+            out += &format!("{}  ok\n", format(*value));
+            continue;
+        }
+        misaligned += 1;
+        out += &format!("{}  misaligned by {}, down {}", format(*value), offset, format(value - offset));
+        if let Some(up) = value.checked_add(align - offset) {
+            out += &format!(", up {}", format(up));
+        }
+        out += "\n";
+    }
+    out + &format!("{} of {} misaligned for alignment {}", misaligned, values.len(), align)
+}
+
+/// One line per value found more than once, with its positions from 1, and
+/// the count of such values. With a mask, values are compared once masked
+/// and the different values behind a collision are listed.
+fn dups_report(values: &[u64], mask: Option<u64>, format: &dyn Fn(u64) -> String) -> String {
+    let mut positions: BTreeMap<u64, Vec<(usize, u64)>> = BTreeMap::new();
+    for (i, value) in values.iter().enumerate() {
+        positions.entry(value & mask.unwrap_or(u64::MAX)).or_default().push((i + 1, *value));
+    }
+    let mut out = String::new();
+    let mut dups = 0;
+    for (key, entries) in positions.iter().filter(|(_, entries)| entries.len() > 1) {
+        dups += 1;
+        let at: Vec<String> = entries.iter().map(|(i, _)| i.to_string()).collect();
+        out += &format!("{}  {} times, at {}", format(*key), entries.len(), at.join(", "));
+        let mut originals: Vec<u64> = entries.iter().map(|(_, value)| *value).collect();
+        originals.sort_unstable();
+        originals.dedup();
+        if originals.len() > 1 {
+            let originals: Vec<String> = originals.into_iter().map(format).collect();
+            out += &format!(", from {}", originals.join(", "));
+        }
+        out += "\n";
+    }
+    let after = mask.map(|mask| format!(" after mask {}", format(mask))).unwrap_or_default();
+    match dups {
+        0 => out + &format!("no duplicates in {} values{}", values.len(), after),
+        1 => out + &format!("1 duplicated value in {} values{}", values.len(), after),
+        _ => out + &format!("{} duplicated values in {} values{}", dups, values.len(), after),
+    }
+}
+
+/// Count, min, max, sum, mean and range of values. The sum is written in
+/// decimal when it does not fit in 64 bits, and an inexact mean is written
+/// truncated with its decimal value.
+fn aggregate(values: &[u64], format: &dyn Fn(u64) -> String) -> String {
+    let (min, max) = match (values.iter().min(), values.iter().max()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return "no values".to_string(),
+    };
+    let count = values.len() as u128;
+    let sum: u128 = values.iter().map(|value| *value as u128).sum();
+    let sum_text = match u64::try_from(sum) {
+        Ok(sum) => format(sum),
+        Err(_) => format!("{} (over 64 bits)", sum),
+    };
+    // The mean is at most the max, so it fits.
+    let mut mean = format((sum / count) as u64);
+    if !sum.is_multiple_of(count) {
+        mean += &format!(" ({} in decimal)", units::format_decimal(sum, count));
+    }
+    format!("count  {}\nmin    {}\nmax    {}\nsum    {}\nmean   {}\nrange  {}",
+        count, format(min), format(max), sum_text, mean, format(max - min))
+}
+
+/// One line per bit, from the highest set in any value, with how often it
+/// is set and whether it is stuck, and the count of toggling bits.
+fn bit_frequencies(values: &[u64]) -> String {
+    if values.is_empty() {
+        return "no values".to_string();
+    }
+    let any = values.iter().fold(0, |any, value| any | value);
+    let bits = (64 - any.leading_zeros()).max(1);
+    let mut out = "bit  set\n".to_string();
+    let (mut ones, mut zeros) = (0, 0);
+    for i in (0..bits).rev() {
+        let set = values.iter().filter(|value| *value >> i & 1 == 1).count();
+        let state = if set == values.len() {
+            ones += 1;
+            "  stuck at 1"
+        } else if set == 0 {
+            zeros += 1;
+            "  stuck at 0"
+        } else {
+            ""
+        };
+        out += &format!("{:<3}  {}/{} {:>3}%{}\n", i, set, values.len(), set * 100 / values.len(), state);
+    }
+    out + &format!("{} toggling, {} stuck at 1, {} stuck at 0 of {} bits in {} samples",
+        bits - ones - zeros, ones, zeros, bits, values.len())
+}
+
+#[cfg(test)]
+mod test_list {
+    use super::*;
+    use crate::base::Base;
+
+    fn hex(value: u64) -> String {
+        format!("{:#x}", value)
+    }
+
+    fn run(op: ListOp, values: &[u64]) -> String {
+        report(&op, values, &|arg| Base::Hex.to_num(arg), &hex).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let (op, values) = parse("alignreport", &["4", "0x1000,0x1002,", "1004"]).unwrap();
+        assert_eq!(op, ListOp::Align(4));
+        assert_eq!(values, vec!["0x1000", "0x1002", "1004"]);
+        assert_eq!(parse("alignreport", &["0x10"]).unwrap(), (ListOp::Align(16), Vec::new()));
+        assert!(parse("alignreport", &["0"]).is_err());
+        assert!(parse("alignreport", &[]).is_err());
+        let mask = Some("7ff".to_string());
+        assert_eq!(parse("dups", &["1", "--mask", "7ff", "2"]).unwrap(), (ListOp::Dups { mask }, vec!["1".to_string(), "2".to_string()]));
+        assert!(parse("dups", &["--mask"]).is_err());
+        assert!(parse("dups", &["--unique"]).is_err());
+        let sort = ListOp::Sort { unique: true, reverse: true };
+        assert_eq!(parse("sort", &["--reverse", "3,1", "--unique"]).unwrap(), (sort, vec!["3".to_string(), "1".to_string()]));
+    }
+
+    #[test]
+    fn test_align_report() {
+        let out = run(ListOp::Align(4), &[0x1000, 0x1002, u64::MAX]);
+        assert_eq!(out, "0x1000  ok\n\
+            0x1002  misaligned by 2, down 0x1000, up 0x1004\n\
+            0xffffffffffffffff  misaligned by 3, down 0xfffffffffffffffc\n\
+            2 of 3 misaligned for alignment 4");
+    }
+
+    #[test]
+    fn test_dups_report() {
+        let out = run(ListOp::Dups { mask: None }, &[0x123, 0x7df, 0x123, 0x7e8, 0x123]);
+        assert_eq!(out, "0x123  3 times, at 1, 3, 5\n1 duplicated value in 5 values");
+        assert_eq!(run(ListOp::Dups { mask: None }, &[1, 2]), "no duplicates in 2 values");
+        let out = run(ListOp::Dups { mask: Some("ff".to_string()) }, &[0x123, 0x223, 0x7df, 0x7df, 0x8df]);
+        assert_eq!(out, "0x23  2 times, at 1, 2, from 0x123, 0x223\n\
+            0xdf  3 times, at 3, 4, 5, from 0x7df, 0x8df\n\
+            2 duplicated values in 5 values after mask 0xff");
+        assert!(report(&ListOp::Dups { mask: Some("zz".to_string()) }, &[], &|arg| Base::Hex.to_num(arg), &hex).is_err());
+    }
+
+    #[test]
+    fn test_sort() {
+        let values = [0x30, 0x4, 0x30, 0x1000];
+        assert_eq!(run(ListOp::Sort { unique: false, reverse: false }, &values), "0x4\n0x30\n0x30\n0x1000");
+        assert_eq!(run(ListOp::Sort { unique: true, reverse: false }, &values), "0x4\n0x30\n0x1000");
+        assert_eq!(run(ListOp::Sort { unique: true, reverse: true }, &values), "0x1000\n0x30\n0x4");
+    }
+
+    #[test]
+    fn test_aggregate() {
+        assert_eq!(run(ListOp::Agg, &[0x30, 0x4, 0x1000]), "count  3\nmin    0x4\nmax    0x1000\nsum    0x1034\n\
+            mean   0x566 (~1382.667 in decimal)\nrange  0xffc");
+        assert_eq!(run(ListOp::Agg, &[2, 4]), "count  2\nmin    0x2\nmax    0x4\nsum    0x6\nmean   0x3\nrange  0x2");
+        let out = run(ListOp::Agg, &[u64::MAX, 1]);
+        assert!(out.contains("sum    18446744073709551616 (over 64 bits)\nmean   0x8000000000000000\n"));
+        assert_eq!(run(ListOp::Agg, &[]), "no values");
+    }
+
+    #[test]
+    fn test_bit_frequencies() {
+        assert_eq!(run(ListOp::BitFreq, &[0x9, 0xb, 0x8, 0x9]), "bit  set\n\
+            3    4/4 100%  stuck at 1\n\
+            2    0/4   0%  stuck at 0\n\
+            1    1/4  25%\n\
+            0    3/4  75%\n\
+            2 toggling, 1 stuck at 1, 1 stuck at 0 of 4 bits in 4 samples");
+        assert!(run(ListOp::BitFreq, &[0, 0]).ends_with("0 toggling, 0 stuck at 1, 1 stuck at 0 of 1 bits in 2 samples"));
+    }
+}
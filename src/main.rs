@@ -1,37 +1,133 @@
-//! A small program to change between hex, dec, bin with nice format for binary display.
-//! Mainly aim for embedded developer.
+//! Command line program of the base converter, see the library for the
+//! conversion itself.
 
-mod app;
-use app::{App, START_CMD};
+use std::env;
+use std::io::{stdin, stdout, IsTerminal};
+use std::process;
+use base_converter::app::App;
+use base_converter::command::Command;
+use base_converter::{batch, cancel, cli, config, exit, rpc, CommandError};
 
 /// Main funtion of the program
 fn main() {
+    let args = match cli::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::usage());
+            process::exit(exit::USAGE);
+        }
+    };
+    if args.help {
+        print!("{}", cli::usage());
+        return;
+    }
+    if let Some(shell) = &args.completions {
+        print!("{}", cli::completions(shell));
+        return;
+    }
     let mut app = App::new();
-    loop {
-        let input = app.get_input();
-
-        if input.trim() == format!("{}q", START_CMD) 
-        || input.trim() == format!("{}quit", START_CMD) {
-            break;
+    if let Err(e) = app.load_config() {
+        app.print_error(&format!("Error: {}", e));
+    }
+    if !args.no_rc && args.interactive(stdin().is_terminal()) {
+        if let Err(e) = app.load_rc() {
+            app.print_error(&format!("Error: {}", e));
         }
-
-        if input.is_empty() {
-            continue;
+    }
+    for (key, value) in config::env_settings(env::vars()) {
+        if let Err(e) = app.set(&key, &value) {
+            app.print_error(&format!("Error: {} (from {}{})", e, config::ENV_PREFIX, key.to_uppercase()));
+            process::exit(exit::USAGE);
         }
-
-        if app.is_command(&input) {
-            if let Err(e) = app.execute(&input) {
-                println!("{}", e);
-            };
+    }
+    for (key, value) in &args.settings {
+        if let Err(e) = app.set(key, value) {
+            app.print_error(&format!("Error: {}", e));
+            process::exit(exit::USAGE);
+        }
+    }
+    let code = if args.serve {
+        match rpc::Server::new(app.settings().clone()).serve(stdin().lock(), &mut stdout()) {
+            Ok(()) => 0,
+            Err(e) => {
+                app.print_error(&format!("Error: {}", e));
+                exit::IO
+            }
+        }
+    } else if args.tui {
+        app.load_history();
+        let code = match app.run(Command::Tui) {
+            Ok(()) => 0,
+            Err(e) => {
+                app.print_error(&format!("Error: {}", e));
+                exit::USAGE
+            }
+        };
+        if let Err(e) = app.save_history() {
+            app.print_error(&format!("Error: {}", e));
+        }
+        code
+    } else if let Some(path) = &args.file {
+        // `-` streams stdin, e.g. a log piped through the converter.
+        let summary = if path == "-" {
+            batch::run(&app, stdin().lock(), &mut stdout().lock(), false).map_err(|e| CommandError::Io(e.to_string()))
         } else {
-            match app.convert(&input) {
-                Ok(output) => {
-                    app.print(&output);
-                },
-                Err(e) => {
-                    println!("Error: {}", e);
+            // Progress only shows when results go elsewhere than the terminal.
+            let progress = !stdout().is_terminal();
+            batch::open(path)
+                .and_then(|input| input.run(&app, &mut stdout().lock(), false, progress).map_err(|e| CommandError::Io(e.to_string())))
+        };
+        match summary {
+            Ok(summary) => summary.code,
+            Err(e) => {
+                app.print_error(&format!("Error: {}", e));
+                exit::IO
+            }
+        }
+    } else if !args.values.is_empty() {
+        convert_all(&app, &args.values)
+    } else if stdin().is_terminal() {
+        repl(&mut app);
+        0
+    } else {
+        // Lines piped to stdin are run without prompt.
+        app.session(stdin().lock())
+    };
+    process::exit(code);
+}
+
+/// Convert the values given on the command line. Return the exit code of the
+/// first failure, 0 if none.
+fn convert_all(app: &App, values: &[String]) -> i32 {
+    let mut code = 0;
+    app.print_header();
+    for value in values {
+        match app.convert(value) {
+            Ok(output) => app.print(&output),
+            Err(e) => {
+                app.print_error(&format!("Error: {}", e));
+                if code == 0 {
+                    code = exit::code(&e);
                 }
             }
         }
     }
-}
\ No newline at end of file
+    code
+}
+
+/// Run the interactive prompt until `:q`.
+fn repl(app: &mut App) {
+    app.load_history();
+    // Ctrl+C while a command runs cancels it, at the prompt it clears the line.
+    if let Err(e) = cancel::install() {
+        app.print_error(&format!("Error: {}", e));
+    }
+    while !app.is_done() {
+        let input = app.get_input();
+        cancel::reset();
+        app.handle_paste(&input);
+    }
+    if let Err(e) = app.save_history() {
+        app.print_error(&format!("Error: {}", e));
+    }
+}
@@ -0,0 +1,98 @@
+//! Paging of output taller than the terminal, through `$PAGER` or a small
+//! built-in pager.
+
+use std::env;
+use std::io::{self, stdout, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal;
+
+const MORE: &str = "--More-- (space: next page, enter: next line, q: quit)";
+
+/// Write a text to `out`, paging it if `enabled` and it does not fit in the
+/// terminal.
+pub fn print(text: &str, enabled: bool, out: &mut dyn Write) -> io::Result<()> {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    let height = match terminal::size() {
+        Ok((_, rows)) if enabled && stdout().is_terminal() => rows as usize,
+        _ => 0,
+    };
+    if height < 2 || text.lines().count() < height {
+        return writeln!(out, "{}", text);
+    }
+    if let Some(pager) = env::var("PAGER").ok().filter(|p| !p.trim().is_empty()) {
+        if external(&pager, text) {
+            return Ok(());
+        }
+    }
+    builtin(text, height, out)
+}
+
+/// Pipe a text to an external pager. Return false if it could not be run
+/// or failed, e.g. `$PAGER` names a missing program, so that the text is
+/// paged by the built-in pager instead.
+fn external(pager: &str, text: &str) -> bool {
+    let child = Command::new("sh").arg("-c").arg(pager).stdin(Stdio::piped()).spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may quit before reading everything.
+        let _ = writeln!(stdin, "{}", text);
+    }
+    child.wait().is_ok_and(|status| status.success())
+}
+
+/// Write a text one screen at a time, waiting for a key between screens.
+fn builtin(text: &str, height: usize, out: &mut dyn Write) -> io::Result<()> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut shown = 0;
+    let mut next = height - 1;
+    while shown < lines.len() {
+        let end = (shown + next).min(lines.len());
+        for line in &lines[shown..end] {
+            writeln!(out, "{}", line)?;
+        }
+        shown = end;
+        if shown == lines.len() {
+            break;
+        }
+        write!(out, "{}", MORE)?;
+        out.flush()?;
+        let key = wait_key();
+        write!(out, "\r{}\r", " ".repeat(MORE.len()))?;
+        match key {
+            Some(KeyCode::Char(' ')) | Some(KeyCode::PageDown) => next = height - 1,
+            Some(KeyCode::Enter) | Some(KeyCode::Down) | Some(KeyCode::Char('j')) => next = 1,
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+/// Wait for a key press in raw mode. `None` if the terminal cannot be read.
+fn wait_key() -> Option<KeyCode> {
+    terminal::enable_raw_mode().ok()?;
+    let key = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => break Some(key.code),
+            Ok(_) => continue,
+            Err(_) => break None,
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    key
+}
+
+#[cfg(test)]
+mod test_pager {
+    use super::*;
+
+    #[test]
+    fn test_external() {
+        assert!(external("cat > /dev/null", "text"));
+        assert!(!external("exit 3", "text"));
+        assert!(!external("/nonexistent/pager", "text"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Registry of feature commands. Each feature module (e.g. `can`) registers
+//! its commands with their help lines and completion words, and `App` looks
+//! them up by name.
+
+use crate::bits;
+use crate::ble;
+use crate::bytes;
+use crate::can;
+use crate::crc;
+use crate::divider;
+use crate::escape;
+use crate::func::ArgParser;
+use crate::posit;
+use crate::usb;
+
+/// Handler of a feature command. It gets the command arguments and a parser
+/// for numeric arguments, and returns the text to print.
+pub type PluginFn = fn(&[&str], ArgParser) -> Result<String, String>;
+
+/// A command provided by a feature module.
+pub struct Plugin {
+    pub name: &'static str,
+    /// Usage and description of each form of the command, for `:help`.
+    pub help: &'static [(&'static str, &'static str)],
+    /// Words completed after the command name, e.g. subcommands.
+    pub completions: &'static [&'static str],
+    pub run: PluginFn,
+}
+
+/// Registered feature commands.
+#[derive(Default)]
+pub struct Registry {
+    plugins: Vec<Plugin>,
+}
+
+impl Registry {
+    /// A registry with the commands of all feature modules.
+    pub fn new() -> Self {
+        let mut registry = Registry::default();
+        can::register(&mut registry);
+        usb::register(&mut registry);
+        ble::register(&mut registry);
+        posit::register(&mut registry);
+        bits::register(&mut registry);
+        bytes::register(&mut registry);
+        crc::register(&mut registry);
+        divider::register(&mut registry);
+        escape::register(&mut registry);
+        registry
+    }
+
+    /// Add a command, replacing any command of the same name.
+    pub fn register(&mut self, plugin: Plugin) {
+        self.plugins.retain(|other| other.name != plugin.name);
+        self.plugins.push(plugin);
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Plugin> {
+        self.plugins.iter().find(|plugin| plugin.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Plugin> {
+        self.plugins.iter()
+    }
+
+    /// Help lines of all commands, in the layout of `:help`.
+    pub fn help(&self) -> String {
+        self.plugins.iter()
+            .flat_map(|plugin| plugin.help.iter())
+            .map(|(usage, desc)| format!("    {:27} {}\n", usage, desc))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_plugin {
+    use super::*;
+
+    fn echo(args: &[&str], _parse: ArgParser) -> Result<String, String> {
+        Ok(args.join(" "))
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::new();
+        assert!(registry.find("can").is_some());
+        assert!(registry.help().contains("    :uuid <uuid>                convert a BLE UUID"));
+        registry.register(Plugin { name: "echo", help: &[(":echo <words>", "print words")], completions: &[], run: echo });
+        let plugin = registry.find("echo").unwrap();
+        assert_eq!((plugin.run)(&["a", "b"], &|_| Ok(0)), Ok("a b".to_string()));
+        assert!(registry.help().ends_with("    :echo <words>               print words\n"));
+        assert!(registry.find("foo").is_none());
+    }
+}
@@ -0,0 +1,119 @@
+//! Experimental decoder for posit bit patterns, split into sign, regime,
+//! exponent and fraction. Posits use 2 exponent bits by default, as in the
+//! 2022 standard.
+
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+const USAGE: &str = "Usage: :posit <value> 8|16|32 [<es>]";
+
+/// Default number of exponent bits.
+const ES: u32 = 2;
+
+/// Register the `:posit` command.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "posit",
+        help: &[(":posit <value> 8|16|32 [es]", "decode a posit (experimental), es exponent bits (2)")],
+        completions: &["8", "16", "32"],
+        run: command,
+    });
+}
+
+/// Handle `:posit <value> 8|16|32 [<es>]`.
+pub fn command(args: &[&str], parse: ArgParser) -> Result<String, String> {
+    let (value, bits, es) = match args {
+        [value, bits] => (value, bits, None),
+        [value, bits, es] => (value, bits, Some(es)),
+        _ => return Err(USAGE.to_string()),
+    };
+    let bits = match *bits {
+        "8" => 8,
+        "16" => 16,
+        "32" => 32,
+        _ => return Err(format!("No posit size {}, expect 8, 16 or 32", bits)),
+    };
+    let es = match es {
+        Some(es) => es.parse::<u32>().ok().filter(|es| *es <= 4).ok_or_else(|| format!("Invalid exponent size {}, expect 0 to 4", es))?,
+        None => ES,
+    };
+    let value = parse(value).map_err(|e| e.to_string())?;
+    if value >> bits != 0 {
+        return Err(format!("0x{:x} does not fit in a posit{}", value, bits));
+    }
+    Ok(decode(value, bits, es))
+}
+
+/// Breakdown of a posit of `bits` bits with `es` exponent bits.
+fn decode(value: u64, bits: u32, es: u32) -> String {
+    let head = format!("posit{} es={} 0x{:0width$x}", bits, es, value, width = bits as usize / 4);
+    let mask = (1u64 << bits) - 1;
+    if value == 0 {
+        return format!("{}\nvalue       0", head);
+    }
+    if value == 1 << (bits - 1) {
+        return format!("{}\nvalue       NaR (not a real)", head);
+    }
+    let negative = value >> (bits - 1) == 1;
+    let magnitude = if negative { value.wrapping_neg() & mask } else { value };
+    // Bits after the sign, most significant first.
+    let body: Vec<u64> = (0..bits - 1).rev().map(|i| magnitude >> i & 1).collect();
+    let run = body.iter().take_while(|bit| **bit == body[0]).count();
+    let k = if body[0] == 1 { run as i64 - 1 } else { -(run as i64) };
+    let regime_len = (run + 1).min(body.len());
+    let rest = &body[regime_len..];
+    let (exponent, fraction) = rest.split_at(rest.len().min(es as usize));
+    let number = |bits: &[u64]| bits.iter().fold(0u64, |num, bit| num << 1 | bit);
+    let text = |bits: &[u64]| bits.iter().map(|bit| bit.to_string()).collect::<String>();
+    // Exponent bits cut off by the end of the posit are 0.
+    let e = number(exponent) << (es as usize - exponent.len());
+    let scale = k * (1 << es) + e as i64;
+    let fraction_value = 1.0 + number(fraction) as f64 / (1u64 << fraction.len()) as f64;
+    let real = fraction_value * 2f64.powi(scale as i32) * if negative { -1.0 } else { 1.0 };
+    let real = if scale.abs() > 20 { format!("{:e}", real) } else { real.to_string() };
+    let mut out = head;
+    out += &format!("\nsign        {}", if negative { "- (fields of the two's complement)" } else { "+" });
+    out += &format!("\nregime      {} (k = {})", text(&body[..regime_len]), k);
+    out += &format!("\nexponent    {} (e = {})", if exponent.is_empty() { "-".to_string() } else { text(exponent) }, e);
+    out += &format!("\nfraction    {} (1 + {})", if fraction.is_empty() { "-".to_string() } else { text(fraction) }, fraction_value - 1.0);
+    out += &format!("\nscale       2^{}", scale);
+    out += &format!("\nvalue       {}", real);
+    out
+}
+
+#[cfg(test)]
+mod test_posit {
+    use super::*;
+    use crate::testing::run;
+
+    fn value(args: &str) -> String {
+        run(command, args).unwrap().lines().last().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_decode() {
+        let out = run(command, "4000 16").unwrap();
+        assert!(out.starts_with("posit16 es=2 0x4000"));
+        assert!(out.contains("regime      10 (k = 0)"));
+        assert!(out.contains("exponent    00 (e = 0)"));
+        assert!(out.contains("value       1"));
+        assert_eq!(value("40 8"), "value       1");
+        assert_eq!(value("c0 8"), "value       -1");
+        assert_eq!(value("48 8"), "value       2");
+        assert_eq!(value("44 8"), "value       1.5");
+        assert_eq!(value("7f 8"), "value       1.6777216e7");
+        assert_eq!(value("1 8"), "value       5.960464477539063e-8");
+        assert_eq!(value("60 8 0"), "value       2");
+        assert_eq!(value("80 8"), "value       NaR (not a real)");
+        assert_eq!(value("0 32"), "value       0");
+        assert_eq!(value("40000000 32"), "value       1");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(run(command, "100 8").is_err());
+        assert!(run(command, "40 12").is_err());
+        assert!(run(command, "40 8 5").is_err());
+        assert!(run(command, "40").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Primality and factorization of 64-bit values, for `isprime` and `factor`,
+//! e.g. to size hash tables or pick divider chains. Primality is the
+//! deterministic Miller-Rabin test, and factors are found with Pollard's rho
+//! method, so that any 64-bit value is factored at once.
+
+/// Bases for which Miller-Rabin is exact below 2^64.
+const WITNESSES: &[u64] = &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Whether `n` is prime.
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(*p) {
+            return n == *p;
+        }
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+    WITNESSES.iter().all(|a| {
+        let mut x = pow_mod(*a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// A divisor of the composite `n`, other than 1 and `n`.
+fn divisor(n: u64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+    // Pollard's rho, with Floyd's cycle detection and a new constant when a
+    // cycle gives no divisor.
+    for c in 1.. {
+        let step = |x: u64| ((x as u128 * x as u128 + c) % n as u128) as u64;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = step(x);
+            y = step(step(y));
+            d = gcd(x.abs_diff(y), n);
+        }
+        if d != n {
+            return d;
+        }
+    }
+    unreachable!()
+}
+
+/// Greatest common divisor.
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Prime factors of `n` with their exponents, in increasing order. Empty for
+/// 0 and 1.
+pub fn factor(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    let mut pending = if n > 1 { vec![n] } else { vec![] };
+    while let Some(n) = pending.pop() {
+        if is_prime(n) {
+            primes.push(n);
+        } else {
+            let d = divisor(n);
+            pending.push(d);
+            pending.push(n / d);
+        }
+    }
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((last, exp)) if *last == p => *exp += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
+
+/// Factorization as text, e.g. `2^3 * 3 * 5`.
+pub fn describe(factors: &[(u64, u32)]) -> String {
+    let terms: Vec<String> = factors.iter()
+        .map(|(p, exp)| if *exp == 1 { p.to_string() } else { format!("{}^{}", p, exp) })
+        .collect();
+    terms.join(" * ")
+}
+
+#[cfg(test)]
+mod test_prime {
+    use super::*;
+
+    #[test]
+    fn test_is_prime() {
+        let small: Vec<u64> = (0..50).filter(|n| is_prime(*n)).collect();
+        assert_eq!(small, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47]);
+        assert!(is_prime(4_294_967_291));
+        assert!(is_prime(18_446_744_073_709_551_557));
+        // Strong pseudoprime to bases 2, 3, 5 and 7.
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(u64::MAX));
+    }
+
+    #[test]
+    fn test_factor() {
+        assert_eq!(factor(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(factor(1), vec![]);
+        assert_eq!(factor(0), vec![]);
+        assert_eq!(factor(97), vec![(97, 1)]);
+        assert_eq!(factor(u64::MAX), vec![(3, 1), (5, 1), (17, 1), (257, 1), (641, 1), (65537, 1), (6_700_417, 1)]);
+        assert_eq!(factor(4_294_967_291 * 4_294_967_279), vec![(4_294_967_279, 1), (4_294_967_291, 1)]);
+        assert_eq!(factor(1 << 63), vec![(2, 63)]);
+        assert_eq!(describe(&factor(360)), "2^3 * 3^2 * 5");
+    }
+}
@@ -0,0 +1,103 @@
+//! Progress of bulk operations on large files, drawn on stderr when it is a
+//! terminal, e.g. `[#######-------------]  35%  1.1 GiB of 3.2 GiB`, so that
+//! a long job is seen moving.
+
+use std::io::{stderr, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::units;
+
+/// Delay before the first drawing, so that quick operations draw nothing.
+const DELAY: Duration = Duration::from_millis(500);
+
+/// Delay between two drawings.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Width of the bar, in characters.
+const WIDTH: u64 = 20;
+
+/// Progress of an operation over a number of bytes.
+pub struct Progress {
+    /// Number of bytes of the operation, if known, e.g. not for pipes.
+    total: Option<u64>,
+    done: u64,
+    enabled: bool,
+    next: Instant,
+    /// Length of the drawn line, 0 if none.
+    drawn: usize,
+}
+
+impl Progress {
+    /// Progress over `total` bytes, drawn if `enabled` and stderr is a
+    /// terminal.
+    pub fn new(total: Option<u64>, enabled: bool) -> Self {
+        Progress { total, done: 0, enabled: enabled && stderr().is_terminal(), next: Instant::now() + DELAY, drawn: 0 }
+    }
+
+    /// Progress that is never drawn.
+    pub fn hidden() -> Self {
+        Progress::new(None, false)
+    }
+
+    /// Count `bytes` more bytes done, and redraw if due.
+    pub fn advance(&mut self, bytes: u64) {
+        self.done += bytes;
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        if now < self.next {
+            return;
+        }
+        self.next = now + INTERVAL;
+        let line = describe(self.done, self.total);
+        let mut err = stderr().lock();
+        let _ = write!(err, "\r{:width$}", line, width = self.drawn);
+        let _ = err.flush();
+        self.drawn = line.len();
+    }
+
+    /// Clear the drawn line, e.g. before printing an error.
+    pub fn clear(&mut self) {
+        if self.drawn > 0 {
+            let mut err = stderr().lock();
+            let _ = write!(err, "\r{:width$}\r", "", width = self.drawn);
+            let _ = err.flush();
+            self.drawn = 0;
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Line of progress of `done` bytes out of `total`.
+pub fn describe(done: u64, total: Option<u64>) -> String {
+    match total {
+        Some(total) => {
+            let done = done.min(total);
+            let percent = if total == 0 { 100 } else { (done as u128 * 100 / total as u128) as u64 };
+            let filled = (percent * WIDTH / 100) as usize;
+            let bar = "#".repeat(filled) + &"-".repeat(WIDTH as usize - filled);
+            format!("[{}] {:3}%  {} of {}", bar, percent, units::format_approximate_size(done), units::format_approximate_size(total))
+        }
+        None => format!("{} read", units::format_approximate_size(done)),
+    }
+}
+
+#[cfg(test)]
+mod test_progress {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe(0, Some(1 << 30)), "[--------------------]   0%  0 B of 1.0 GiB");
+        assert_eq!(describe(3 << 28, Some(1 << 30)), "[###############-----]  75%  768.0 MiB of 1.0 GiB");
+        assert_eq!(describe(2000, Some(1500)), "[####################] 100%  1.5 KiB of 1.5 KiB");
+        assert_eq!(describe(0, Some(0)), "[####################] 100%  0 B of 0 B");
+        assert_eq!(describe(5 << 40, None), "5.0 TiB read");
+    }
+}
@@ -0,0 +1,57 @@
+//! Random values of `:rand`, e.g. test vectors or fuzzed register writes.
+//! The generator is SplitMix64, seeded from the clock: fast and well spread,
+//! but not for cryptographic use.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most values of one `:rand`, `:seq` or `:combos`.
+pub const MAX_COUNT: usize = 1 << 20;
+
+/// SplitMix64 generator.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// Generator seeded from the clock and the process id, different at
+    /// each run.
+    pub fn from_clock() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Rng(nanos ^ (std::process::id() as u64) << 32)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random value of `width` bits, 1 to 64.
+    pub fn bits(&mut self, width: u32) -> u64 {
+        self.next_u64() >> (64 - width)
+    }
+}
+
+#[cfg(test)]
+mod test_random {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        // Reference outputs of SplitMix64 seeded with 0.
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+
+        let mut rng = Rng::from_clock();
+        let values: Vec<u64> = (0..1000).map(|_| rng.bits(4)).collect();
+        assert!(values.iter().all(|value| *value < 16));
+        assert!((0..16).all(|value| values.contains(&value)));
+        assert_ne!(Rng::new(1).bits(64), Rng::new(2).bits(64));
+    }
+}
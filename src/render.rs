@@ -0,0 +1,168 @@
+//! Rendering of output lines, with optional ANSI colors.
+
+use std::env;
+use std::fmt;
+use std::io::{stdout, IsTerminal};
+use crate::settings::Settings;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+/// Colors used in turn for digit groups.
+const GROUP_COLORS: [&str; 2] = ["\x1b[36m", "\x1b[33m"];
+
+/// Color setting, changed with `:color on|off|auto`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorMode {
+    On,
+    Off,
+    /// Color only when stdout is a terminal and `NO_COLOR` is not set.
+    Auto,
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorMode::On => write!(f, "on"),
+            ColorMode::Off => write!(f, "off"),
+            ColorMode::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl ColorMode {
+    /// Read a color mode from its name.
+    pub fn from_name(name: &str) -> Option<ColorMode> {
+        match name {
+            "on" => Some(ColorMode::On),
+            "off" => Some(ColorMode::Off),
+            "auto" => Some(ColorMode::Auto),
+            _ => None,
+        }
+    }
+
+    /// Tell whether output should be colored.
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorMode::On => true,
+            ColorMode::Off => false,
+            ColorMode::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Render a converted value tagged with its base, e.g. `<bin> 0001_0000`.
+/// With colors, the tag, the `0x`/`0b` prefix and any trailing note are dimmed
+/// and `_`-separated digit groups are colored alternately.
+pub fn output(tag: &str, out: &str, color: bool) -> String {
+    if !color {
+        return format!("<{}> {}", tag, out);
+    }
+    let (value, note) = match out.find(' ') {
+        Some(i) => out.split_at(i),
+        None => (out, ""),
+    };
+    let (prefix, digits) = if value.starts_with("0x") || value.starts_with("0b") {
+        value.split_at(2)
+    } else {
+        ("", value)
+    };
+    let groups: Vec<String> = digits.split('_')
+        .enumerate()
+        .map(|(i, group)| format!("{}{}{}", GROUP_COLORS[i % 2], group, RESET))
+        .collect();
+    let mut ret = format!("{}<{}>{} ", DIM, tag, RESET);
+    if !prefix.is_empty() {
+        ret += &format!("{}{}{}", DIM, prefix, RESET);
+    }
+    ret += &groups.join("_");
+    if !note.is_empty() {
+        ret += &format!("{}{}{}", DIM, note, RESET);
+    }
+    ret
+}
+
+/// Render an error message, in red with colors.
+pub fn error(msg: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", RED, msg, RESET)
+    } else {
+        msg.to_string()
+    }
+}
+
+/// Remove ANSI color codes, e.g. `\x1b[31m`.
+pub fn strip_colors(text: &str) -> String {
+    let mut ret = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+/// Render the prompt template. `{key}` is replaced by the value of the setting
+/// `key`, with `{in}` and `{out}` standing for the input and output bases.
+/// Unknown placeholders are left as is.
+pub fn prompt(template: &str, settings: &Settings) -> String {
+    let mut ret = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        ret += &rest[..start];
+        rest = &rest[start..];
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        let key = match &rest[1..end] {
+            "in" => "from",
+            "out" => "to",
+            key => key,
+        };
+        match settings.get(key) {
+            Some(value) => ret += &value,
+            None => ret += &rest[..=end],
+        }
+        rest = &rest[end + 1..];
+    }
+    ret + rest
+}
+
+#[cfg(test)]
+mod test_render {
+    use super::*;
+
+    #[test]
+    fn test_output() {
+        assert_eq!(output("bin", "0001_0000", false), "<bin> 0001_0000");
+        assert_eq!(output("bin", "0001_0000", true),
+            "\x1b[2m<bin>\x1b[0m \x1b[36m0001\x1b[0m_\x1b[33m0000\x1b[0m");
+        assert_eq!(output("hex", "0x12 (u16)", true),
+            "\x1b[2m<hex>\x1b[0m \x1b[2m0x\x1b[0m\x1b[36m12\x1b[0m\x1b[2m (u16)\x1b[0m");
+        assert_eq!(error("Error: x", false), "Error: x");
+        assert_eq!(error("Error: x", true), "\x1b[31mError: x\x1b[0m");
+    }
+
+    #[test]
+    fn test_prompt() {
+        let settings = Settings { width: 32, ..Default::default() };
+        assert_eq!(prompt(&settings.prompt, &settings), "<hex>$ ");
+        assert_eq!(prompt("{in}→{out} w{width}> ", &settings), "hex→bin w32> ");
+        assert_eq!(prompt("{foo} {in", &settings), "{foo} {in");
+    }
+
+    #[test]
+    fn test_color_mode() {
+        assert!(ColorMode::On.enabled());
+        assert!(!ColorMode::Off.enabled());
+        assert_eq!(ColorMode::from_name("auto"), Some(ColorMode::Auto));
+        assert_eq!(ColorMode::from_name("yes"), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! JSON-RPC 2.0 server, started with `--serve`, so that editors and other
+//! tools can run the converter as a subprocess. Requests and responses are
+//! JSON objects, one per line, e.g.
+//!
+//! ```text
+//! --> {"jsonrpc": "2.0", "id": 1, "method": "convert", "params": {"input": "ff"}}
+//! <-- {"jsonrpc":"2.0","id":1,"result":{"input":"ff","value":255,"output":"1111_1111",...}}
+//! ```
+//!
+//! Methods:
+//! - `convert {input, settings?}`: the conversion in every base, like `:format json`.
+//!   `settings` is an object of `:set` keys and values used for this request only.
+//! - `evaluate {expression}`: the value of a number or function call, and its width.
+//! - `decode {decoder, args}`: the text of a decoder command, e.g. `can` with `["7df"]`.
+//! - `set {key: value, ...}`: change the settings of the following requests, and
+//!   return all settings.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use serde_json::{json, Map, Value};
+use crate::converter;
+use crate::error::{CommandError, ConvertError};
+use crate::exit;
+use crate::func;
+use crate::plugin::Registry;
+use crate::settings::{self, Settings};
+
+/// Error of a request, sent as the `error` member of the response.
+#[derive(Debug, PartialEq)]
+pub enum RpcError {
+    /// The line is not JSON.
+    Parse(String),
+    /// The JSON is not a request object.
+    InvalidRequest,
+    MethodNotFound(String),
+    InvalidParams(String),
+    Convert(ConvertError),
+    Command(CommandError),
+    Decode(String),
+}
+
+impl RpcError {
+    /// Error code. Protocol errors have the codes of the JSON-RPC
+    /// specification, failed requests have the exit code of the program.
+    pub fn code(&self) -> i32 {
+        match self {
+            RpcError::Parse(_) => -32700,
+            RpcError::InvalidRequest => -32600,
+            RpcError::MethodNotFound(_) => -32601,
+            RpcError::InvalidParams(_) => -32602,
+            RpcError::Convert(e) => exit::code(e),
+            RpcError::Command(_) => exit::USAGE,
+            RpcError::Decode(_) => exit::INVALID,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        json!({ "code": self.code(), "message": self.to_string() })
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Parse(msg) => write!(f, "parse error: {}", msg),
+            RpcError::InvalidRequest => write!(f, "invalid request"),
+            RpcError::MethodNotFound(name) => write!(f, "no method {}", name),
+            RpcError::InvalidParams(msg) => write!(f, "invalid params: {}", msg),
+            RpcError::Convert(e) => write!(f, "{}", e),
+            RpcError::Command(e) => write!(f, "{}", e),
+            RpcError::Decode(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<ConvertError> for RpcError {
+    fn from(e: ConvertError) -> Self {
+        RpcError::Convert(e)
+    }
+}
+
+impl From<CommandError> for RpcError {
+    fn from(e: CommandError) -> Self {
+        RpcError::Command(e)
+    }
+}
+
+/// Server answering requests with its own settings and decoders.
+pub struct Server {
+    settings: Settings,
+    plugins: Registry,
+}
+
+impl Server {
+    pub fn new(settings: Settings) -> Self {
+        Self { settings, plugins: Registry::new() }
+    }
+
+    /// Answer every line of `input` until its end. Empty lines are skipped.
+    pub fn serve(&mut self, input: impl BufRead, output: &mut dyn Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one request line. Notifications, i.e. valid requests without
+    /// `id`, get no response. Invalid requests always do, with a null `id`
+    /// if they have none, e.g. `[]` or `5`.
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(response(Value::Null, Err(RpcError::Parse(e.to_string())))),
+        };
+        let id = request.get("id").cloned();
+        let method = match (request.get("jsonrpc"), request.get("method")) {
+            (Some(version), Some(Value::String(method))) if version == "2.0" => method,
+            _ => return Some(response(id.unwrap_or(Value::Null), Err(RpcError::InvalidRequest))),
+        };
+        let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+        let result = self.call(method, &params);
+        id.map(|id| response(id, result))
+    }
+
+    /// Run a method.
+    fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "convert" => {
+                let input = param_str(params, "input")?;
+                let mut settings = self.settings.clone();
+                if let Some(overrides) = params.get("settings") {
+                    apply(&mut settings, object(overrides)?)?;
+                }
+                let conversion = converter::convert(input, &settings)?;
+                Ok(conversion.to_value(&settings))
+            }
+            "evaluate" => {
+                let value = func::eval(param_str(params, "expression")?, &|arg| self.settings.in_base.to_num(arg), &self.settings)?;
+                Ok(json!({ "value": value.num, "width": value.width }))
+            }
+            "decode" => {
+                let name = param_str(params, "decoder")?;
+                let plugin = self.plugins.find(name).ok_or_else(|| RpcError::InvalidParams(format!("no decoder {}", name)))?;
+                let args = match params.get("args") {
+                    None => Vec::new(),
+                    Some(Value::Array(args)) => args.iter()
+                        .map(|arg| arg.as_str().ok_or_else(|| RpcError::InvalidParams("args must be strings".to_string())))
+                        .collect::<Result<Vec<&str>, RpcError>>()?,
+                    Some(_) => return Err(RpcError::InvalidParams("args must be an array".to_string())),
+                };
+                let text = (plugin.run)(&args, &|arg| func::read_arg(arg, &self.settings)).map_err(RpcError::Decode)?;
+                Ok(json!({ "text": text }))
+            }
+            "set" => {
+                let mut settings = self.settings.clone();
+                apply(&mut settings, object(params)?)?;
+                self.settings = settings;
+                let values: Map<String, Value> = settings::KEYS.iter()
+                    .map(|key| (key.to_string(), json!(self.settings.get(key))))
+                    .collect();
+                Ok(Value::Object(values))
+            }
+            _ => Err(RpcError::MethodNotFound(method.to_string())),
+        }
+    }
+}
+
+/// Response object of a request.
+fn response(id: Value, result: Result<Value, RpcError>) -> String {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": e.to_value() }),
+    }.to_string()
+}
+
+fn object(params: &Value) -> Result<&Map<String, Value>, RpcError> {
+    params.as_object().ok_or_else(|| RpcError::InvalidParams("expect an object".to_string()))
+}
+
+/// Get a string member of the params.
+fn param_str<'a>(params: &'a Value, key: &str) -> Result<&'a str, RpcError> {
+    params.get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::InvalidParams(format!("missing string {}", key)))
+}
+
+/// Apply `:set` keys and values. Values can be strings, numbers or booleans.
+fn apply(settings: &mut Settings, values: &Map<String, Value>) -> Result<(), RpcError> {
+    for (key, value) in values {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Bool(true) => "on".to_string(),
+            Value::Bool(false) => "off".to_string(),
+            Value::Number(n) => n.to_string(),
+            _ => return Err(RpcError::InvalidParams(format!("bad value of {}", key))),
+        };
+        settings.set(key, &value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_rpc {
+    use super::*;
+
+    fn call(server: &mut Server, request: Value) -> Value {
+        serde_json::from_str(&server.handle(&request.to_string()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_methods() {
+        let mut server = Server::new(Settings::default());
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 1, "method": "convert", "params": {"input": "ff"}}));
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["output"], "1111_1111");
+        let params = json!({"input": "10", "settings": {"from": "dec", "to": "hex", "width": 16}});
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 2, "method": "convert", "params": params}));
+        assert_eq!(response["result"]["output"], "0x000a");
+
+        let params = json!({"expression": "htons(0x1234)"});
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 3, "method": "evaluate", "params": params}));
+        assert_eq!(response["result"], json!({"value": 0x3412, "width": 16}));
+
+        let params = json!({"decoder": "can", "args": ["7df"]});
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 4, "method": "decode", "params": params}));
+        assert!(response["result"]["text"].as_str().unwrap().contains("11-bit"));
+
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 5, "method": "set", "params": {"from": "dec"}}));
+        assert_eq!(response["result"]["from"], "dec");
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 6, "method": "evaluate", "params": {"expression": "10"}}));
+        assert_eq!(response["result"]["value"], 10);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut server = Server::new(Settings::default());
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 1, "method": "convert", "params": {"input": "fg"}}));
+        assert_eq!(response["error"], json!({"code": 1, "message": "invalid hex digit in fg"}));
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 2, "method": "set", "params": {"to": "hx"}}));
+        assert_eq!(response["error"]["code"], 2);
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 3, "method": "round"}));
+        assert_eq!(response["error"]["code"], -32601);
+        let response = call(&mut server, json!({"jsonrpc": "2.0", "id": 4, "method": "convert"}));
+        assert_eq!(response["error"]["code"], -32602);
+        let response = call(&mut server, json!({"id": 5, "method": "convert"}));
+        assert_eq!(response["error"]["code"], -32600);
+        let response: Value = serde_json::from_str(&server.handle("{").unwrap()).unwrap();
+        assert_eq!((response["id"].clone(), response["error"]["code"].clone()), (Value::Null, json!(-32700)));
+        assert_eq!(server.handle(r#"{"jsonrpc": "2.0", "method": "set", "params": {"to": "dec"}}"#), None);
+        assert_eq!(server.handle(r#"{"jsonrpc": "2.0", "method": "round"}"#), None);
+    }
+
+    #[test]
+    fn test_invalid_without_id() {
+        let mut server = Server::new(Settings::default());
+        for request in ["[]", "\"x\"", "5", "null", "{}", r#"{"method": "convert"}"#, r#"[{"jsonrpc": "2.0", "id": 1, "method": "convert"}]"#] {
+            let response: Value = serde_json::from_str(&server.handle(request).unwrap()).unwrap();
+            assert_eq!(response, json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "invalid request"}}), "{}", request);
+        }
+    }
+
+    #[test]
+    fn test_serve() {
+        let mut server = Server::new(Settings::default());
+        let input = "{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"evaluate\", \"params\": {\"expression\": \"ff\"}}\n\n";
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "{\"id\":1,\"jsonrpc\":\"2.0\",\"result\":{\"value\":255,\"width\":null}}\n");
+    }
+}
@@ -0,0 +1,95 @@
+//! Sequences of `:seq`, e.g. register addresses or the inputs of a lookup
+//! table, and the combinations of flags of `:combos`.
+
+use crate::random::MAX_COUNT;
+
+/// Values from `start` to `end` included, `step` apart, counting down if
+/// `end` is below `start`.
+pub fn values(start: u64, end: u64, step: u64) -> Result<Vec<u64>, String> {
+    if step == 0 {
+        return Err("step must not be 0".to_string());
+    }
+    let steps = start.abs_diff(end) / step;
+    if steps >= MAX_COUNT as u64 {
+        return Err(format!("{} values, at most {}", steps as u128 + 1, MAX_COUNT));
+    }
+    Ok((0..=steps).map(|i| if end < start { start - i * step } else { start + i * step }).collect())
+}
+
+/// Values of `width` bits with exactly `bits` bits set, in increasing order.
+pub fn combos(width: u32, bits: u32) -> Result<Vec<u64>, String> {
+    let count = binomial(width, bits);
+    if count > MAX_COUNT as u128 {
+        return Err(format!("{} values, at most {}", count, MAX_COUNT));
+    }
+    if bits == 0 {
+        return Ok(vec![0]);
+    }
+    let last = u64::MAX >> (64 - bits) << (width - bits);
+    let mut value = u64::MAX >> (64 - bits);
+    let mut values = Vec::with_capacity(count as usize);
+    loop {
+        values.push(value);
+        if value == last {
+            return Ok(values);
+        }
+        // Next value with as many bits set (Gosper's hack).
+        let low = value & value.wrapping_neg();
+        let carry = value + low;
+        value = carry | (((value ^ carry) >> 2) / low);
+    }
+}
+
+/// Number of ways to choose `k` of `n` items.
+fn binomial(n: u32, k: u32) -> u128 {
+    (0..k.min(n - k) as u128).fold(1, |count, i| count * (n as u128 - i) / (i + 1))
+}
+
+/// One value per line, after its index from 0 if `index`.
+pub fn table(values: &[u64], index: bool, format: &dyn Fn(u64) -> String) -> String {
+    let digits = values.len().saturating_sub(1).to_string().len();
+    let mut out = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if index {
+            out += &format!("{:>digits$}  ", i, digits = digits);
+        }
+        out += &format(*value);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod test_seq {
+    use super::*;
+
+    #[test]
+    fn test_values() {
+        assert_eq!(values(0x4000_0000, 0x4000_0010, 4), Ok(vec![0x4000_0000, 0x4000_0004, 0x4000_0008, 0x4000_000c, 0x4000_0010]));
+        assert_eq!(values(10, 1, 4), Ok(vec![10, 6, 2]));
+        assert_eq!(values(0, u64::MAX, 1 << 63), Ok(vec![0, 1 << 63]));
+        assert_eq!(values(5, 5, 1), Ok(vec![5]));
+        assert!(values(0, 1, 0).is_err());
+        assert_eq!(values(0, u64::MAX, 1), Err("18446744073709551616 values, at most 1048576".to_string()));
+    }
+
+    #[test]
+    fn test_combos() {
+        assert_eq!(combos(4, 2), Ok(vec![0b0011, 0b0101, 0b0110, 0b1001, 0b1010, 0b1100]));
+        assert_eq!(combos(8, 0), Ok(vec![0]));
+        assert_eq!(combos(8, 8), Ok(vec![0xff]));
+        assert_eq!(combos(64, 1).unwrap().last(), Some(&(1 << 63)));
+        assert_eq!(combos(64, 63).unwrap().len(), 64);
+        assert_eq!(combos(16, 8).unwrap().len(), 12870);
+        assert_eq!(combos(64, 32), Err("1832624140942590534 values, at most 1048576".to_string()));
+    }
+
+    #[test]
+    fn test_table() {
+        let values: Vec<u64> = (0..11).collect();
+        let out = table(&values, true, &|value| format!("0x{:x}", value));
+        assert!(out.starts_with(" 0  0x0\n 1  0x1\n"));
+        assert!(out.ends_with("10  0xa\n"));
+        assert_eq!(table(&[1, 2], false, &|value| value.to_string()), "1\n2\n");
+    }
+}
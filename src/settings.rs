@@ -0,0 +1,229 @@
+//! User settings, changed with `:set <key> <value>` and printed with `:show`.
+
+use crate::base::{Base, Style};
+use crate::conversion::OutputFormat;
+use crate::error::CommandError;
+use crate::history;
+use crate::render::ColorMode;
+use crate::units::{self, Rounding};
+
+/// Names of the settings, in the order `:show` prints them.
+pub const KEYS: &[&str] = &["from", "to", "width", "group", "case", "style", "format", "color", "history", "prompt", "pager", "quiet", "fullscale", "round", "tickrate", "kilo", "float", "others"];
+
+/// Configuration consumed by the parser and the formatter.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub in_base: Base,
+    pub out_base: Base,
+    /// Output width in bits, 0 to print only the needed digits.
+    pub width: u32,
+    /// Number of binary digits per `_`-separated group, 0 for no grouping.
+    pub group: u32,
+    /// Print hex digits in upper case.
+    pub uppercase: bool,
+    pub style: Style,
+    pub format: OutputFormat,
+    pub color: ColorMode,
+    /// Maximum number of history entries.
+    pub history_size: usize,
+    /// Prompt template, see `render::prompt`.
+    pub prompt: String,
+    /// Page output taller than the terminal.
+    pub pager: bool,
+    /// Print only the converted value, without base tag or width note.
+    pub quiet: bool,
+    /// Value of 100% for `pct`, `frac` and `ratio`, e.g. 0x8000 for Q1.15.
+    pub full_scale: u64,
+    /// Rounding of `pct`, `frac`, `freq`, `period` and `ticks`.
+    pub rounding: Rounding,
+    /// Tick rate in Hz for `ticks` and `duration`, e.g. of an RTOS.
+    pub tick_rate: u64,
+    /// Read the shorthand `4k` or `2M` with binary prefixes, 1024, else with
+    /// SI ones, 1000.
+    pub binary_shorthand: bool,
+    /// Read float literals, e.g. `3.14f` or `2.5e3`, as their IEEE 754 bits.
+    pub float: bool,
+    /// Pad VHDL literals to the width with an `others`-style aggregate, e.g.
+    /// `(15 downto 8 => '0') & x"be"`.
+    pub others: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            in_base: Base::Hex,
+            out_base: Base::Bin,
+            width: 0,
+            group: 4,
+            uppercase: false,
+            style: Style::Plain,
+            format: OutputFormat::Text,
+            color: ColorMode::Auto,
+            history_size: history::DEFAULT_SIZE,
+            prompt: "<{in}>$ ".to_string(),
+            pager: true,
+            quiet: false,
+            full_scale: 1 << 16,
+            rounding: Rounding::Nearest,
+            tick_rate: 1000,
+            binary_shorthand: true,
+            float: false,
+            others: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Change a setting from its name and a value given as text.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), CommandError> {
+        match key {
+            "from" => self.in_base = Base::from_name(value).ok_or_else(|| CommandError::UnknownBase(value.to_string()))?,
+            "to" => self.out_base = Base::from_name(value).ok_or_else(|| CommandError::UnknownBase(value.to_string()))?,
+            "width" => {
+                self.width = match value.parse::<u32>() {
+                    Ok(width) if width <= 64 => width,
+                    _ => return Err(CommandError::invalid(key, value, "0 to 64")),
+                }
+            }
+            "group" => {
+                self.group = value.parse::<u32>().map_err(|_| CommandError::invalid(key, value, "a number"))?
+            }
+            "case" => {
+                self.uppercase = match value {
+                    "lower" => false,
+                    "upper" => true,
+                    _ => return Err(CommandError::invalid(key, value, "lower or upper")),
+                }
+            }
+            "style" => self.style = Style::from_name(value).ok_or_else(|| CommandError::invalid(key, value, "plain, c, verilog or vhdl"))?,
+            "format" => {
+                self.format = OutputFormat::from_name(value)
+                    .ok_or_else(|| CommandError::invalid(key, value, "text, json, csv or tsv"))?
+            }
+            "color" => {
+                self.color = ColorMode::from_name(value).ok_or_else(|| CommandError::invalid(key, value, "on, off or auto"))?
+            }
+            "history" => {
+                self.history_size = value.parse::<usize>().map_err(|_| CommandError::invalid(key, value, "a number"))?
+            }
+            "prompt" => self.prompt = value.to_string(),
+            "pager" => self.pager = on_off(key, value)?,
+            "quiet" => self.quiet = on_off(key, value)?,
+            "fullscale" => {
+                self.full_scale = full_scale(value).ok_or_else(|| CommandError::invalid(key, value, "q1 to q63 or a number"))?
+            }
+            "round" => {
+                self.rounding = Rounding::from_name(value)
+                    .ok_or_else(|| CommandError::invalid(key, value, "nearest, floor, ceil or truncate"))?
+            }
+            "tickrate" => {
+                self.tick_rate = tick_rate(value).ok_or_else(|| CommandError::invalid(key, value, "a frequency in whole Hz, e.g. 1kHz"))?
+            }
+            "kilo" => {
+                self.binary_shorthand = match value {
+                    "1024" => true,
+                    "1000" => false,
+                    _ => return Err(CommandError::invalid(key, value, "1024 or 1000")),
+                }
+            }
+            "float" => self.float = on_off(key, value)?,
+            "others" => self.others = on_off(key, value)?,
+            _ => return Err(CommandError::UnknownSetting(key.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Get the value of a setting as text.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "from" => self.in_base.to_string(),
+            "to" => self.out_base.to_string(),
+            "width" => self.width.to_string(),
+            "group" => self.group.to_string(),
+            "case" => if self.uppercase { "upper" } else { "lower" }.to_string(),
+            "style" => self.style.to_string(),
+            "format" => self.format.to_string(),
+            "color" => self.color.to_string(),
+            "history" => self.history_size.to_string(),
+            "prompt" => self.prompt.clone(),
+            "pager" => if self.pager { "on" } else { "off" }.to_string(),
+            "quiet" => if self.quiet { "on" } else { "off" }.to_string(),
+            // `q0` is not a valid value, 1 is written as a number.
+            "fullscale" if self.full_scale.is_power_of_two() && self.full_scale > 1 => format!("q{}", self.full_scale.trailing_zeros()),
+            "fullscale" => self.full_scale.to_string(),
+            "round" => self.rounding.to_string(),
+            "tickrate" => self.tick_rate.to_string(),
+            "kilo" => if self.binary_shorthand { "1024" } else { "1000" }.to_string(),
+            "float" => if self.float { "on" } else { "off" }.to_string(),
+            "others" => if self.others { "on" } else { "off" }.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Read an on/off value of the setting `key`.
+fn on_off(key: &str, value: &str) -> Result<bool, CommandError> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(CommandError::invalid(key, value, "on or off")),
+    }
+}
+
+/// Read a full scale, `q<n>` for the Qm.n formats where 1.0 is 2^n, or a
+/// number in decimal or with a `0x` prefix.
+fn full_scale(value: &str) -> Option<u64> {
+    let num = if let Some(bits) = value.strip_prefix('q') {
+        bits.parse::<u32>().ok().filter(|bits| (1..64).contains(bits)).map(|bits| 1 << bits)
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse().ok()
+    };
+    num.filter(|num| *num > 0)
+}
+
+/// Read a tick rate, in Hz or with a unit, e.g. `32.768kHz`.
+fn tick_rate(value: &str) -> Option<u64> {
+    let mhz = units::parse_frequency(value).or_else(|_| units::scaled(value, 1000)).ok()?;
+    Some(mhz / 1000).filter(|hz| *hz > 0 && mhz % 1000 == 0)
+}
+
+#[cfg(test)]
+mod test_settings {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut settings = Settings::default();
+        assert!(settings.set("from", "dec").is_ok() && settings.in_base == Base::Dec);
+        assert!(settings.set("width", "32").is_ok() && settings.width == 32);
+        assert!(settings.set("case", "upper").is_ok() && settings.uppercase);
+        assert_eq!(settings.set("width", "65"), Err(CommandError::invalid("width", "65", "0 to 64")));
+        assert!(settings.set("group", "-1").is_err());
+        assert!(settings.set("case", "title").is_err());
+        assert!(settings.set("colour", "on").is_err());
+        for key in KEYS {
+            let value = settings.get(key).unwrap();
+            assert!(settings.set(key, &value).is_ok());
+        }
+        assert_eq!(settings.get("width"), Some("32".to_string()));
+        assert_eq!(settings.get("foo"), None);
+        assert!(settings.set("fullscale", "q15").is_ok() && settings.full_scale == 0x8000);
+        assert!(settings.set("fullscale", "0x3e8").is_ok() && settings.get("fullscale") == Some("1000".to_string()));
+        assert!(settings.set("fullscale", "0").is_err());
+        assert!(settings.set("fullscale", "q64").is_err());
+        for full_scale in ["1", "q1", "q15", "q63", "1000"] {
+            assert!(settings.set("fullscale", full_scale).is_ok());
+            let value = settings.get("fullscale").unwrap();
+            assert_eq!(value, full_scale);
+            assert!(settings.set("fullscale", &value).is_ok());
+        }
+        assert!(settings.set("tickrate", "32.768kHz").is_ok() && settings.tick_rate == 32768);
+        assert!(settings.set("tickrate", "100").is_ok() && settings.tick_rate == 100);
+        assert!(settings.set("tickrate", "0.5Hz").is_err());
+        assert!(settings.set("kilo", "1000").is_ok() && !settings.binary_shorthand);
+        assert!(settings.set("kilo", "1k").is_err());
+    }
+}
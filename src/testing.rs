@@ -0,0 +1,17 @@
+//! Fixtures shared by the unit tests.
+
+use crate::base::Base;
+use crate::error::ConvertError;
+use crate::func::ArgParser;
+
+/// Read a value in hex, the default input base, e.g. as the `ArgParser`
+/// of decoder commands.
+pub fn hex(input: &str) -> Result<u64, ConvertError> {
+    Base::Hex.to_num(input)
+}
+
+/// Run a plugin command on whitespace-separated `args` read in hex, e.g.
+/// `run(command, "bcd 0210")`.
+pub fn run(command: fn(&[&str], ArgParser) -> Result<String, String>, args: &str) -> Result<String, String> {
+    command(&args.split_whitespace().collect::<Vec<_>>(), &hex)
+}
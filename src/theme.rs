@@ -0,0 +1,138 @@
+//! Colors of the TUI, set in a `[theme]` table of the configuration file:
+//! ```toml
+//! [theme]
+//! name = "monochrome"
+//! selection = "blue"
+//! ```
+//! `name` picks a preset, `default` or `monochrome` for terminals without
+//! colors, and the other keys override its colors: `nibble` for every other
+//! group of 4 bits, `field` for the field diagram, `error`, `selection` for
+//! the selected bit or entry and `diff` for the bits that differ when
+//! comparing. `none` draws without color, with bold or reversed text instead.
+
+use crate::error::CommandError;
+
+/// Color of a theme, one of the basic terminal colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    None,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    White,
+}
+
+/// Names of the colors.
+const COLORS: &[(&str, Color)] = &[
+    ("none", Color::None),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("gray", Color::Gray),
+    ("white", Color::White),
+];
+
+impl Color {
+    pub fn from_name(name: &str) -> Option<Color> {
+        COLORS.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, color)| *color)
+    }
+
+    pub fn name(self) -> &'static str {
+        COLORS.iter().find(|(_, color)| *color == self).map_or("none", |(name, _)| name)
+    }
+}
+
+/// Colors of the TUI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub nibble: Color,
+    pub field: Color,
+    pub error: Color,
+    pub selection: Color,
+    pub diff: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            nibble: Color::Cyan,
+            field: Color::Green,
+            error: Color::Red,
+            selection: Color::None,
+            diff: Color::Yellow,
+        }
+    }
+}
+
+impl Theme {
+    /// Theme without colors, for limited terminals.
+    pub fn monochrome() -> Self {
+        Theme {
+            nibble: Color::None,
+            field: Color::None,
+            error: Color::None,
+            selection: Color::None,
+            diff: Color::None,
+        }
+    }
+
+    /// Change a color, or the whole theme with `name`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), CommandError> {
+        if key == "name" {
+            *self = match value {
+                "default" => Theme::default(),
+                "monochrome" => Theme::monochrome(),
+                _ => return Err(CommandError::invalid("theme", value, "default or monochrome")),
+            };
+            return Ok(());
+        }
+        let color = Color::from_name(value)
+            .ok_or_else(|| CommandError::invalid(key, value, "none, black, red, green, yellow, blue, magenta, cyan, gray or white"))?;
+        match key {
+            "nibble" => self.nibble = color,
+            "field" => self.field = color,
+            "error" => self.error = color,
+            "selection" => self.selection = color,
+            "diff" => self.diff = color,
+            _ => return Err(CommandError::UnknownSetting(format!("theme.{}", key))),
+        }
+        Ok(())
+    }
+
+    /// Colors of the theme, by key.
+    pub fn colors(&self) -> [(&'static str, Color); 5] {
+        [
+            ("nibble", self.nibble),
+            ("field", self.field),
+            ("error", self.error),
+            ("selection", self.selection),
+            ("diff", self.diff),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test_theme {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut theme = Theme::default();
+        theme.set("name", "monochrome").unwrap();
+        theme.set("selection", "Blue").unwrap();
+        assert_eq!(theme, Theme { selection: Color::Blue, ..Theme::monochrome() });
+        assert_eq!(theme.set("name", "dark"), Err(CommandError::invalid("theme", "dark", "default or monochrome")));
+        assert!(theme.set("error", "pink").is_err());
+        assert_eq!(theme.set("border", "red"), Err(CommandError::UnknownSetting("theme.border".to_string())));
+        assert_eq!(Color::from_name(Color::Magenta.name()), Some(Color::Magenta));
+    }
+}
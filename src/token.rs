@@ -0,0 +1,65 @@
+//! Tokenizer of input literals. Digit groups may be separated by `_`,
+//! spaces, commas, apostrophes or colons, e.g. `1111 0000`, `DE:AD:BE:EF` or
+//! `1'000'000`, in any base: separators are removed here before a base reads
+//! the digits. Trailing `//` and `#` comments of input lines are stripped
+//! here too.
+
+use std::borrow::Cow;
+
+/// Characters separating groups of digits.
+pub const SEPARATORS: &[char] = &['_', ' ', ',', '\'', ':'];
+
+pub fn is_separator(c: char) -> bool {
+    SEPARATORS.contains(&c)
+}
+
+/// Digits of a literal without separators, borrowed if it has none.
+pub fn digits(literal: &str) -> Cow<'_, str> {
+    if literal.contains(SEPARATORS) {
+        Cow::Owned(literal.chars().filter(|c| !is_separator(*c)).collect())
+    } else {
+        Cow::Borrowed(literal)
+    }
+}
+
+/// A line without its trailing `// ...` or `# ...` comment, e.g. of a value
+/// list copied from code. Comment markers inside double quotes are kept.
+pub fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let mut quoted = false;
+    for (i, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'"' => quoted = !quoted,
+            b'#' if !quoted => return line[..i].trim_end(),
+            b'/' if !quoted && bytes.get(i + 1) == Some(&b'/') => return line[..i].trim_end(),
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod test_token {
+    use super::*;
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(digits("1111 0000"), "11110000");
+        assert_eq!(digits("DE:AD:BE:EF"), "DEADBEEF");
+        assert_eq!(digits("1'000'000"), "1000000");
+        assert_eq!(digits("0001_0000"), "00010000");
+        assert_eq!(digits("12,345"), "12345");
+        assert!(matches!(digits("ff"), Cow::Borrowed("ff")));
+        assert_eq!(digits("_"), "");
+    }
+
+    #[test]
+    fn test_strip_comment() {
+        assert_eq!(strip_comment("0x40 // UART0 base"), "0x40");
+        assert_eq!(strip_comment("0x40\t# UART0 base"), "0x40");
+        assert_eq!(strip_comment("# header"), "");
+        assert_eq!(strip_comment("frac(3/4)"), "frac(3/4)");
+        assert_eq!(strip_comment(":prompt \"# \" // shell-like"), ":prompt \"# \"");
+        assert_eq!(strip_comment("ff"), "ff");
+    }
+}
@@ -0,0 +1,83 @@
+//! Session transcript, written with `:log <path>` until `:log off`. Every
+//! input and output line is appended with a UTC timestamp and a marker:
+//! `>` for input, `<` for output and `!` for errors, e.g.
+//! `2026-10-16 09:30:00 > 0xff`.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::date;
+use crate::error::CommandError;
+use crate::render;
+
+/// Marker of input lines.
+pub const INPUT: char = '>';
+/// Marker of output lines.
+pub const OUTPUT: char = '<';
+/// Marker of error lines.
+pub const ERROR: char = '!';
+
+/// Transcript file opened for appending.
+pub struct Transcript {
+    path: PathBuf,
+    file: File,
+}
+
+impl Transcript {
+    /// Open `path` for appending, creating it if needed.
+    pub fn open(path: &str) -> Result<Self, CommandError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| CommandError::Io(format!("cannot open {}: {}", path, e)))?;
+        Ok(Self { path: PathBuf::from(path), file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a text of one or more lines, each with a timestamp and `marker`.
+    /// Write errors are ignored, like those of the output.
+    pub fn write(&mut self, marker: char, text: &str) {
+        let _ = self.file.write_all(format(SystemTime::now(), marker, text).as_bytes());
+    }
+}
+
+/// Format the transcript lines of a text. ANSI color codes are removed.
+pub fn format(time: SystemTime, marker: char, text: &str) -> String {
+    let stamp = timestamp(time);
+    render::strip_colors(text).lines()
+        .map(|line| format!("{} {} {}\n", stamp, marker, line))
+        .collect()
+}
+
+/// Format a time as `YYYY-MM-DD HH:MM:SS`, in UTC.
+fn timestamp(time: SystemTime) -> String {
+    date::format(time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()))
+}
+
+#[cfg(test)]
+mod test_transcript {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(at(0)), "1970-01-01 00:00:00");
+        assert_eq!(timestamp(at(951_782_400)), "2000-02-29 00:00:00");
+        assert_eq!(timestamp(at(1_700_000_000)), "2023-11-14 22:13:20");
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(at(0), INPUT, "ff"), "1970-01-01 00:00:00 > ff\n");
+        assert_eq!(format(at(0), OUTPUT, "\x1b[2m<bin>\x1b[0m 1\nb\n"), "1970-01-01 00:00:00 < <bin> 1\n1970-01-01 00:00:00 < b\n");
+    }
+}
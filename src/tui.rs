@@ -0,0 +1,1131 @@
+//! Full-screen interface, started with `:tui` or `--tui`: an input line, the
+//! last result in every base and the history. Inputs run like at the prompt,
+//! and the output of commands is shown under the panels. While typing, the
+//! result panel previews the value of the input. Tab moves the focus
+//! to the bits of the result, which can be toggled one by one, then to the
+//! history, whose entries can be edited again or loaded as `ans`. Bits,
+//! fields and history entries can also be clicked. F2 pins the result to
+//! compare it with the next ones, bit by bit. Outside the input line, single
+//! keys change the output base and the width, see `shortcut`. Colors come
+//! from the theme of the configuration file, see `theme`. F1 opens a help
+//! of the commands, functions and keys, filtered while typing.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use crossterm::execute;
+use ratatui::layout::{Constraint, Layout, Margin, Position, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use crate::app::App;
+use crate::base::{self, Base};
+use crate::command::Command;
+use crate::conversion::Conversion;
+use crate::error::CommandError;
+use crate::field::Field;
+use crate::render;
+use crate::settings::Settings;
+use crate::theme::{self, Theme};
+
+/// Set while the interface runs, as it cannot be nested with `:tui`.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+const PROMPT: &str = "> ";
+/// Column of the first bit in the result panel, after the `bits` label.
+const BITS_COLUMN: u16 = 7;
+/// Entries moved by PageUp/PageDown in the history.
+const PAGE: usize = 10;
+/// Key bindings of each focus, shown at the bottom.
+const INPUT_KEYS: &str = "F1: help   Enter: run   Up/Down: recall history   Tab: next panel   F2: compare   Esc: leave";
+const BITS_KEYS: &str = "Left/Right: select bit   Space/Enter: toggle   h/d/b/o: base   1/2/4/8: bytes   Tab: next panel   Esc: back to input";
+const COMPARE_KEYS: &str = "Left/Right: select bit   Up/Down: a or b   Space/Enter: toggle   F2: stop comparing";
+const HISTORY_KEYS: &str = "Up/Down: select   Enter: edit   a: load as ans   Tab/Esc: back to input";
+const HELP_KEYS: &str = "Type to search   Up/Down: scroll   Esc/F1: close";
+/// Key bindings, listed in the help.
+const KEY_HELP: &[(&str, &str)] = &[
+    ("F1", "open or close this help"),
+    ("Enter", "run the input line"),
+    ("Up/Down", "recall history entries in the input line"),
+    ("Tab", "move to the bit editor, the history, then back to the input line"),
+    ("Esc", "back to the input line, or leave from it"),
+    ("Ctrl+C, Ctrl+D", "leave"),
+    ("Left/Right", "select a bit in the bit editor"),
+    ("Space/Enter", "toggle the selected bit"),
+    ("F2", "pin the result to compare it with the next ones, or stop comparing"),
+    ("Up/Down", "choose the value to edit when comparing"),
+    ("h/d/b/o", "change the output base, outside the input line"),
+    ("1/2/4/8, 0", "set the width in bytes, or none, outside the input line"),
+    ("a", "load the selected history entry as ans"),
+    ("Mouse", "click a bit to toggle it, a field or a history entry to select it"),
+];
+
+/// Part of the interface receiving the keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Focus {
+    #[default]
+    Input,
+    /// The bits of the result, with the selected bit.
+    Bits,
+    /// The history, with the selected entry.
+    History,
+}
+
+/// State of the interface, besides that of the App.
+#[derive(Default)]
+pub struct Tui {
+    focus: Focus,
+    /// Bit selected in the bit editor, 0 for the least significant.
+    bit: u32,
+    /// History entry selected in the history panel.
+    selected: usize,
+    /// Search of the help, shown while set.
+    help: Option<String>,
+    /// Lines scrolled in the help.
+    help_scroll: u16,
+    /// Value compared with the result, pinned with F2.
+    pinned: Option<Conversion>,
+    /// The bit editor edits the pinned value rather than the result.
+    on_pinned: bool,
+    input: String,
+    /// Cursor position in `input`, in chars.
+    cursor: usize,
+    /// History entry recalled with Up/Down.
+    recall: Option<usize>,
+    /// Value of the input being typed, or why it has none.
+    preview: Option<Result<Conversion, String>>,
+    /// Output and errors of the last input.
+    message: String,
+    /// The last input failed.
+    error: bool,
+    /// Set by Esc, Ctrl+C or `:q`.
+    done: bool,
+}
+
+/// Run the interface until it is left. The terminal is restored afterwards.
+pub fn run(app: &mut App) -> Result<(), CommandError> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(CommandError::TuiRunning);
+    }
+    let ret = ratatui::try_init().and_then(|mut terminal| {
+        let ret = execute!(io::stdout(), EnableMouseCapture).and_then(|_| Tui::default().event_loop(&mut terminal, app));
+        let restored = execute!(io::stdout(), DisableMouseCapture).and(ratatui::try_restore());
+        restored.and(ret)
+    });
+    RUNNING.store(false, Ordering::SeqCst);
+    ret.map_err(|e| CommandError::Io(format!("cannot run the TUI: {}", e)))
+}
+
+impl Tui {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+        while !self.done {
+            terminal.draw(|frame| self.draw(frame, app))?;
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => self.key(key, app),
+                Event::Mouse(mouse) => {
+                    let size = terminal.size()?;
+                    self.mouse(mouse, Rect::new(0, 0, size.width, size.height), app);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a key press.
+    pub fn key(&mut self, key: KeyEvent, app: &mut App) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && matches!(key.code, KeyCode::Char('c') | KeyCode::Char('d')) {
+            self.done = true;
+        } else if self.help.is_some() {
+            self.help_key(key);
+        } else if key.code == KeyCode::F(1) {
+            self.help = Some(String::new());
+            self.help_scroll = 0;
+        } else if key.code == KeyCode::F(2) {
+            self.pin(app);
+        } else {
+            match self.focus {
+                Focus::Input => self.input_key(key, app),
+                Focus::Bits => self.bits_key(key, app),
+                Focus::History => self.history_key(key, app),
+            }
+            self.preview = preview(app, &self.input);
+        }
+    }
+
+    /// Handle a mouse event, on a screen of size `area`: a click on a bit
+    /// toggles it, on a field selects it in the bit editor and on a history
+    /// entry selects it.
+    pub fn mouse(&mut self, mouse: MouseEvent, area: Rect, app: &mut App) {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+        let areas = areas(area, app);
+        let click = Position::new(mouse.column, mouse.row);
+        let previewing = self.focus == Focus::Input && self.preview.is_some();
+        if areas.input.contains(click) {
+            self.focus = Focus::Input;
+        } else if let (false, Some(conversion)) = (previewing, app.last()) {
+            let inner = areas.result.inner(Margin::new(1, 1));
+            let fields = areas.fields.inner(Margin::new(1, 1));
+            let selected = if self.focus == Focus::Bits { Some(self.bit) } else { None };
+            // Rows of the bits, of the pinned value too when comparing.
+            let row = click.y.wrapping_sub(inner.y);
+            let (bits, on_pinned) = match &self.pinned {
+                Some(pinned) => {
+                    let on_pinned = match row {
+                        0 => Some(false),
+                        1 => Some(true),
+                        _ => None,
+                    };
+                    (compare_bits(conversion, pinned, selected, app.settings()), on_pinned)
+                }
+                None => {
+                    let on_pinned = if row == bits_row(conversion) { Some(false) } else { None };
+                    (shown_bits(conversion.value, conversion.width, selected, app.settings()), on_pinned)
+                }
+            };
+            if let Some(on_pinned) = on_pinned.filter(|_| inner.contains(click)) {
+                if let Some(bit) = bit_at(bits, (click.x - inner.x).wrapping_sub(BITS_COLUMN)) {
+                    self.focus = Focus::Bits;
+                    self.on_pinned = on_pinned;
+                    self.bit = bit;
+                    self.toggle(app);
+                }
+            } else if fields.contains(click) && (1..=3).contains(&(click.y - fields.y)) {
+                if let Some(field) = field_at(app.fields(), conversion.value, click.x - fields.x) {
+                    self.focus = Focus::Bits;
+                    self.bit = field.lsb;
+                }
+            }
+        }
+        let history = areas.history.inner(Margin::new(1, 1));
+        if history.contains(click) {
+            let index = self.history_start(app, history.height) + (click.y - history.y) as usize;
+            if index < app.history().len() {
+                self.focus = Focus::History;
+                self.selected = index;
+            }
+        }
+        self.preview = preview(app, &self.input);
+    }
+
+    /// Index of the first history entry shown in `height` rows: the most
+    /// recent entries fit, scrolled up to the selected entry.
+    fn history_start(&self, app: &App, height: u16) -> usize {
+        let start = app.history().len().saturating_sub(height as usize);
+        match self.selected_entry() {
+            Some(selected) => start.min(selected),
+            None => start,
+        }
+    }
+
+    /// History entry highlighted: the selected one, or the one recalled in
+    /// the input line.
+    fn selected_entry(&self) -> Option<usize> {
+        if self.focus == Focus::History { Some(self.selected) } else { self.recall }
+    }
+
+    /// Move the focus to the next panel that has something to show.
+    fn next_focus(&mut self, app: &App) {
+        let entries = app.history().len();
+        self.focus = match self.focus {
+            Focus::Input if app.last().is_some() => Focus::Bits,
+            Focus::Input | Focus::Bits if entries > 0 => {
+                self.selected = entries - 1;
+                Focus::History
+            }
+            _ => Focus::Input,
+        };
+    }
+
+    /// Handle a key of the help: typed chars search it.
+    fn help_key(&mut self, key: KeyEvent) {
+        let search = match self.help.as_mut() {
+            Some(search) => search,
+            None => return,
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::F(1) => self.help = None,
+            KeyCode::Char(c) => {
+                search.push(c);
+                self.help_scroll = 0;
+            }
+            KeyCode::Backspace => {
+                search.pop();
+                self.help_scroll = 0;
+            }
+            KeyCode::Up => self.help_scroll = self.help_scroll.saturating_sub(1),
+            KeyCode::Down => self.help_scroll = self.help_scroll.saturating_add(1),
+            KeyCode::PageUp => self.help_scroll = self.help_scroll.saturating_sub(PAGE as u16),
+            KeyCode::PageDown => self.help_scroll = self.help_scroll.saturating_add(PAGE as u16),
+            _ => {}
+        }
+    }
+
+    /// Pin the result to compare it with the next ones, or stop comparing.
+    fn pin(&mut self, app: &App) {
+        self.pinned = match self.pinned {
+            None => app.last().cloned(),
+            Some(_) => None,
+        };
+        self.on_pinned = false;
+    }
+
+    /// Value edited by the bit editor.
+    fn edited<'a>(&'a self, app: &'a App) -> Option<&'a Conversion> {
+        if self.on_pinned { self.pinned.as_ref() } else { app.last() }
+    }
+
+    /// Handle a key of the bit editor.
+    fn bits_key(&mut self, key: KeyEvent, app: &mut App) {
+        let bits = self.edited(app).map_or(0, |conversion| max_bits(conversion.width, app.settings()));
+        match key.code {
+            KeyCode::Up | KeyCode::Down if self.pinned.is_some() => self.on_pinned = key.code == KeyCode::Down,
+            KeyCode::Tab => self.next_focus(app),
+            KeyCode::Esc => self.focus = Focus::Input,
+            KeyCode::Left => self.bit = (self.bit + 1).min(bits.saturating_sub(1)),
+            KeyCode::Right => self.bit = self.bit.saturating_sub(1),
+            KeyCode::Char(' ') | KeyCode::Enter => self.toggle(app),
+            KeyCode::Char(c) => {
+                self.shortcut(c, app);
+                let bits = self.edited(app).map_or(0, |conversion| max_bits(conversion.width, app.settings()));
+                self.bit = self.bit.min(bits.saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle a key of the history panel.
+    fn history_key(&mut self, key: KeyEvent, app: &mut App) {
+        let last = app.history().len().saturating_sub(1);
+        match key.code {
+            KeyCode::Tab | KeyCode::Esc => self.focus = Focus::Input,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(PAGE),
+            KeyCode::PageDown => self.selected = (self.selected + PAGE).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Enter => {
+                self.recall_entry(app, Some(self.selected));
+                self.focus = Focus::Input;
+            }
+            KeyCode::Char('a') => {
+                let conversion = app.history().get(self.selected).and_then(|entry| entry.conversion.clone());
+                match conversion {
+                    Some(conversion) => {
+                        self.message = format!("ans = {}", conversion.input);
+                        self.error = false;
+                        app.set_last(conversion);
+                        self.focus = Focus::Input;
+                    }
+                    None => {
+                        self.message = format!("Error: entry {} has no result", self.selected + 1);
+                        self.error = true;
+                    }
+                }
+            }
+            KeyCode::Char(c) => self.shortcut(c, app),
+            _ => {}
+        }
+    }
+
+    /// Run the setting of a single key: `h`, `d`, `b` or `o` for the output
+    /// base, and `1`, `2`, `4` or `8` for a width of as many bytes, `0` for
+    /// none. Like commands, the change can be undone with `:undo`.
+    fn shortcut(&mut self, key: char, app: &mut App) {
+        let (setting, value) = match key {
+            'h' => ("to", "hex"),
+            'd' => ("to", "dec"),
+            'b' => ("to", "bin"),
+            'o' => ("to", "oct"),
+            '0' => ("width", "0"),
+            '1' => ("width", "8"),
+            '2' => ("width", "16"),
+            '4' => ("width", "32"),
+            '8' => ("width", "64"),
+            _ => return,
+        };
+        let (ret, _, _) = app.capture(|app| app.run(Command::Set(vec![(setting.to_string(), value.to_string())])));
+        self.error = ret.is_err();
+        self.message = match ret {
+            Ok(()) => format!(":{} {}", setting, value),
+            Err(e) => format!("Error: {}", e),
+        };
+    }
+
+    /// Toggle the selected bit of the edited value.
+    fn toggle(&mut self, app: &mut App) {
+        let edited = match self.edited(app) {
+            Some(conversion) => toggled(conversion, self.bit, app.settings()),
+            None => return,
+        };
+        if self.on_pinned {
+            self.pinned = Some(edited);
+        } else {
+            app.set_last(edited);
+        }
+    }
+
+    /// Handle a key of the input line.
+    fn input_key(&mut self, key: KeyEvent, app: &mut App) {
+        let len = self.input.chars().count();
+        match key.code {
+            KeyCode::Esc => self.done = true,
+            KeyCode::Tab => self.next_focus(app),
+            KeyCode::Enter => self.submit(app),
+            KeyCode::Char(c) => {
+                let i = self.byte_index();
+                self.input.insert(i, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let i = self.byte_index();
+                self.input.remove(i);
+            }
+            KeyCode::Delete if self.cursor < len => {
+                let i = self.byte_index();
+                self.input.remove(i);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = len,
+            KeyCode::Up => {
+                let last = app.history().len().checked_sub(1);
+                self.recall_entry(app, self.recall.map_or(last, |i| Some(i.saturating_sub(1))));
+            }
+            KeyCode::Down => {
+                let next = self.recall.map(|i| i + 1).filter(|i| *i < app.history().len());
+                self.recall_entry(app, next);
+            }
+            _ => {}
+        }
+    }
+
+    /// Put a history entry in the input line, or clear it with `None`.
+    fn recall_entry(&mut self, app: &App, index: Option<usize>) {
+        self.recall = index;
+        self.input = index.and_then(|i| app.history().get(i)).map(|entry| entry.input.clone()).unwrap_or_default();
+        self.cursor = self.input.chars().count();
+    }
+
+    /// Run the input line.
+    fn submit(&mut self, app: &mut App) {
+        let input = std::mem::take(&mut self.input);
+        self.cursor = 0;
+        self.recall = None;
+        let (code, out, err) = app.capture(|app| app.handle(&input));
+        self.error = code != 0;
+        self.message = render::strip_colors(&format!("{}{}", out, err)).trim_end().to_string();
+        if app.is_done() {
+            self.done = true;
+        }
+    }
+
+    /// Byte index of the cursor in the input.
+    fn byte_index(&self) -> usize {
+        self.input.char_indices().nth(self.cursor).map_or(self.input.len(), |(i, _)| i)
+    }
+
+    /// Draw the whole screen.
+    pub fn draw(&self, frame: &mut Frame, app: &App) {
+        let Areas { input: input_area, result: result_area, history: history_area, fields: fields_area, message: message_area, keys: keys_area } =
+            areas(frame.area(), app);
+
+        let input = Paragraph::new(format!("{}{}", PROMPT, self.input)).block(Block::bordered().title(" Input "));
+        frame.render_widget(input, input_area);
+        frame.set_cursor_position((input_area.x + 1 + (PROMPT.len() + self.cursor) as u16, input_area.y + 1));
+
+        let styles = Styles::new(app.theme());
+        let preview = if self.focus == Focus::Input { self.preview.as_ref() } else { None };
+        let bit = if self.focus == Focus::Bits { Some(self.bit) } else { None };
+        let shown = match preview {
+            Some(preview) => preview.as_ref().ok(),
+            None => app.last(),
+        };
+        if let Some(conversion) = shown.filter(|_| !app.fields().is_empty()) {
+            let fields = Paragraph::new(field_lines(app.fields(), conversion.value, &styles, bit)).block(Block::bordered().title(" Fields "));
+            frame.render_widget(fields, fields_area);
+        }
+        let result = match preview {
+            Some(Ok(conversion)) => Paragraph::new(result_lines(Some(conversion), app.settings(), &styles, None)).block(Block::bordered().title(" Preview ")),
+            Some(Err(e)) => Paragraph::new(e.as_str()).style(styles.error).block(Block::bordered().title(" Preview ")),
+            None => match (&self.pinned, app.last()) {
+                (Some(pinned), Some(last)) => {
+                    let selected = bit.map(|bit| (self.on_pinned, bit));
+                    Paragraph::new(compare_lines(last, pinned, app.settings(), &styles, selected)).block(Block::bordered().title(" Compare "))
+                }
+                _ => Paragraph::new(result_lines(app.last(), app.settings(), &styles, bit)).block(Block::bordered().title(" Result ")),
+            },
+        };
+        frame.render_widget(result, result_area);
+
+        let entries = app.history();
+        let selected = self.selected_entry();
+        let start = self.history_start(app, history_area.height.saturating_sub(2));
+        let lines: Vec<Line> = entries.iter()
+            .enumerate()
+            .skip(start)
+            .map(|(i, entry)| {
+                let mut spans = vec![Span::raw(format!("{:4}  {}", i + 1, entry.input))];
+                if let Some(conversion) = &entry.conversion {
+                    let text = conversion.repr(&app.settings().out_base, app.settings());
+                    spans.push(Span::styled(format!("  {}", text), Style::default().add_modifier(Modifier::DIM)));
+                }
+                let line = Line::from(spans);
+                if selected == Some(i) {
+                    line.style(styles.selection)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" History ")), history_area);
+
+        let style = if self.error { styles.error } else { Style::default() };
+        frame.render_widget(Paragraph::new(self.message.as_str()).style(style).wrap(Wrap { trim: false }), message_area);
+        let keys = match self.focus {
+            _ if self.help.is_some() => HELP_KEYS,
+            Focus::Input => INPUT_KEYS,
+            Focus::Bits if self.pinned.is_some() => COMPARE_KEYS,
+            Focus::Bits => BITS_KEYS,
+            Focus::History => HISTORY_KEYS,
+        };
+        frame.render_widget(Paragraph::new(keys).style(Style::default().add_modifier(Modifier::DIM)), keys_area);
+
+        if let Some(search) = &self.help {
+            let area = frame.area().inner(Margin::new(2, 1));
+            let help = Paragraph::new(help_lines(app, search))
+                .scroll((self.help_scroll, 0))
+                .block(Block::bordered().title(format!(" Help: {}_ ", search)));
+            frame.render_widget(Clear, area);
+            frame.render_widget(help, area);
+        }
+    }
+}
+
+/// Lines of the help: the entries of `:help` and the key bindings, by
+/// section, that contain `search` in their usage or description.
+fn help_lines(app: &App, search: &str) -> Vec<Line<'static>> {
+    let search = search.to_lowercase();
+    let keys = KEY_HELP.iter().map(|(key, description)| ("Keys", key.to_string(), description.to_string()));
+    let mut lines = Vec::new();
+    let mut section = "";
+    for (entry_section, usage, description) in app.help_entries().into_iter().chain(keys) {
+        if !usage.to_lowercase().contains(&search) && !description.to_lowercase().contains(&search) {
+            continue;
+        }
+        if entry_section != section {
+            section = entry_section;
+            lines.push(Line::styled(format!("{}:", section), Style::default().add_modifier(Modifier::BOLD)));
+        }
+        lines.push(Line::from(format!("  {:27} {}", usage, description)));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(format!("Nothing matches {}", search)));
+    }
+    lines
+}
+
+/// Styles of the theme. Without their color, errors and the bits that
+/// differ are bold and the selection is reversed.
+struct Styles {
+    /// Every other group of 4 bits, the second one from the right first.
+    nibble: Style,
+    field: Style,
+    error: Style,
+    selection: Style,
+    diff: Style,
+}
+
+impl Styles {
+    fn new(theme: &Theme) -> Self {
+        let fg = |color| color_of(color).map_or(Style::default(), |color| Style::default().fg(color));
+        Styles {
+            nibble: fg(theme.nibble),
+            field: fg(theme.field),
+            error: color_of(theme.error).map_or(Style::default().add_modifier(Modifier::BOLD), |color| Style::default().fg(color)),
+            selection: color_of(theme.selection).map_or(Style::default().add_modifier(Modifier::REVERSED), |color| Style::default().bg(color)),
+            diff: fg(theme.diff).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Style of a bit, by its group of 4 bits.
+    fn nibble(&self, bit: u32) -> Style {
+        if bit / 4 % 2 == 1 { self.nibble } else { Style::default() }
+    }
+}
+
+/// Terminal color of a theme color.
+fn color_of(color: theme::Color) -> Option<Color> {
+    match color {
+        theme::Color::None => None,
+        theme::Color::Black => Some(Color::Black),
+        theme::Color::Red => Some(Color::Red),
+        theme::Color::Green => Some(Color::Green),
+        theme::Color::Yellow => Some(Color::Yellow),
+        theme::Color::Blue => Some(Color::Blue),
+        theme::Color::Magenta => Some(Color::Magenta),
+        theme::Color::Cyan => Some(Color::Cyan),
+        theme::Color::Gray => Some(Color::Gray),
+        theme::Color::White => Some(Color::White),
+    }
+}
+
+/// Areas of the screen.
+struct Areas {
+    input: Rect,
+    result: Rect,
+    history: Rect,
+    /// Empty without fields.
+    fields: Rect,
+    message: Rect,
+    keys: Rect,
+}
+
+/// Split the screen into its areas.
+fn areas(area: Rect, app: &App) -> Areas {
+    let [input, main, message, keys] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(6),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ]).areas(area);
+    let fields_height = if app.fields().is_empty() { 0 } else { 7 };
+    let [main, fields] = Layout::vertical([Constraint::Min(6), Constraint::Length(fields_height)]).areas(main);
+    let [result, history] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+    Areas { input, result, history, fields, message, keys }
+}
+
+/// Value of an input being typed, `None` for commands and empty inputs.
+/// Unfinished inputs are completed first if they do not convert as they are,
+/// see `complete`.
+fn preview(app: &App, input: &str) -> Option<Result<Conversion, String>> {
+    let input = input.trim();
+    if input.is_empty() || app.is_command(input) || input.starts_with('!') {
+        return None;
+    }
+    Some(app.convert(input).or_else(|e| {
+        let completed = complete(input);
+        app.convert(&completed)
+            .map(|conversion| Conversion { input: input.to_string(), ..conversion })
+            .map_err(|_| e.to_string())
+    }))
+}
+
+/// Complete a partially typed input: a trailing digit separator is dropped,
+/// a radix prefix without digits reads as 0 and open calls are closed, e.g.
+/// `htons(0x` becomes `htons(0x0)`.
+fn complete(input: &str) -> String {
+    let mut ret = input.trim_end_matches(['_', ' ', ',']).to_string();
+    let lower = ret.to_ascii_lowercase();
+    if ["0x", "0b", "0o"].iter().any(|prefix| lower.ends_with(prefix)) {
+        ret.push('0');
+    }
+    let open = ret.matches('(').count().saturating_sub(ret.matches(')').count());
+    ret + &")".repeat(open)
+}
+
+/// Lines of the result panel: a conversion in every base, and its bits with
+/// the `selected` bit highlighted.
+fn result_lines(conversion: Option<&Conversion>, settings: &Settings, styles: &Styles, selected: Option<u32>) -> Vec<Line<'static>> {
+    let conversion = match conversion {
+        Some(conversion) => conversion,
+        None => return vec![Line::from("No result yet")],
+    };
+    let mut lines = vec![Line::from(format!("{:6} {}", "input", conversion.input))];
+    // The output base stands out.
+    for name in base::names() {
+        if let Some(base) = Base::from_name(&name) {
+            let line = Line::from(format!("{:6} {}", name, conversion.repr(&base, settings)));
+            lines.push(if base == settings.out_base { line.style(Style::default().add_modifier(Modifier::BOLD)) } else { line });
+        }
+    }
+    if let Some(width) = conversion.width {
+        lines.push(Line::from(format!("{:6} u{}", "width", width)));
+    }
+    let bits = shown_bits(conversion.value, conversion.width, selected, settings);
+    lines.push(bits_line("bits", conversion.value, bits, |bit| {
+        if selected == Some(bit) { styles.selection } else { styles.nibble(bit) }
+    }));
+    if let Some(bit) = selected {
+        lines.push(Line::from(format!("{:6} {} = {}", "", bit, conversion.value >> bit & 1)));
+    }
+    lines
+}
+
+/// Lines of the compare panel: the bits of the result `a`, of the pinned
+/// value `b` and of their XOR, with the differing bits highlighted. The
+/// `selected` bit is given with whether it is in `b`.
+fn compare_lines(a: &Conversion, b: &Conversion, settings: &Settings, styles: &Styles, selected: Option<(bool, u32)>) -> Vec<Line<'static>> {
+    let bits = compare_bits(a, b, selected.map(|(_, bit)| bit), settings);
+    let diff = a.value ^ b.value;
+    let row = |label: &str, value: u64, on_pinned: bool| {
+        let mut line = bits_line(label, value, bits, |bit| {
+            if selected == Some((on_pinned, bit)) {
+                styles.selection
+            } else if diff >> bit & 1 == 1 {
+                styles.diff
+            } else {
+                styles.nibble(bit)
+            }
+        });
+        line.push_span(Span::raw(format!("  {:#x}", value)));
+        line
+    };
+    let mut xor = bits_line("xor", diff, bits, |bit| styles.nibble(bit));
+    xor.push_span(Span::raw(format!("  {:#x}", diff)));
+    vec![
+        row("a", a.value, false),
+        row("b", b.value, true),
+        xor,
+        Line::from(format!("{:6} {} bit(s) differ", "", diff.count_ones())),
+        Line::from(format!("{:6} {}", "a", a.input)),
+        Line::from(format!("{:6} {}", "b", b.input)),
+    ]
+}
+
+/// Number of bits shown when comparing, enough for both values.
+fn compare_bits(a: &Conversion, b: &Conversion, selected: Option<u32>, settings: &Settings) -> u32 {
+    shown_bits(a.value, a.width, selected, settings).max(shown_bits(b.value, b.width, selected, settings))
+}
+
+/// Line of the `bits` lowest bits of a value, grouped by nibbles, after a
+/// label. Each bit is drawn with the style given by `style`.
+fn bits_line(label: &str, value: u64, bits: u32, style: impl Fn(u32) -> Style) -> Line<'static> {
+    let mut spans = vec![Span::raw(format!("{:1$}", label, BITS_COLUMN as usize))];
+    for bit in (0..bits).rev() {
+        let digit = if value >> bit & 1 == 1 { "1" } else { "0" };
+        spans.push(Span::styled(digit, style(bit)));
+        if bit % 4 == 0 && bit > 0 {
+            spans.push(Span::raw(" "));
+        }
+    }
+    Line::from(spans)
+}
+
+/// Conversion of a value with a bit toggled.
+fn toggled(conversion: &Conversion, bit: u32, settings: &Settings) -> Conversion {
+    let value = conversion.value ^ 1 << bit;
+    Conversion {
+        input: format!("{:#x}", value),
+        base: Base::Hex,
+        value,
+        width: conversion.width,
+        text: Conversion::format(value, conversion.width, &settings.out_base, settings),
+    }
+}
+
+/// Row of the bits in the result panel, after the input, the bases and the
+/// width.
+fn bits_row(conversion: &Conversion) -> u16 {
+    let bases = base::names().iter().filter(|name| Base::from_name(name).is_some()).count();
+    (1 + bases + conversion.width.map_or(0, |_| 1)) as u16
+}
+
+/// Bit drawn at `column` of a row of `bits` bits, grouped by nibbles.
+fn bit_at(bits: u32, column: u16) -> Option<u32> {
+    let mut x = 0;
+    for bit in (0..bits).rev() {
+        if x == column {
+            return Some(bit);
+        }
+        x += if bit % 4 == 0 { 2 } else { 1 };
+    }
+    None
+}
+
+/// Field drawn at `column` of the field diagram.
+fn field_at(fields: &[Field], value: u64, column: u16) -> Option<&Field> {
+    let mut x = 1;
+    for field in fields {
+        let width = field_cells(field, value).iter().map(String::len).max().unwrap_or(0) as u16 + 2;
+        if (x..x + width).contains(&column) {
+            return Some(field);
+        }
+        x += width + 1;
+    }
+    None
+}
+
+/// Rows of the box of a field: its name, bit range and value.
+fn field_cells(field: &Field, value: u64) -> [String; 3] {
+    [field.name.clone(), field.range(), field.format(value)]
+}
+
+/// Diagram of the register fields of a value: a box per field with its
+/// name, bit range and value. The field of the `selected` bit is highlighted.
+fn field_lines(fields: &[Field], value: u64, styles: &Styles, selected: Option<u32>) -> Vec<Line<'static>> {
+    let cells: Vec<[String; 3]> = fields.iter().map(|field| field_cells(field, value)).collect();
+    let widths: Vec<usize> = cells.iter().map(|cell| cell.iter().map(|text| text.len()).max().unwrap_or(0) + 2).collect();
+    let border = |left: &str, middle: &str, right: &str| {
+        let lines: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+        Line::styled(format!("{}{}{}", left, lines.join(middle), right), styles.field)
+    };
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for row in 0..3 {
+        let mut spans = vec![Span::styled("│", styles.field)];
+        for ((field, cell), width) in fields.iter().zip(&cells).zip(&widths) {
+            let style = if selected.is_some_and(|bit| field.contains(bit)) {
+                styles.selection
+            } else if row == 0 {
+                styles.field
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(format!("{:^width$}", cell[row], width = width), style));
+            spans.push(Span::styled("│", styles.field));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(border("└", "┴", "┘"));
+    lines
+}
+
+/// Number of bits that can be edited: the width of the setting or of the
+/// function that produced the value, else 64.
+fn max_bits(width: Option<u32>, settings: &Settings) -> u32 {
+    match (settings.width, width) {
+        (0, Some(width)) => width,
+        (0, None) => 64,
+        (width, _) => width,
+    }
+}
+
+/// Number of bits shown: enough for the value and the selected bit, in whole
+/// bytes, and at least the width.
+fn shown_bits(value: u64, width: Option<u32>, selected: Option<u32>, settings: &Settings) -> u32 {
+    let needed = (64 - value.leading_zeros()).max(selected.map_or(0, |bit| bit + 1));
+    let shown = (needed.div_ceil(8) * 8).max(8).max(width.unwrap_or(0)).max(settings.width);
+    shown.min(max_bits(width, settings).max(8))
+}
+
+#[cfg(test)]
+mod test_tui {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    /// Size of the test screen.
+    const SCREEN: Rect = Rect { x: 0, y: 0, width: 60, height: 24 };
+
+    fn app() -> App {
+        App::with_output(Box::new(io::sink()), Box::new(io::sink()))
+    }
+
+    fn press(tui: &mut Tui, app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            let code = if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) };
+            tui.key(KeyEvent::from(code), app);
+        }
+    }
+
+    /// Draw the interface and return the screen, one line per row.
+    fn screen(tui: &Tui, app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(SCREEN.width, SCREEN.height)).unwrap();
+        terminal.draw(|frame| tui.draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>().trim_end().to_string() + "\n")
+            .collect()
+    }
+
+    /// Position of the first place where `text` is drawn, as (column, row).
+    fn position(tui: &Tui, app: &App, text: &str) -> (u16, u16) {
+        let screen = screen(tui, app);
+        let (row, line) = screen.lines().enumerate().find(|(_, line)| line.contains(text)).unwrap();
+        (line[..line.find(text).unwrap()].chars().count() as u16, row as u16)
+    }
+
+    /// Click on the screen where `text` is drawn, `offset` chars after it.
+    fn click(tui: &mut Tui, app: &mut App, text: &str, offset: u16) {
+        let (column, row) = position(tui, app, text);
+        let mouse = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: column + offset,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        tui.mouse(mouse, SCREEN, app);
+    }
+
+    #[test]
+    fn test_input() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, "ff\n");
+        assert_eq!(app.last().unwrap().value, 255);
+        assert_eq!(tui.message, "<bin> 1111_1111");
+        press(&mut tui, &mut app, "1z");
+        tui.key(KeyEvent::from(KeyCode::Left), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Backspace), &mut app);
+        press(&mut tui, &mut app, "2");
+        assert_eq!((tui.input.as_str(), tui.cursor), ("2z", 1));
+        press(&mut tui, &mut app, "\n");
+        assert!(tui.error);
+        assert_eq!(tui.message, "Error: invalid hex digit in 2z");
+
+        tui.key(KeyEvent::from(KeyCode::Up), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Up), &mut app);
+        assert_eq!((tui.input.as_str(), tui.recall), ("ff", Some(0)));
+        tui.key(KeyEvent::from(KeyCode::Down), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Down), &mut app);
+        assert_eq!((tui.input.as_str(), tui.recall), ("", None));
+
+        press(&mut tui, &mut app, ":q\n");
+        assert!(tui.done);
+    }
+
+    #[test]
+    fn test_draw() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        assert!(screen(&tui, &app).contains("│No result yet"));
+        press(&mut tui, &mut app, "htons(1)\n:to dec\n1");
+        let preview = screen(&tui, &app);
+        assert!(preview.contains("│> 1"));
+        assert!(preview.contains("│dec    1 "));
+        tui.key(KeyEvent::from(KeyCode::Backspace), &mut app);
+        let screen = screen(&tui, &app);
+        assert!(screen.contains("│hex    0x0100"));
+        assert!(screen.contains("│oct    0o000400"));
+        assert!(screen.contains("│width  u16"));
+        assert!(screen.contains("│   2  :to dec"));
+        assert!(screen.contains("│bits   0000 0001 0000 0000"));
+        assert!(screen.contains("Enter: run"));
+    }
+
+    #[test]
+    fn test_bits() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        assert_eq!(tui.focus, Focus::Input);
+        press(&mut tui, &mut app, "f0\n");
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        assert_eq!(tui.focus, Focus::Bits);
+        press(&mut tui, &mut app, " ");
+        for _ in 0..9 {
+            tui.key(KeyEvent::from(KeyCode::Left), &mut app);
+        }
+        tui.key(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(app.last().unwrap().value, 0x2f1);
+        assert_eq!(app.last().unwrap().text, "0010_1111_0001");
+        let screen = screen(&tui, &app);
+        assert!(screen.contains("│bits   0000 0010 1111 0001"));
+        assert!(screen.contains("│       9 = 1"));
+        assert!(screen.contains("Space/Enter: toggle"));
+
+        tui.key(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_eq!((tui.focus, tui.done), (Focus::Input, false));
+    }
+
+    #[test]
+    fn test_history() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, "ff\n1\n:to dec\n");
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        assert_eq!((tui.focus, tui.selected), (Focus::History, 2));
+        assert!(screen(&tui, &app).contains("a: load as ans"));
+        tui.key(KeyEvent::from(KeyCode::Char('a')), &mut app);
+        assert_eq!((tui.focus, tui.error, tui.message.as_str()), (Focus::History, true, "Error: entry 3 has no result"));
+        tui.key(KeyEvent::from(KeyCode::Home), &mut app);
+        assert!(screen(&tui, &app).contains("│   1  ff  255"));
+        tui.key(KeyEvent::from(KeyCode::Char('a')), &mut app);
+        assert_eq!((tui.focus, tui.message.as_str()), (Focus::Input, "ans = ff"));
+        press(&mut tui, &mut app, "htons(ans)\n");
+        assert_eq!(app.last().unwrap().value, 0xff00);
+
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Up), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Up), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!((tui.focus, tui.input.as_str(), tui.recall), (Focus::Input, "1", Some(1)));
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        assert_eq!(tui.focus, Focus::Input);
+    }
+
+    #[test]
+    fn test_fields() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, ":deffield mode[7:4] en[3] div[2:0]\n");
+        assert!(!screen(&tui, &app).contains("Fields"));
+        press(&mut tui, &mut app, "3a");
+        let diagram = screen(&tui, &app);
+        assert!(diagram.contains("││ mode │ en │ div │"));
+        assert!(diagram.contains("││ 7:4  │ 3  │ 2:0 │"));
+        assert!(diagram.contains("││ 0x3  │ 1  │ 0x2 │"));
+
+        press(&mut tui, &mut app, "\n");
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        for _ in 0..3 {
+            tui.key(KeyEvent::from(KeyCode::Left), &mut app);
+        }
+        tui.key(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert!(screen(&tui, &app).contains("││ 0x3  │ 0  │ 0x2 │"));
+    }
+
+    #[test]
+    fn test_mouse() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, "f0\n:deffield hi[7:4] lo[3:0]\n");
+        // The last bit, then the first one.
+        click(&mut tui, &mut app, "bits   ", 7 + 8);
+        assert_eq!((tui.focus, tui.bit, app.last().unwrap().value), (Focus::Bits, 0, 0xf1));
+        click(&mut tui, &mut app, "bits   ", 7);
+        assert_eq!((tui.bit, app.last().unwrap().value), (7, 0x71));
+        click(&mut tui, &mut app, "bits   ", 7 + 4);
+        assert_eq!(app.last().unwrap().value, 0x71);
+
+        click(&mut tui, &mut app, "│ lo ", 2);
+        assert_eq!((tui.focus, tui.bit), (Focus::Bits, 0));
+        click(&mut tui, &mut app, "   1  f0", 0);
+        assert_eq!((tui.focus, tui.selected), (Focus::History, 0));
+        click(&mut tui, &mut app, "> ", 0);
+        assert_eq!(tui.focus, Focus::Input);
+    }
+
+    #[test]
+    fn test_compare() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        tui.key(KeyEvent::from(KeyCode::F(2)), &mut app);
+        assert!(tui.pinned.is_none());
+        press(&mut tui, &mut app, "f0\n");
+        tui.key(KeyEvent::from(KeyCode::F(2)), &mut app);
+        press(&mut tui, &mut app, "3c\n");
+        let compare = screen(&tui, &app);
+        assert!(compare.contains("│a      0011 1100  0x3c"));
+        assert!(compare.contains("│b      1111 0000  0xf0"));
+        assert!(compare.contains("│xor    1100 1100  0xcc"));
+        assert!(compare.contains("│       4 bit(s) differ"));
+
+        // Toggle bit 2 of b, then bit 0 of a.
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Down), &mut app);
+        press(&mut tui, &mut app, "  ");
+        for _ in 0..2 {
+            tui.key(KeyEvent::from(KeyCode::Left), &mut app);
+        }
+        press(&mut tui, &mut app, " ");
+        tui.key(KeyEvent::from(KeyCode::Up), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Right), &mut app);
+        tui.key(KeyEvent::from(KeyCode::Right), &mut app);
+        press(&mut tui, &mut app, " ");
+        assert_eq!((app.last().unwrap().value, tui.pinned.as_ref().unwrap().value), (0x3d, 0xf4));
+        assert!(screen(&tui, &app).contains("Up/Down: a or b"));
+        click(&mut tui, &mut app, "│b      ", 8 + 8);
+        assert_eq!((tui.on_pinned, tui.pinned.as_ref().unwrap().value), (true, 0xf5));
+
+        tui.key(KeyEvent::from(KeyCode::F(2)), &mut app);
+        assert!(tui.pinned.is_none());
+        assert!(screen(&tui, &app).contains("Result"));
+    }
+
+    #[test]
+    fn test_shortcuts() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, "1ff\n");
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        press(&mut tui, &mut app, "h2");
+        assert_eq!((app.settings().out_base.clone(), app.settings().width), (Base::Hex, 16));
+        assert_eq!(tui.message, ":width 16");
+        for _ in 0..20 {
+            tui.key(KeyEvent::from(KeyCode::Left), &mut app);
+        }
+        press(&mut tui, &mut app, "1");
+        assert_eq!((tui.bit, tui.error, tui.message.as_str()), (7, false, ":width 8"));
+        press(&mut tui, &mut app, "x");
+        assert_eq!(tui.message, ":width 8");
+
+        tui.key(KeyEvent::from(KeyCode::Tab), &mut app);
+        press(&mut tui, &mut app, "d0");
+        assert!(screen(&tui, &app).contains("│   1  1ff  511"));
+        tui.key(KeyEvent::from(KeyCode::Esc), &mut app);
+        press(&mut tui, &mut app, ":undo\n");
+        assert_eq!(app.settings().width, 8);
+    }
+
+    /// Style of the first cell where `text` is drawn.
+    fn style_at(tui: &Tui, app: &App, text: &str) -> Style {
+        let mut terminal = Terminal::new(TestBackend::new(SCREEN.width, SCREEN.height)).unwrap();
+        terminal.draw(|frame| tui.draw(frame, app)).unwrap();
+        terminal.backend().buffer()[position(tui, app, text)].style()
+    }
+
+    #[test]
+    fn test_theme() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, "f0\n:from x\n");
+        assert_eq!(style_at(&tui, &app, "Error").fg, Some(Color::Red));
+        assert_eq!(style_at(&tui, &app, "1111 0000").fg, Some(Color::Cyan));
+
+        let path = std::env::temp_dir().join(format!("base_converter_theme_{}.toml", std::process::id()));
+        std::fs::write(&path, "[theme]\nname = \"monochrome\"\nnibble = \"blue\"\n").unwrap();
+        press(&mut tui, &mut app, &format!(":load {}\n:from x\n", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        let error = style_at(&tui, &app, "Error");
+        assert_eq!((error.fg, error.add_modifier), (Some(Color::Reset), Modifier::BOLD));
+        assert_eq!(style_at(&tui, &app, "1111 0000").fg, Some(Color::Blue));
+    }
+
+    #[test]
+    fn test_help() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        tui.key(KeyEvent::from(KeyCode::F(1)), &mut app);
+        let help = screen(&tui, &app);
+        assert!(help.contains("│Commands:"));
+        assert!(help.contains("Esc/F1: close"));
+
+        press(&mut tui, &mut app, "swap");
+        let help = screen(&tui, &app);
+        assert!(help.contains(" Help: swap_ "));
+        assert!(help.contains("│Functions:"));
+        assert!(help.contains("│  htons(x), ntohs(x)"));
+        assert!(!help.contains("Commands:"));
+        for _ in 0..4 {
+            tui.key(KeyEvent::from(KeyCode::Backspace), &mut app);
+        }
+        press(&mut tui, &mut app, "Toggle");
+        assert!(screen(&tui, &app).contains("│  Space/Enter"));
+        press(&mut tui, &mut app, "zz");
+        assert!(screen(&tui, &app).contains("│Nothing matches togglezz"));
+
+        tui.key(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_eq!((tui.help.is_none(), tui.done), (true, false));
+        assert!(!screen(&tui, &app).contains("Help"));
+    }
+
+    #[test]
+    fn test_preview() {
+        let (mut tui, mut app) = (Tui::default(), app());
+        press(&mut tui, &mut app, "htons(1");
+        assert_eq!(tui.preview.as_ref().unwrap().as_ref().unwrap().value, 0x100);
+        let screen = screen(&tui, &app);
+        assert!(screen.contains("Preview"));
+        assert!(screen.contains("│input  htons(1"));
+        assert!(screen.contains("│dec    256"));
+
+        press(&mut tui, &mut app, "z");
+        assert_eq!(tui.preview, Some(Err("invalid hex digit in htons(1z".to_string())));
+        press(&mut tui, &mut app, "\n");
+        assert_eq!(tui.preview, None);
+        assert!(app.last().is_none());
+        press(&mut tui, &mut app, ":to dec");
+        assert_eq!(tui.preview, None);
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(complete("0x"), "0x0");
+        assert_eq!(complete("1010_"), "1010");
+        assert_eq!(complete("htons(0x"), "htons(0x0)");
+        assert_eq!(complete("rotl(ff, "), "rotl(ff)");
+        assert_eq!(complete("ff"), "ff");
+    }
+}
@@ -0,0 +1,421 @@
+//! Units of the calculators. Byte sizes are written with binary prefixes,
+//! `KiB` for 1024 bytes, or SI prefixes, `kB` for 1000 bytes. The shorthand
+//! of linker scripts and datasheets, `256K` or `1M`, is binary unless the
+//! `kilo` setting is 1000. Durations are written in `ns`, `us`, `ms` or `s`,
+//! or as `1h 2m 3.45s` with days, hours and minutes, and frequencies in `Hz`,
+//! `kHz`, `MHz` or `GHz`.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::IntErrorKind;
+
+/// Letters of the prefixes, from kilo to exa.
+const PREFIXES: &[char] = &['K', 'M', 'G', 'T', 'P', 'E'];
+
+/// Units of durations, in picoseconds.
+pub const TIMES: &[(&str, u64)] = &[
+    ("ps", 1),
+    ("ns", 1_000),
+    ("us", 1_000_000),
+    ("ms", 1_000_000_000),
+    ("s", 1_000_000_000_000),
+];
+
+/// Units of the parts of a long duration, in picoseconds.
+const CLOCK: &[(&str, u128)] = &[
+    ("d", 86_400_000_000_000_000),
+    ("h", 3_600_000_000_000_000),
+    ("m", 60_000_000_000_000),
+    ("s", 1_000_000_000_000),
+];
+
+/// Units of frequencies, in millihertz.
+pub const FREQUENCIES: &[(&str, u64)] = &[
+    ("Hz", 1_000),
+    ("kHz", 1_000_000),
+    ("MHz", 1_000_000_000),
+    ("GHz", 1_000_000_000_000),
+];
+
+/// Value of a prefix, by its letter, as a power of 1024 or 1000.
+fn prefix(letter: char, binary: bool) -> Option<u64> {
+    let exponent = PREFIXES.iter().position(|p| p.eq_ignore_ascii_case(&letter))? as u32 + 1;
+    Some(if binary { 1 << (10 * exponent) } else { 1000u64.pow(exponent) })
+}
+
+/// Rounding of results that are not whole, changed with `:round`. Results
+/// are not negative, so `Floor` and `Truncate` are the same.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Rounding {
+    Nearest,
+    Floor,
+    Ceil,
+    Truncate,
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rounding::Nearest => write!(f, "nearest"),
+            Rounding::Floor => write!(f, "floor"),
+            Rounding::Ceil => write!(f, "ceil"),
+            Rounding::Truncate => write!(f, "truncate"),
+        }
+    }
+}
+
+impl Rounding {
+    /// Read a rounding mode from its name.
+    pub fn from_name(name: &str) -> Option<Rounding> {
+        match name {
+            "nearest" => Some(Rounding::Nearest),
+            "floor" => Some(Rounding::Floor),
+            "ceil" => Some(Rounding::Ceil),
+            "truncate" => Some(Rounding::Truncate),
+            _ => None,
+        }
+    }
+
+    /// Divide `num` by `den`, rounded.
+    pub fn divide(self, num: u128, den: u128) -> u128 {
+        match self {
+            Rounding::Nearest => (num + den / 2) / den,
+            Rounding::Floor | Rounding::Truncate => num / den,
+            Rounding::Ceil => num.div_ceil(den),
+        }
+    }
+}
+
+/// Read a decimal number with an optional fraction, e.g. `1.5`, as a
+/// numerator and a power of 10 denominator, `(15, 10)`.
+pub fn decimal(number: &str) -> Result<(u128, u128), IntErrorKind> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let digits = |text: &str| text.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(IntErrorKind::Empty);
+    }
+    if !digits(whole) || !digits(fraction) || fraction.len() > 18 {
+        return Err(IntErrorKind::InvalidDigit);
+    }
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| IntErrorKind::PosOverflow)? };
+    let denominator = 10u128.pow(fraction.len() as u32);
+    let fraction: u128 = if fraction.is_empty() { 0 } else { fraction.parse().map_err(|_| IntErrorKind::InvalidDigit)? };
+    let numerator = whole.checked_mul(denominator).ok_or(IntErrorKind::PosOverflow)? + fraction;
+    Ok((numerator, denominator))
+}
+
+/// Read a decimal number with an optional fraction, e.g. `1.5`, multiplied
+/// by `scale`. The result must be a whole number.
+pub fn scaled(number: &str, scale: u64) -> Result<u64, IntErrorKind> {
+    let (numerator, denominator) = decimal(number)?;
+    let numerator = numerator.checked_mul(scale as u128).ok_or(IntErrorKind::PosOverflow)?;
+    if !numerator.is_multiple_of(denominator) {
+        return Err(IntErrorKind::InvalidDigit);
+    }
+    u64::try_from(numerator / denominator).map_err(|_| IntErrorKind::PosOverflow)
+}
+
+/// Write `num / den` with up to 3 decimals, e.g. `0.75`. A rounded value
+/// starts with `~`.
+pub fn format_decimal(num: u128, den: u128) -> String {
+    // Only the remainder is scaled, and that of a denominator above 2^118
+    // is shifted down first, inexactly, so that the scaling cannot overflow.
+    let (whole, rem) = (num / den, num % den);
+    let shift = (128 - den.leading_zeros()).saturating_sub(118);
+    let thousandths = ((rem >> shift) * 1000 + (den >> shift) / 2) / (den >> shift);
+    let fraction = format!("{:03}", thousandths % 1000);
+    let fraction = fraction.trim_end_matches('0');
+    let dot = if fraction.is_empty() { "" } else { "." };
+    let exact = rem == 0 || (shift == 0 && (rem * 1000).is_multiple_of(den));
+    let approx = if exact { "" } else { "~" };
+    format!("{}{}{}{}", approx, whole + thousandths / 1000, dot, fraction)
+}
+
+/// Split a quantity into its number and unit, e.g. `1.5 MiB`.
+fn split_unit(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    (text[..split].trim_end(), text[split..].trim_start())
+}
+
+/// Name of a byte unit, e.g. `MiB` or `MB`.
+fn unit_name(letter: char, binary: bool) -> String {
+    match (letter.to_ascii_uppercase(), binary) {
+        (letter, true) => format!("{}iB", letter),
+        ('K', false) => "kB".to_string(),
+        (letter, false) => format!("{}B", letter),
+    }
+}
+
+/// Read a byte size, e.g. `256K`, `1MiB`, `1.5 MiB`, `4kB` or `512B`. A
+/// plain number is a count of bytes.
+pub fn parse_size(text: &str) -> Result<u64, IntErrorKind> {
+    parse_size_as(text, true)
+}
+
+/// Read a byte size like `parse_size`, with the shorthand `4k` or `2M` read
+/// with binary prefixes if `binary`, else with SI ones.
+pub fn parse_size_as(text: &str, binary: bool) -> Result<u64, IntErrorKind> {
+    let (number, unit) = split_unit(text);
+    let scale = match unit.chars().collect::<Vec<char>>()[..] {
+        [] | ['B'] => Some(1),
+        [letter] => prefix(letter, binary),
+        [letter, 'i'] | [letter, 'i', 'B'] => prefix(letter, true),
+        [letter, 'B'] => prefix(letter, false),
+        _ => None,
+    }.ok_or(IntErrorKind::InvalidDigit)?;
+    scaled(number, scale)
+}
+
+/// Read a quantity with one of `units`, e.g. `1.5 ms`, in the smallest unit
+/// of the table. Units are not case sensitive, and `µs` is `us`.
+fn parse_quantity(text: &str, units: &[(&str, u64)]) -> Result<u64, IntErrorKind> {
+    let (number, unit) = split_unit(text);
+    let unit = unit.replace('µ', "u");
+    let (_, scale) = units.iter().find(|(name, _)| name.eq_ignore_ascii_case(&unit)).ok_or(IntErrorKind::InvalidDigit)?;
+    scaled(number, *scale)
+}
+
+/// Read a duration, e.g. `10us`, in picoseconds.
+pub fn parse_time(text: &str) -> Result<u64, IntErrorKind> {
+    parse_quantity(text, TIMES)
+}
+
+/// Read a frequency, e.g. `32.768kHz`, in millihertz.
+pub fn parse_frequency(text: &str) -> Result<u64, IntErrorKind> {
+    parse_quantity(text, FREQUENCIES)
+}
+
+/// Read a duration made of parts like `1h 2m 3.45s` or `1h2m`, with units
+/// `d`, `h`, `m`, `s`, `ms`, `us` and `ns`, in picoseconds.
+pub fn parse_duration(text: &str) -> Result<u128, IntErrorKind> {
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(IntErrorKind::Empty);
+    }
+    let mut total: u128 = 0;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let (number, after) = (&rest[..number_end], rest[number_end..].trim_start());
+        let unit_end = after.find(|c: char| !c.is_alphabetic()).unwrap_or(after.len());
+        let unit = after[..unit_end].replace('µ', "u");
+        let scale = CLOCK.iter().map(|(name, scale)| (*name, *scale))
+            .chain(TIMES.iter().map(|(name, scale)| (*name, *scale as u128)))
+            .find(|(name, _)| *name == unit)
+            .map(|(_, scale)| scale)
+            .ok_or(IntErrorKind::InvalidDigit)?;
+        let (num, den) = decimal(number)?;
+        let ps = num.checked_mul(scale).ok_or(IntErrorKind::PosOverflow)?;
+        if !ps.is_multiple_of(den) {
+            return Err(IntErrorKind::InvalidDigit);
+        }
+        total = total.checked_add(ps / den).ok_or(IntErrorKind::PosOverflow)?;
+        rest = after[unit_end..].trim_start();
+    }
+    Ok(total)
+}
+
+/// Write the duration `num / den` in picoseconds, e.g. `1h 2m 3.45s`, or
+/// `1.5ms` under a minute. A rounded value starts with `~`.
+pub fn format_duration(num: u128, den: u128) -> String {
+    let minute = CLOCK[2].1;
+    if num < minute * den {
+        return format_quantity(num, den, TIMES).replace(' ', "");
+    }
+    let second = CLOCK[3].1 * den;
+    let mut seconds = num / second;
+    let mut parts = Vec::new();
+    for (name, scale) in &CLOCK[..3] {
+        let count = seconds / (scale / CLOCK[3].1);
+        seconds %= scale / CLOCK[3].1;
+        if count > 0 || !parts.is_empty() {
+            parts.push(format!("{}{}", count, name));
+        }
+    }
+    let fraction = num % second;
+    parts.push(format!("{}s", format_decimal(seconds * second + fraction, second)));
+    let approx = if parts.last().is_some_and(|part| part.starts_with('~')) { "~" } else { "" };
+    format!("{}{}", approx, parts.join(" ").replace('~', ""))
+}
+
+/// Write the quantity `num / den`, counted in the smallest unit of `units`,
+/// with the largest unit it reaches and up to 3 decimals, e.g. `1.5 ms`. A
+/// rounded value starts with `~`.
+pub fn format_quantity(num: u128, den: u128, units: &[(&str, u64)]) -> String {
+    let (name, unit) = units.iter().rev()
+        .find(|(_, unit)| num >= *unit as u128 * den)
+        .unwrap_or(&units[0]);
+    format!("{} {}", format_decimal(num, den * *unit as u128), name)
+}
+
+/// Both readings of a size whose prefix could be binary or SI, e.g. `1MB`
+/// or `1M`, the one `parse_size_as` uses first: `1 MB = 1000000 B, 1 MiB =
+/// 1048576 B`. None if the size is not ambiguous.
+pub fn ambiguity(text: &str, shorthand_binary: bool) -> Option<String> {
+    let (number, unit) = split_unit(text);
+    let (letter, binary) = match unit.chars().collect::<Vec<char>>()[..] {
+        [letter] => (letter, shorthand_binary),
+        [letter, 'B'] => (letter, false),
+        _ => return None,
+    };
+    let reading = |binary| {
+        let bytes = scaled(number, prefix(letter, binary)?).ok()?;
+        Some(format!("{} {} = {} B", number, unit_name(letter, binary), bytes))
+    };
+    Some(format!("{}, {}", reading(binary)?, reading(!binary)?))
+}
+
+/// Write a byte size with the largest binary prefix that keeps it exact
+/// within 3 decimals, e.g. `256 KiB` or `1.5 MiB`, else in bytes.
+pub fn format_size(num: u64) -> String {
+    format_prefixed(num, true).unwrap_or_else(|| format!("{} B", num))
+}
+
+/// Write a byte size rounded to 1 decimal with the largest binary prefix it
+/// reaches, e.g. `1.1 GiB`, else in bytes, where `format_size` is too long.
+pub fn format_approximate_size(num: u64) -> String {
+    for letter in PREFIXES.iter().rev() {
+        let unit = prefix(*letter, true).unwrap_or(u64::MAX);
+        if num >= unit {
+            return format!("{:.1} {}", num as f64 / unit as f64, unit_name(*letter, true));
+        }
+    }
+    format!("{} B", num)
+}
+
+/// Write a byte size with the largest SI prefix that keeps it exact within
+/// 1 decimal, e.g. `2 MB` or `1.5 kB`. None if there is no such prefix.
+pub fn format_si_size(num: u64) -> Option<String> {
+    format_prefixed(num, false)
+}
+
+/// Byte size with the largest prefix that keeps it exact, within 3 decimals
+/// for binary prefixes and 1 for SI ones, which would always be exact.
+fn format_prefixed(num: u64, binary: bool) -> Option<String> {
+    let decimals = if binary { 3 } else { 1 };
+    let scale = 10u128.pow(decimals);
+    for letter in PREFIXES.iter().rev() {
+        let unit = prefix(*letter, binary)? as u128;
+        if num as u128 >= unit && (num as u128 * scale).is_multiple_of(unit) {
+            let scaled = num as u128 * scale / unit;
+            let fraction = format!("{:0width$}", scaled % scale, width = decimals as usize);
+            let fraction = fraction.trim_end_matches('0');
+            let dot = if fraction.is_empty() { "" } else { "." };
+            return Some(format!("{}{}{} {}", scaled / scale, dot, fraction, unit_name(*letter, binary)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test_units {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("256K"), Ok(0x40000));
+        assert_eq!(parse_size("1MiB"), Ok(0x100000));
+        assert_eq!(parse_size("1.5 Mi"), Ok(0x180000));
+        assert_eq!(parse_size("4kB"), Ok(4000));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size("100"), Ok(100));
+        assert_eq!(parse_size("16E"), Err(IntErrorKind::PosOverflow));
+        assert_eq!(parse_size("99999999999999999999999999.5K"), Err(IntErrorKind::PosOverflow));
+        assert_eq!(parse_size("1.0001K"), Err(IntErrorKind::InvalidDigit));
+        assert_eq!(parse_size("3X"), Err(IntErrorKind::InvalidDigit));
+        assert_eq!(parse_size("K"), Err(IntErrorKind::Empty));
+        assert_eq!(parse_size_as("4k", false), Ok(4000));
+        assert_eq!(parse_size_as("1G", false), Ok(1_000_000_000));
+        assert_eq!(parse_size_as("1MiB", false), Ok(0x100000));
+        assert_eq!(parse_size_as("4kB", true), Ok(4000));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0x40000), "256 KiB");
+        assert_eq!(format_size(0x180000), "1.5 MiB");
+        assert_eq!(format_size(1 << 60), "1 EiB");
+        assert_eq!(format_size(1000), "1000 B");
+        assert_eq!(format_size(0x40001), "262145 B");
+        assert_eq!(format_size(u64::MAX), "18446744073709551615 B");
+        assert_eq!(parse_size(&format_size(0x180000)), Ok(0x180000));
+        assert_eq!(format_si_size(2_000_000).as_deref(), Some("2 MB"));
+        assert_eq!(format_si_size(1500).as_deref(), Some("1.5 kB"));
+        assert_eq!(format_si_size(0x100000), None);
+        assert_eq!(format_approximate_size(1000), "1000 B");
+        assert_eq!(format_approximate_size(0x40001), "256.0 KiB");
+        assert_eq!(format_approximate_size(0x49000000), "1.1 GiB");
+        assert_eq!(format_approximate_size(u64::MAX), "16.0 EiB");
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!(Rounding::Nearest.divide(7, 2), 4);
+        assert_eq!(Rounding::Floor.divide(7, 2), 3);
+        assert_eq!(Rounding::Ceil.divide(7, 2), 4);
+        assert_eq!(Rounding::Truncate.divide(5, 2), 2);
+        assert_eq!(Rounding::Ceil.divide(6, 2), 3);
+        assert_eq!(Rounding::from_name(&Rounding::Ceil.to_string()), Some(Rounding::Ceil));
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(decimal("1.5"), Ok((15, 10)));
+        assert_eq!(decimal(".25"), Ok((25, 100)));
+        assert_eq!(decimal("7"), Ok((7, 1)));
+        assert_eq!(decimal("1.5%"), Err(IntErrorKind::InvalidDigit));
+        assert_eq!(format_decimal(3, 4), "0.75");
+        assert_eq!(format_decimal(1, 3), "~0.333");
+        assert_eq!(format_decimal(6, 2), "3");
+        assert_eq!(format_decimal(u128::MAX, 1), "340282366920938463463374607431768211455");
+        assert_eq!(format_decimal(u128::MAX, 1000), "340282366920938463463374607431768211.455");
+        assert_eq!(format_decimal(u128::MAX - 1, u128::MAX), "~1");
+        assert_eq!(format_decimal(u128::MAX / 4, u128::MAX), "~0.25");
+        assert_eq!(format_decimal(1999, 1000), "1.999");
+        assert_eq!(format_decimal(19999, 10000), "~2");
+    }
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(parse_time("10us"), Ok(10_000_000));
+        assert_eq!(parse_time("1.5 ms"), Ok(1_500_000_000));
+        assert_eq!(parse_time("2µs"), Ok(2_000_000));
+        assert_eq!(parse_frequency("32.768kHz"), Ok(32_768_000));
+        assert_eq!(parse_frequency("8 MHZ"), Ok(8_000_000_000));
+        assert_eq!(parse_time("10"), Err(IntErrorKind::InvalidDigit));
+        assert_eq!(parse_frequency("1.5x"), Err(IntErrorKind::InvalidDigit));
+
+        assert_eq!(format_quantity(1_500_000_000, 1, TIMES), "1.5 ms");
+        assert_eq!(format_quantity(1_000_000_000_000_000, 3_000_000, FREQUENCIES), "~333.333 kHz");
+        assert_eq!(format_quantity(1, 2, TIMES), "0.5 ps");
+        assert_eq!(format_quantity(0, 1, FREQUENCIES), "0 Hz");
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_duration("1h 2m 3.45s"), Ok(3_723_450_000_000_000));
+        assert_eq!(parse_duration("1h2m"), Ok(3_720_000_000_000_000));
+        assert_eq!(parse_duration("1.5ms"), Ok(1_500_000_000));
+        assert_eq!(parse_duration("2d 10 us"), Ok(172_800_000_010_000_000));
+        assert_eq!(parse_duration("1x"), Err(IntErrorKind::InvalidDigit));
+        assert_eq!(parse_duration("5"), Err(IntErrorKind::InvalidDigit));
+        assert_eq!(parse_duration(""), Err(IntErrorKind::Empty));
+
+        assert_eq!(format_duration(3_723_450_000_000_000, 1), "1h 2m 3.45s");
+        assert_eq!(format_duration(90_061_000_000_000_000, 1), "1d 1h 1m 1s");
+        assert_eq!(format_duration(3_600_000_000_000_000, 1), "1h 0m 0s");
+        assert_eq!(format_duration(1_500_000_000, 1), "1.5ms");
+        assert_eq!(format_duration(60_000_000_000_000_001, 1000), "~1m 0s");
+        assert_eq!(parse_duration(&format_duration(3_723_450_000_000_000, 1)), Ok(3_723_450_000_000_000));
+    }
+
+    #[test]
+    fn test_ambiguity() {
+        assert_eq!(ambiguity("1MB", true).as_deref(), Some("1 MB = 1000000 B, 1 MiB = 1048576 B"));
+        assert_eq!(ambiguity("2k", true).as_deref(), Some("2 KiB = 2048 B, 2 kB = 2000 B"));
+        assert_eq!(ambiguity("2k", false).as_deref(), Some("2 kB = 2000 B, 2 KiB = 2048 B"));
+        assert_eq!(ambiguity("1.5MiB", true), None);
+        assert_eq!(ambiguity("512B", true), None);
+        assert_eq!(ambiguity("1.0001K", true), None);
+    }
+}
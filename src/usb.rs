@@ -0,0 +1,93 @@
+//! Decoders for values found in USB descriptors.
+
+use crate::base::Base;
+use crate::func::ArgParser;
+use crate::plugin::{Plugin, Registry};
+
+const USAGE: &str = "Usage: :usb bcd <value> | :usb len <value> | :usb id <vid> <pid> | :usb id <vid:pid>";
+
+/// Register the `:usb` command.
+pub fn register(registry: &mut Registry) {
+    registry.register(Plugin {
+        name: "usb",
+        help: &[
+            (":usb bcd <value>", "decode a bcdUSB/bcdDevice version"),
+            (":usb len <value>", "split a wTotalLength into descriptor bytes"),
+            (":usb id <vid> <pid>", "print a VID:PID pair"),
+        ],
+        completions: &["bcd", "len", "id"],
+        run: command,
+    });
+}
+
+/// Handle `:usb <bcd|len|id> ...`.
+pub fn command(args: &[&str], parse: ArgParser) -> Result<String, String> {
+    let read = |arg: &str| parse(arg).map_err(|e| e.to_string());
+    match args {
+        ["bcd", value] => bcd(read(value)?),
+        ["len", value] => total_length(read(value)?),
+        ["id", vid, pid] => vid_pid(read(vid)?, read(pid)?),
+        ["id", pair] => match pair.split_once(':') {
+            // `vid:pid` is always hex, with or without `0x`, as lsusb prints it.
+            Some((vid, pid)) => {
+                let hex = |id: &str| Base::Hex.to_num(id).map_err(|e| e.to_string());
+                vid_pid(hex(vid)?, hex(pid)?)
+            }
+            None => {
+                let pair = read(pair)?;
+                vid_pid(pair >> 16, pair & 0xffff)
+            }
+        },
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Decode a `bcdUSB`/`bcdDevice` field, e.g. `0x0210` is version 2.10.
+fn bcd(value: u64) -> Result<String, String> {
+    if value > 0xffff {
+        return Err("BCD version must fit in 16 bits".to_string());
+    }
+    let digits: Vec<u64> = (0..4).rev().map(|i| (value >> (i * 4)) & 0xf).collect();
+    if digits.iter().any(|d| *d > 9) {
+        return Err(format!("0x{:04x} is not a valid BCD value", value));
+    }
+    Ok(format!("version {}.{}{}", digits[0] * 10 + digits[1], digits[2], digits[3]))
+}
+
+/// Show a `wTotalLength` value and its bytes as they appear in a descriptor.
+fn total_length(value: u64) -> Result<String, String> {
+    if value > 0xffff {
+        return Err("wTotalLength must fit in 16 bits".to_string());
+    }
+    Ok(format!("{} bytes, descriptor bytes {:02x} {:02x} (little-endian)", value, value & 0xff, value >> 8))
+}
+
+/// Pretty-print a vendor/product ID pair.
+fn vid_pid(vid: u64, pid: u64) -> Result<String, String> {
+    if vid > 0xffff || pid > 0xffff {
+        return Err("VID and PID must fit in 16 bits".to_string());
+    }
+    Ok(format!("{:04x}:{:04x} (vid {}, pid {})", vid, pid, vid, pid))
+}
+
+#[cfg(test)]
+mod test_usb {
+    use super::*;
+    use crate::testing::run;
+
+    #[test]
+    fn test_usb() {
+        assert_eq!(run(command, "bcd 0210").unwrap(), "version 2.10");
+        assert_eq!(run(command, "bcd 1100").unwrap(), "version 11.00");
+        assert!(run(command, "bcd 021a").is_err());
+        assert_eq!(run(command, "len 0129").unwrap(), "297 bytes, descriptor bytes 29 01 (little-endian)");
+        assert_eq!(run(command, "id 46d c52b").unwrap(), "046d:c52b (vid 1133, pid 50475)");
+        assert_eq!(run(command, "id 046dc52b").unwrap(), "046d:c52b (vid 1133, pid 50475)");
+        assert_eq!(run(command, "id 046d:c52b").unwrap(), "046d:c52b (vid 1133, pid 50475)");
+        assert_eq!(run(command, "id 0x046d:0xC52B").unwrap(), "046d:c52b (vid 1133, pid 50475)");
+        assert_eq!(command(&["id", "046d:c52b"], &|arg| Base::Dec.to_num(arg)).unwrap(), "046d:c52b (vid 1133, pid 50475)");
+        assert_eq!(run(command, "id 046d:xyz").unwrap_err(), "invalid hex digit in xyz");
+        assert!(run(command, "id 10000 1").is_err());
+        assert!(run(command, "foo").is_err());
+    }
+}
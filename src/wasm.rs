@@ -0,0 +1,46 @@
+//! JavaScript bindings of the converter, built with the `wasm` feature:
+//! ```js
+//! const converter = new Converter();
+//! converter.set("from", "dec");
+//! converter.convert("255"); // "1111_1111"
+//! ```
+
+use wasm_bindgen::prelude::*;
+use crate::converter;
+
+/// Converter with its own settings.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Converter(converter::Converter);
+
+#[wasm_bindgen]
+impl Converter {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Change a setting, with the keys and values of `:set`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), JsError> {
+        self.0.settings.set(key, value).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Get a setting as text.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.0.settings.get(key)
+    }
+
+    /// Convert a value or function call to the output base.
+    pub fn convert(&self, input: &str) -> Result<String, JsError> {
+        self.0.convert(input).map(|conversion| conversion.text).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Convert a value and return it in every base as a JSON object, like
+    /// `:format json`.
+    #[wasm_bindgen(js_name = convertJson)]
+    pub fn convert_json(&self, input: &str) -> Result<String, JsError> {
+        self.0.convert(input)
+            .map(|conversion| conversion.to_json(&self.0.settings))
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+}
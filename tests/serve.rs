@@ -0,0 +1,30 @@
+//! `--serve` started as a subprocess, as editors run it.
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_serve_skips_rc() {
+    let home = std::env::temp_dir().join(format!("base_converter_home_{}", std::process::id()));
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join(".baseconvrc"), ":show\n:to dec\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_base_converter"))
+        .arg("--serve")
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", &home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let request = r#"{"jsonrpc": "2.0", "id": 1, "method": "convert", "params": {"input": "ff"}}"#;
+    writeln!(child.stdin.take().unwrap(), "{}", request).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&home).unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "{}", stdout);
+    assert!(lines[0].starts_with(r#"{"id":1,"jsonrpc":"2.0","result":"#), "{}", stdout);
+    assert!(lines[0].contains(r#""output":"1111_1111""#), "{}", stdout);
+}